@@ -0,0 +1,104 @@
+//! Amazon Bedrock request mapping.
+//!
+//! The rest of the app builds OpenAI-style chat completion payloads. Bedrock's
+//! `InvokeModel` API instead takes the model's native body, so for Anthropic
+//! models the payload is translated to the Messages API shape on the way out
+//! and the response is translated back to `choices[0].message.content`.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::http::{self, aws_uri_encode};
+
+/// Messages API version accepted by Anthropic models on Bedrock.
+const ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// Anthropic requires `max_tokens`; used when none is configured.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Default `bedrock-runtime` endpoint for a region.
+pub fn default_api_base(region: &str) -> String {
+    format!("https://bedrock-runtime.{}.amazonaws.com", region)
+}
+
+/// `InvokeModel` URL for a model (model IDs may contain `:`, which must be escaped).
+pub fn invoke_url(base: &str, model: &str) -> String {
+    format!("{}/model/{}/invoke", base.trim_end_matches('/'), aws_uri_encode(model, false))
+}
+
+/// Translate an OpenAI-style chat completion payload into an Anthropic Messages body.
+///
+/// System messages are joined into the top-level `system` prompt. A
+/// `response_format` JSON schema has no Bedrock equivalent, so it becomes an
+/// instruction appended to the system prompt.
+pub fn to_invoke_body(payload: &Value) -> Value {
+    let mut system_parts: Vec<String> = Vec::new();
+    let mut messages: Vec<Value> = Vec::new();
+
+    for message in payload["messages"].as_array().into_iter().flatten() {
+        let role = message["role"].as_str().unwrap_or("user");
+        let content = message["content"].as_str().unwrap_or_default();
+        if role == "system" {
+            system_parts.push(content.to_string());
+        } else {
+            messages.push(json!({ "role": role, "content": content }));
+        }
+    }
+
+    if let Some(schema) = payload.pointer("/response_format/json_schema/schema") {
+        system_parts.push(format!(
+            "Respond with only a JSON object (no code fences or other text) matching this JSON schema:\n{}",
+            schema
+        ));
+    }
+
+    let mut body = json!({
+        "anthropic_version": ANTHROPIC_VERSION,
+        "max_tokens": payload["max_tokens"].as_u64().unwrap_or(u64::from(DEFAULT_MAX_TOKENS)),
+        "messages": messages,
+    });
+    if !system_parts.is_empty() {
+        body["system"] = json!(system_parts.join("\n\n"));
+    }
+    if let Some(temperature) = payload["temperature"].as_f64() {
+        // Anthropic models accept 0.0-1.0
+        body["temperature"] = json!(temperature.clamp(0.0, 1.0));
+    }
+    body
+}
+
+/// Translate an Anthropic Messages response into an OpenAI-style chat completion.
+pub fn to_chat_completion(response: &Value) -> Result<Value> {
+    let blocks = response["content"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Bedrock response missing content"))?;
+    let text: String = blocks
+        .iter()
+        .filter(|b| b["type"] == "text")
+        .filter_map(|b| b["text"].as_str())
+        .collect();
+
+    let finish_reason = match response["stop_reason"].as_str() {
+        Some("max_tokens") => "length",
+        _ => "stop",
+    };
+
+    let mut completion = json!({
+        "choices": [{
+            "message": { "role": "assistant", "content": http::strip_code_fence(&text) },
+            "finish_reason": finish_reason
+        }]
+    });
+    if let Some(usage) = response.get("usage") {
+        completion["usage"] = usage.clone();
+    }
+    Ok(completion)
+}
+
+/// Extract the error message from a Bedrock error body (`{"message": "..."}`).
+pub fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().or_else(|| v["Message"].as_str()).map(|s| s.to_string()))
+        .unwrap_or_else(|| body.to_string())
+}
@@ -0,0 +1,112 @@
+//! Embedded reference text for shell builtins and keywords.
+//!
+//! Builtins like `cd` and keywords like `for` have no man pages of their own,
+//! so explain would otherwise have nothing to ground them with. Entries are
+//! kept short to leave room for real man pages within `max_reference_chars`.
+
+/// Keywords that are followed by another command (e.g. `if grep -q ...`).
+const COMMAND_PREFIX_KEYWORDS: &[&str] = &["if", "elif", "then", "else", "while", "until", "do", "!", "{", "time"];
+
+/// Keywords that close a construct and are not followed by a command.
+const CLOSING_KEYWORDS: &[&str] = &["fi", "done", "esac", "}"];
+
+/// Reference entries: the names that trigger them and their text.
+const REFERENCES: &[(&[&str], &str)] = &[
+    (
+        &["cd"],
+        "cd [-L|-P] [dir]\n\
+         Change the shell working directory to DIR (default $HOME). `cd -` changes to $OLDPWD and \
+         prints it. CDPATH lists directories searched for a relative DIR. -P resolves symbolic links; \
+         -L (default) follows them logically. Updates PWD and OLDPWD.",
+    ),
+    (
+        &["export"],
+        "export [-fn] [name[=value] ...]\n\
+         Mark each NAME for export to the environment of subsequently executed commands, assigning \
+         VALUE first if given. -n removes the export property; -f refers to shell functions (bash). \
+         With no NAMEs, lists exported variables.",
+    ),
+    (
+        &["for"],
+        "for name [in words ...]; do commands; done\n\
+         Expand WORDS (globs, variables, command substitutions) and run COMMANDS once per resulting \
+         word with NAME set to it. Without `in words`, iterates over \"$@\". bash also supports \
+         `for (( init; cond; step )); do ...; done`. `break` and `continue` control the loop.",
+    ),
+    (
+        &["while", "until"],
+        "while list; do commands; done\n\
+         until list; do commands; done\n\
+         Run COMMANDS repeatedly while (until) the last command of LIST exits with status zero. \
+         Common idiom: `while IFS= read -r line; do ...; done < file` processes input line by line.",
+    ),
+    (
+        &["if"],
+        "if list; then commands; [elif list; then commands;] ... [else commands;] fi\n\
+         Run the COMMANDS after `then` for the first LIST that exits with status zero, otherwise the \
+         `else` COMMANDS. LIST is any command; `[ ... ]` (test) and `[[ ... ]]` are common conditions.",
+    ),
+    (
+        &["case"],
+        "case word in [pattern [| pattern] ...) commands ;;] ... esac\n\
+         Match WORD against each glob PATTERN in order and run the COMMANDS of the first match. \
+         `*)` is a catch-all. bash also supports `;&` (fall through) and `;;&` (test the next pattern).",
+    ),
+    (
+        &["[["],
+        "[[ expression ]]\n\
+         bash/zsh conditional; exits 0 if EXPRESSION is true. No word splitting or globbing inside. \
+         File tests: -e -f -d -r -w -x -s. Strings: -z -n == != < >; the right side of == and != is a \
+         glob pattern; =~ matches an extended regex (captures in BASH_REMATCH). Integers: -eq -ne -lt \
+         -le -gt -ge. Combine with && || ! and parentheses.",
+    ),
+    (
+        &["source", "."],
+        "source filename [arguments]\n\
+         . filename [arguments]\n\
+         Read and execute commands from FILENAME in the current shell, so variables and functions it \
+         defines persist. Searches PATH when FILENAME contains no slash.",
+    ),
+    (
+        &["read"],
+        "read [-rs] [-p prompt] [-a array] [-d delim] [-t timeout] [-n nchars] [name ...]\n\
+         Read a line from standard input and split it on IFS into the NAMEs (the last NAME gets the \
+         rest; REPLY if none). -r keeps backslashes literal; -p shows a prompt; -s hides input; \
+         -t times out; -a reads words into an array. Exits non-zero at end of file.",
+    ),
+    (
+        &["set"],
+        "set [-eux] [-o option] [--] [args ...]\n\
+         Set shell options or positional parameters. -e exits when a command fails; -u errors on unset \
+         variables; -x traces commands; -o pipefail fails a pipeline if any part fails; `set -- args` \
+         replaces $1, $2, .... Use + instead of - to turn an option off.",
+    ),
+    (
+        &["local"],
+        "local [option] name[=value] ...\n\
+         Create variables visible only within the current function and the functions it calls.",
+    ),
+    (
+        &["unset"],
+        "unset [-fv] name ...\n\
+         Remove each variable NAME (or, with -f, each function NAME).",
+    ),
+];
+
+/// Embedded reference text for a builtin or keyword, if there is one.
+pub fn reference(name: &str) -> Option<&'static str> {
+    REFERENCES
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .map(|(_, text)| *text)
+}
+
+/// Whether `word` is a keyword followed by a command rather than arguments.
+pub fn is_command_prefix(word: &str) -> bool {
+    COMMAND_PREFIX_KEYWORDS.contains(&word)
+}
+
+/// Whether `word` is a keyword that closes a construct.
+pub fn is_closing_keyword(word: &str) -> bool {
+    CLOSING_KEYWORDS.contains(&word)
+}
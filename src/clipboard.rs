@@ -0,0 +1,185 @@
+//! Clipboard backends with fallbacks.
+//!
+//! `arboard` covers desktop sessions, but fails on headless Wayland, over SSH,
+//! and in some tmux setups. Copying falls back to platform tools (`wl-copy`,
+//! `xclip`, `pbcopy`, `clip.exe`) and finally to an OSC 52 escape sequence,
+//! which asks the terminal emulator itself to set the clipboard. Set
+//! `SHAI_CLIPBOARD` to force a single backend.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+use crate::config::env;
+use crate::explain;
+
+/// A way of putting text on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+pub enum Backend {
+    Arboard,
+    #[strum(serialize = "wl-copy")]
+    WlCopy,
+    Xclip,
+    Pbcopy,
+    #[strum(serialize = "clip.exe", serialize = "clip")]
+    ClipExe,
+    Osc52,
+}
+
+impl Backend {
+    /// Backends to try, in order, when none is forced.
+    ///
+    /// External tools are only tried when they apply to the current session.
+    fn auto_chain() -> Vec<Backend> {
+        let has_env = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+
+        let mut chain = vec![Backend::Arboard];
+        if has_env("WAYLAND_DISPLAY") {
+            chain.push(Backend::WlCopy);
+        }
+        if has_env("DISPLAY") {
+            chain.push(Backend::Xclip);
+        }
+        if cfg!(target_os = "macos") {
+            chain.push(Backend::Pbcopy);
+        }
+        // Windows, or WSL with Windows interop
+        if cfg!(windows) || explain::find_in_path("clip.exe").is_some() {
+            chain.push(Backend::ClipExe);
+        }
+        chain.push(Backend::Osc52);
+        chain
+    }
+
+    fn copy(self, text: &str) -> Result<(), String> {
+        match self {
+            Backend::Arboard => arboard::Clipboard::new()
+                .and_then(|mut cb| cb.set_text(text))
+                .map_err(|e| e.to_string()),
+            Backend::WlCopy => pipe_to("wl-copy", &[], text),
+            Backend::Xclip => pipe_to("xclip", &["-selection", "clipboard"], text),
+            Backend::Pbcopy => pipe_to("pbcopy", &[], text),
+            Backend::ClipExe => pipe_to("clip.exe", &[], text),
+            Backend::Osc52 => osc52(text),
+        }
+    }
+}
+
+/// Run a clipboard tool with the text on stdin.
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("{}: {}", program, e))?;
+    }
+
+    let status = child.wait().map_err(|e| format!("{}: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} exited with {}", program, status))
+    }
+}
+
+/// Ask the terminal to set the clipboard with an OSC 52 escape sequence.
+///
+/// Works over SSH when the local terminal supports it. Inside tmux, the
+/// sequence is wrapped in a DCS passthrough so it reaches the outer terminal.
+fn osc52(text: &str) -> Result<(), String> {
+    let mut stderr = std::io::stderr();
+    if !stderr.is_terminal() {
+        return Err("stderr is not a terminal".to_string());
+    }
+
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+
+    stderr
+        .write_all(sequence.as_bytes())
+        .and_then(|_| stderr.flush())
+        .map_err(|e| e.to_string())
+}
+
+/// Standard base64 encoding with padding.
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Copy text to the clipboard, returning the backend that succeeded.
+///
+/// Honors `SHAI_CLIPBOARD` to force a backend; otherwise tries each
+/// applicable backend in turn. On failure, returns every backend's error.
+pub fn copy(text: &str) -> Result<Backend, String> {
+    let chain = match std::env::var(env::SHAI_CLIPBOARD) {
+        Ok(forced) if !forced.trim().is_empty() => match forced.trim().to_lowercase().parse() {
+            Ok(backend) => vec![backend],
+            Err(_) => {
+                let valid: Vec<String> = Backend::iter().map(|b| b.to_string()).collect();
+                return Err(format!(
+                    "invalid {}={} (valid: {})",
+                    env::SHAI_CLIPBOARD,
+                    forced,
+                    valid.join(", ")
+                ));
+            }
+        },
+        _ => Backend::auto_chain(),
+    };
+
+    let mut errors = Vec::new();
+    for backend in chain {
+        match backend.copy(text) {
+            Ok(()) => return Ok(backend),
+            Err(e) => {
+                log::debug!("Clipboard backend {} failed: {}", backend, e);
+                errors.push(format!("{}: {}", backend, e));
+            }
+        }
+    }
+    Err(errors.join("; "))
+}
+
+/// Read text from the clipboard.
+///
+/// Only `arboard` can read the clipboard; the other backends are write-only.
+/// Empty or non-text clipboard contents are an error.
+pub fn paste() -> Result<String, String> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut cb| cb.get_text())
+        .map_err(|e| match e {
+            arboard::Error::ContentNotAvailable => "the clipboard is empty or doesn't hold text".to_string(),
+            e => e.to_string(),
+        })?;
+    if text.trim().is_empty() {
+        return Err("the clipboard is empty".to_string());
+    }
+    Ok(text)
+}
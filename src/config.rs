@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 use colored::Colorize;
+
+use crate::http;
+use crate::output::{out, outln};
+use crate::shell::ShellType;
 use serde::{Deserialize, Deserializer, Serialize};
 use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
@@ -45,6 +49,7 @@ where
 pub enum ConfigSource {
     Default,
     TomlFile,
+    ProjectFile,
     JsonFile,
     Environment,
     Cli,
@@ -55,6 +60,7 @@ impl fmt::Display for ConfigSource {
         match self {
             ConfigSource::Default => write!(f, "default"),
             ConfigSource::TomlFile => write!(f, "toml"),
+            ConfigSource::ProjectFile => write!(f, "project"),
             ConfigSource::JsonFile => write!(f, "json"),
             ConfigSource::Environment => write!(f, "env"),
             ConfigSource::Cli => write!(f, "cli"),
@@ -99,6 +105,134 @@ pub enum OutputFormat {
     #[default]
     Human,
     Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Whether this format emits structured (machine-readable) output.
+    pub fn is_structured(self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Yaml)
+    }
+
+    /// Serialize a value in this format and print it to stdout.
+    ///
+    /// YAML output uses YAML; every other format falls back to pretty-printed JSON.
+    pub fn print_serialized<T: Serialize + ?Sized>(self, value: &T) -> anyhow::Result<()> {
+        match self {
+            OutputFormat::Yaml => out!("{}", serde_yaml::to_string(value)?),
+            OutputFormat::Human | OutputFormat::Json => {
+                outln!("{}", serde_json::to_string_pretty(value)?)
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reference source for explain documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExplainReference {
+    /// Man pages (falling back to `--help` output).
+    #[default]
+    Man,
+    /// tldr pages, falling back to man pages when unavailable.
+    Tldr,
+    /// Both tldr and man pages.
+    Both,
+}
+
+/// How structured-output requests constrain the model's JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JsonSchemaMode {
+    /// `response_format` JSON schema with `strict: true` and `additionalProperties: false`.
+    #[default]
+    Strict,
+    /// `response_format` JSON schema without `strict` or `additionalProperties`.
+    Lenient,
+    /// No `response_format`; the schema is described in the system prompt instead.
+    None,
+}
+
+/// How much reasoning OpenAI reasoning models (gpt-5, o-series) do before answering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+/// Key bindings for text input prompts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EditMode {
+    /// Readline-style bindings (Ctrl+A, Ctrl+W, ...).
+    #[default]
+    Emacs,
+    /// Vim-style modal editing, starting in insert mode.
+    Vi,
+}
+
+/// Which output streams of an executed command feed the context mode buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CtxCapture {
+    Stdout,
+    Stderr,
+    /// Standard output followed by standard error, with a delimiter between.
+    #[default]
+    Both,
+}
+
+/// An entry in the action menu shown after picking a suggestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+pub enum MenuAction {
+    Copy,
+    Explain,
+    Execute,
+    Revise,
+    Refine,
+}
+
+/// Default `menu_actions`: every action, in the built-in order.
+pub const DEFAULT_MENU_ACTIONS: &str = "copy,explain,execute,revise,refine";
+
+/// Rough number of characters per token, for sizing prompt text in tokens.
+pub const CHARS_PER_TOKEN: u32 = 4;
+
+/// Token budget for context mode output when `max_tokens` isn't configured.
+const DEFAULT_CTX_MAX_TOKENS: u32 = 25_000;
+
+/// Smallest allowed `ctx_max_chars`.
+const MIN_CTX_MAX_CHARS: u32 = 100;
+
+/// Largest allowed `ctx_max_chars`: the configured `max_tokens` (or
+/// [`DEFAULT_CTX_MAX_TOKENS`]) converted to characters.
+fn ctx_max_chars_limit(max_tokens: Option<u32>) -> u32 {
+    max_tokens
+        .unwrap_or(DEFAULT_CTX_MAX_TOKENS)
+        .saturating_mul(CHARS_PER_TOKEN)
+        .max(MIN_CTX_MAX_CHARS)
+}
+
+/// How requests to Azure OpenAI are authenticated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AzureAuth {
+    /// Static resource key in the `api-key` header.
+    #[default]
+    Key,
+    /// Entra ID (Azure AD) access token as a bearer `Authorization` header.
+    Token,
 }
 
 /// Supported providers.
@@ -116,10 +250,21 @@ pub enum Provider {
     Ollama,
     #[serde(alias = "mistral")]
     Mistral,
+    #[serde(alias = "bedrock")]
+    Bedrock,
+    #[serde(alias = "perplexity")]
+    Perplexity,
+    #[serde(alias = "deepseek")]
+    DeepSeek,
+    #[serde(alias = "local")]
+    Local,
+    /// Canned answers from keyword rules, with no network access (demos and tests).
+    #[serde(alias = "mock")]
+    Mock,
 }
 
-/// Debug/logging level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, EnumIter, Deserialize, Serialize, clap::ValueEnum)]
+/// Debug/logging level, ordered from least to most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumString, EnumIter, Deserialize, Serialize, clap::ValueEnum)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 #[clap(rename_all = "lowercase")]
@@ -147,6 +292,16 @@ impl DebugLevel {
             DebugLevel::Trace => log::LevelFilter::Trace,
         }
     }
+    /// The level for `-v` repeated `count` times: `-v` is Info, `-vv` Debug,
+    /// and `-vvv` or more Trace.
+    pub fn from_verbosity(count: u8) -> Option<Self> {
+        match count {
+            0 => None,
+            1 => Some(DebugLevel::Info),
+            2 => Some(DebugLevel::Debug),
+            _ => Some(DebugLevel::Trace),
+        }
+    }
 }
 
 // ============================================================================
@@ -161,13 +316,48 @@ pub mod env {
     pub const SHAI_MODEL: &str = "SHAI_MODEL";
     pub const SHAI_TEMPERATURE: &str = "SHAI_TEMPERATURE";
     pub const SHAI_SUGGESTION_COUNT: &str = "SHAI_SUGGESTION_COUNT";
+    pub const SHAI_FALLBACK_MODEL: &str = "SHAI_FALLBACK_MODEL";
+    pub const SHAI_SUGGEST_CONCURRENCY: &str = "SHAI_SUGGEST_CONCURRENCY";
+    pub const SHAI_SUGGEST_PROMPT: &str = "SHAI_SUGGEST_PROMPT";
+    pub const SHAI_SUGGEST_PROMPT_FILE: &str = "SHAI_SUGGEST_PROMPT_FILE";
+    pub const SHAI_SHELL: &str = "SHAI_SHELL";
+    pub const SHAI_EXEC_SHELL: &str = "SHAI_EXEC_SHELL";
+    pub const SHAI_AUTO_COPY: &str = "SHAI_AUTO_COPY";
+    pub const SHAI_CONFIRM_BEFORE_EXECUTE: &str = "SHAI_CONFIRM_BEFORE_EXECUTE";
+    pub const SHAI_ALLOW_EXECUTE: &str = "SHAI_ALLOW_EXECUTE";
+    pub const SHAI_CTX_MAX_CHARS: &str = "SHAI_CTX_MAX_CHARS";
+    pub const SHAI_CTX_CAPTURE: &str = "SHAI_CTX_CAPTURE";
+    pub const SHAI_MENU_ACTIONS: &str = "SHAI_MENU_ACTIONS";
     pub const SHAI_SKIP_CONFIRM: &str = "SHAI_SKIP_CONFIRM"; // Legacy, implies noninteractive
     pub const SHAI_FRONTEND: &str = "SHAI_FRONTEND";
     pub const SHAI_OUTPUT_FORMAT: &str = "SHAI_OUTPUT_FORMAT";
+    pub const SHAI_EDIT_MODE: &str = "SHAI_EDIT_MODE";
     pub const SHAI_MAX_REFERENCE_CHARS: &str = "SHAI_MAX_REFERENCE_CHARS";
     pub const SHAI_MAX_TOKENS: &str = "SHAI_MAX_TOKENS";
+    pub const SHAI_REQUEST_TIMEOUT: &str = "SHAI_REQUEST_TIMEOUT";
+    pub const SHAI_MAX_RETRIES: &str = "SHAI_MAX_RETRIES";
+    pub const SHAI_INITIAL_BACKOFF_MS: &str = "SHAI_INITIAL_BACKOFF_MS";
+    pub const SHAI_JSON_SCHEMA_MODE: &str = "SHAI_JSON_SCHEMA_MODE";
+    pub const SHAI_OFFLINE: &str = "SHAI_OFFLINE";
+    pub const SHAI_REASONING_EFFORT: &str = "SHAI_REASONING_EFFORT";
+    pub const SHAI_SHOW_USAGE: &str = "SHAI_SHOW_USAGE";
+    pub const SHAI_SHOW_TIMING: &str = "SHAI_SHOW_TIMING";
     pub const SHAI_DEBUG: &str = "SHAI_DEBUG";
+    pub const SHAI_LOG_FILE: &str = "SHAI_LOG_FILE";
     pub const SHAI_LOCALE: &str = "SHAI_LOCALE";
+    pub const SHAI_NO_HISTORY: &str = "SHAI_NO_HISTORY";
+    pub const SHAI_NO_PROJECT_CONFIG: &str = "SHAI_NO_PROJECT_CONFIG";
+    pub const SHAI_TRUST_PROJECT_CONFIG: &str = "SHAI_TRUST_PROJECT_CONFIG";
+    pub const SHAI_CONFIG_DIR: &str = "SHAI_CONFIG_DIR";
+    pub const SHAI_NO_PROGRESS: &str = "SHAI_NO_PROGRESS"; // Inverse of `progress`
+    pub const SHAI_SPINNER_FRAMES: &str = "SHAI_SPINNER_FRAMES";
+    pub const SHAI_SPINNER_TEMPLATE: &str = "SHAI_SPINNER_TEMPLATE";
+    pub const SHAI_EXPLAIN_REFERENCE: &str = "SHAI_EXPLAIN_REFERENCE";
+    pub const SHAI_CITATION_MIN_CONFIDENCE: &str = "SHAI_CITATION_MIN_CONFIDENCE";
+    pub const SHAI_REFERENCE_TIMEOUT: &str = "SHAI_REFERENCE_TIMEOUT";
+    pub const SHAI_CLIPBOARD: &str = "SHAI_CLIPBOARD";
+    pub const SHAI_RECORD_DIR: &str = "SHAI_RECORD_DIR";
+    pub const SHAI_REPLAY_DIR: &str = "SHAI_REPLAY_DIR";
 
     // OpenAI provider
     pub const OPENAI_API_KEY: &str = "OPENAI_API_KEY";
@@ -187,17 +377,46 @@ pub mod env {
     pub const AZURE_API_BASE: &str = "AZURE_API_BASE";
     pub const AZURE_DEPLOYMENT_NAME: &str = "AZURE_DEPLOYMENT_NAME";
     pub const AZURE_MAX_TOKENS: &str = "AZURE_MAX_TOKENS";
+    pub const AZURE_AUTH: &str = "AZURE_AUTH";
+    pub const AZURE_OPENAI_AD_TOKEN: &str = "AZURE_OPENAI_AD_TOKEN";
 
     // Ollama provider
     pub const OLLAMA_API_BASE: &str = "OLLAMA_API_BASE";
     pub const OLLAMA_MODEL: &str = "OLLAMA_MODEL";
     pub const OLLAMA_MAX_TOKENS: &str = "OLLAMA_MAX_TOKENS";
+    pub const SHAI_OLLAMA_AUTOPULL: &str = "SHAI_OLLAMA_AUTOPULL";
 
     // Mistral provider
     pub const MISTRAL_API_KEY: &str = "MISTRAL_API_KEY";
     pub const MISTRAL_API_BASE: &str = "MISTRAL_API_BASE";
     pub const MISTRAL_MODEL: &str = "MISTRAL_MODEL";
     pub const MISTRAL_MAX_TOKENS: &str = "MISTRAL_MAX_TOKENS";
+
+    // Amazon Bedrock provider
+    pub const BEDROCK_API_BASE: &str = "BEDROCK_API_BASE";
+    pub const BEDROCK_MODEL: &str = "BEDROCK_MODEL";
+    pub const BEDROCK_MAX_TOKENS: &str = "BEDROCK_MAX_TOKENS";
+    pub const AWS_REGION: &str = "AWS_REGION";
+    pub const AWS_ACCESS_KEY_ID: &str = "AWS_ACCESS_KEY_ID";
+    pub const AWS_SECRET_ACCESS_KEY: &str = "AWS_SECRET_ACCESS_KEY";
+    pub const AWS_SESSION_TOKEN: &str = "AWS_SESSION_TOKEN";
+
+    // Perplexity provider
+    pub const PERPLEXITY_API_KEY: &str = "PERPLEXITY_API_KEY";
+    pub const PERPLEXITY_API_BASE: &str = "PERPLEXITY_API_BASE";
+    pub const PERPLEXITY_MODEL: &str = "PERPLEXITY_MODEL";
+    pub const PERPLEXITY_MAX_TOKENS: &str = "PERPLEXITY_MAX_TOKENS";
+
+    // DeepSeek provider
+    pub const DEEPSEEK_API_KEY: &str = "DEEPSEEK_API_KEY";
+    pub const DEEPSEEK_API_BASE: &str = "DEEPSEEK_API_BASE";
+    pub const DEEPSEEK_MODEL: &str = "DEEPSEEK_MODEL";
+    pub const DEEPSEEK_MAX_TOKENS: &str = "DEEPSEEK_MAX_TOKENS";
+
+    // Local OpenAI-compatible server (llama.cpp, LM Studio)
+    pub const LOCAL_API_BASE: &str = "LOCAL_API_BASE";
+    pub const LOCAL_MODEL: &str = "LOCAL_MODEL";
+    pub const LOCAL_MAX_TOKENS: &str = "LOCAL_MAX_TOKENS";
 }
 
 // ============================================================================
@@ -308,6 +527,12 @@ impl FieldMeta {
         self
     }
 
+    /// Mark this field as sensitive (will be masked in output).
+    pub const fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
+
     /// Get the default value as a serde_json::Value.
     pub fn default_json_value(&self) -> Option<serde_json::Value> {
         self.default.map(|s| {
@@ -433,6 +658,8 @@ pub const COMMON_PROVIDER_FIELDS: &[CommonFieldMeta] = &[
     CommonFieldMeta::new("api_base", "API base URL"),
     CommonFieldMeta::new("model", "Model to use"),
     CommonFieldMeta::new("max_tokens", "Max tokens for AI completion"),
+    CommonFieldMeta::new("temperature", "Sampling temperature for this provider (used unless temperature is set globally)"),
+    CommonFieldMeta::new("chat_path", "Chat completions path relative to api_base, replacing the default /v1/chat/completions (for custom gateways)"),
 ];
 
 /// Global settings metadata.
@@ -446,10 +673,53 @@ pub const GLOBAL_SETTINGS_METADATA: &[FieldMeta] = &[
     FieldMeta::new("temperature", "Sampling temperature (0.0 = deterministic, 1.0 = creative)")
         .env(env::SHAI_TEMPERATURE)
         .default("0.05"),
-    FieldMeta::new("suggestion_count", "Number of suggestions to generate")
+    FieldMeta::new("suggestion_count", "Number of suggestions to generate (1-10; larger values are capped at 10)")
         .env(env::SHAI_SUGGESTION_COUNT)
         .default("3")
         .section(Section::Suggest),
+    FieldMeta::new("fallback_model", "Model on the same provider to retry once with when every suggestion attempt returns output that can't be parsed (not used by Azure, which picks the model by deployment)")
+        .env(env::SHAI_FALLBACK_MODEL)
+        .section(Section::Suggest),
+    FieldMeta::new("suggest_concurrency", "Max suggestion requests in flight at once (clamped to 1..suggestion_count); lower it if the provider rate-limits, since each retried request waits out its own backoff")
+        .env(env::SHAI_SUGGEST_CONCURRENCY)
+        .default("4")
+        .section(Section::Suggest),
+    FieldMeta::new("suggest_system_prompt", "Extra instructions appended last to the suggest system prompt (e.g., \"prefer rg over grep\"); takes precedence over suggest_prompt_file")
+        .env(env::SHAI_SUGGEST_PROMPT)
+        .section(Section::Suggest),
+    FieldMeta::new("suggest_prompt_file", "File whose contents are appended last to the suggest system prompt")
+        .env(env::SHAI_SUGGEST_PROMPT_FILE)
+        .section(Section::Suggest),
+    FieldMeta::new("shell", "Shell whose syntax suggestions should use: bash, zsh, fish, powershell, nu, elvish, or xonsh (auto-detected from $SHELL by default)")
+        .env(env::SHAI_SHELL)
+        .section(Section::Suggest),
+    FieldMeta::new("exec_shell", "Shell that executed commands run in: bash, zsh, fish, powershell, nu, elvish, or xonsh (defaults to shell; sh or cmd if neither is known)")
+        .env(env::SHAI_EXEC_SHELL)
+        .section(Section::Suggest),
+    FieldMeta::new("auto_copy", "In noninteractive mode, also copy the emitted command to the clipboard (stdout is unchanged)")
+        .env(env::SHAI_AUTO_COPY)
+        .default("false")
+        .section(Section::Suggest),
+    FieldMeta::new("allow_execute", "Allow running commands from shell-ai (the Execute action, plan execution, and context mode); when false, execution is refused, not just hidden from the menus")
+        .env(env::SHAI_ALLOW_EXECUTE)
+        .default("true")
+        .section(Section::Suggest),
+    FieldMeta::new("ctx_max_chars", "In context mode (--ctx), how many trailing characters of the previous command's output to send (at least 100, and at most 4 characters per token of max_tokens, or 100000 without it)")
+        .env(env::SHAI_CTX_MAX_CHARS)
+        .default("1500")
+        .section(Section::Suggest),
+    FieldMeta::new("ctx_capture", "In context mode (--ctx), which output of the previous command to send: stdout, stderr, or both")
+        .env(env::SHAI_CTX_CAPTURE)
+        .default("both")
+        .section(Section::Suggest),
+    FieldMeta::new("menu_actions", "Comma-separated actions offered after picking a suggestion, in menu order: copy, explain, execute, revise, refine (execute is left out when allow_execute is false)")
+        .env(env::SHAI_MENU_ACTIONS)
+        .default(DEFAULT_MENU_ACTIONS)
+        .section(Section::Suggest),
+    FieldMeta::new("confirm_before_execute", "Before executing a command, show it pre-filled for a final edit: Enter runs it, Escape cancels")
+        .env(env::SHAI_CONFIRM_BEFORE_EXECUTE)
+        .default("false")
+        .section(Section::Suggest),
     FieldMeta::new("skip_confirm", "Legacy: skip confirmation (implies frontend=noninteractive)")
         .env(env::SHAI_SKIP_CONFIRM)
         .default("false")
@@ -460,19 +730,74 @@ pub const GLOBAL_SETTINGS_METADATA: &[FieldMeta] = &[
         .env(env::SHAI_FRONTEND)
         .default("automatic")
         .section(Section::Ui),
-    FieldMeta::new("output_format", "Output format: human or json")
+    FieldMeta::new("output_format", "Output format: human, json, or yaml")
         .env(env::SHAI_OUTPUT_FORMAT)
         .default("human")
         .section(Section::Ui),
+    FieldMeta::new("edit_mode", "Key bindings for text prompts (new prompt, revise): emacs (readline-style) or vi (modal, starts in insert mode)")
+        .env(env::SHAI_EDIT_MODE)
+        .default("emacs")
+        .section(Section::Ui),
+    FieldMeta::new("progress", "Show the animated progress spinner on a terminal (set SHAI_NO_PROGRESS=true to disable)")
+        .default("true")
+        .section(Section::Ui),
+    FieldMeta::new("spinner_frames", "Comma-separated animation frames for the progress spinner")
+        .env(env::SHAI_SPINNER_FRAMES)
+        .default(crate::progress::DEFAULT_FRAMES)
+        .section(Section::Ui),
+    FieldMeta::new("spinner_template", "Layout of the progress line, as an indicatif template: {spinner}, {msg}, and {elapsed} (or {elapsed_precise}), with optional styles like {elapsed:.dim}; falls back to the default if invalid")
+        .env(env::SHAI_SPINNER_TEMPLATE)
+        .default(crate::progress::DEFAULT_TEMPLATE)
+        .section(Section::Ui),
+    FieldMeta::new("show_usage", "After each suggest, explain, or refine request, print the provider-reported token counts to stderr")
+        .env(env::SHAI_SHOW_USAGE)
+        .default("false")
+        .section(Section::Ui),
+    FieldMeta::new("show_timing", "After each API request, print its wall-clock time, time to the response headers, and retry count to stderr")
+        .env(env::SHAI_SHOW_TIMING)
+        .default("false")
+        .section(Section::Ui),
     FieldMeta::new("max_reference_chars", "Max characters for man page references in explain")
         .env(env::SHAI_MAX_REFERENCE_CHARS)
         .default("262144")
         .section(Section::Explain),
+    FieldMeta::new("explain_reference", "Documentation source for explain: man, tldr (compact, falls back to man), or both")
+        .env(env::SHAI_EXPLAIN_REFERENCE)
+        .default("man")
+        .section(Section::Explain),
+    FieldMeta::new("reference_timeout", "Seconds to wait for each man, --help, or tldr lookup in explain before dropping that reference (1-60)")
+        .env(env::SHAI_REFERENCE_TIMEOUT)
+        .default("3")
+        .section(Section::Explain),
+    FieldMeta::new("citation_min_confidence", "Drop explain citations whose confidence is below this threshold (0.0-1.0, 0 = keep all)")
+        .env(env::SHAI_CITATION_MIN_CONFIDENCE)
+        .default("0.3")
+        .section(Section::Explain),
     FieldMeta::new("max_tokens", "Max tokens for an AI completion (optional, API auto-calculates when omitted)")
         .env(env::SHAI_MAX_TOKENS),
+    FieldMeta::new("request_timeout", "API request timeout in seconds (0-3600, 0 = no timeout)")
+        .env(env::SHAI_REQUEST_TIMEOUT)
+        .default("60"),
+    FieldMeta::new("max_retries", "Retries for rate-limited, server-error, or network-failed API requests (0-10)")
+        .env(env::SHAI_MAX_RETRIES)
+        .default("3"),
+    FieldMeta::new("initial_backoff_ms", "Delay before the first retry in milliseconds, doubled on each retry (0-60000)")
+        .env(env::SHAI_INITIAL_BACKOFF_MS)
+        .default("1000"),
+    FieldMeta::new("json_schema_mode", "How responses are constrained to JSON: strict, lenient (no strict/additionalProperties), or none (schema in the prompt only); downgraded automatically if the provider rejects the schema")
+        .env(env::SHAI_JSON_SCHEMA_MODE)
+        .default("strict"),
+    FieldMeta::new("reasoning_effort", "Reasoning effort for OpenAI and Azure reasoning models: minimal, low, medium, or high (temperature is not sent when set)")
+        .env(env::SHAI_REASONING_EFFORT),
+    FieldMeta::new("offline", "Refuse to send requests anywhere but localhost: providers whose api_base isn't a loopback address (and Ollama cloud models) are rejected")
+        .env(env::SHAI_OFFLINE)
+        .default("false"),
     FieldMeta::new("debug", "Debug log level")
         .env(env::SHAI_DEBUG)
         .section(Section::Ui),
+    FieldMeta::new("log_file", "Also append log records to this file as newline-delimited JSON (respects the debug level)")
+        .env(env::SHAI_LOG_FILE)
+        .section(Section::Ui),
     FieldMeta::new("locale", "Language/locale for AI responses (auto-detected from LANG/LC_ALL by default, set explicitly to override, or empty string to disable)")
         .env(env::SHAI_LOCALE)
         .section(Section::Ui)
@@ -516,7 +841,7 @@ pub const PROVIDER_METADATA: &[ProviderMeta] = &[
         display_name: "Azure OpenAI",
         description: "Azure OpenAI Service",
         field_overrides: &[
-            FieldOverride { name: "api_key", env_var: Some(env::AZURE_API_KEY), default: None, required: None },
+            FieldOverride { name: "api_key", env_var: Some(env::AZURE_API_KEY), default: None, required: Some(false) }, // Required only when auth = key
             FieldOverride { name: "api_base", env_var: Some(env::AZURE_API_BASE), default: None, required: Some(true) },
             FieldOverride { name: "model", env_var: None, default: None, required: None },
             FieldOverride { name: "max_tokens", env_var: Some(env::AZURE_MAX_TOKENS), default: None, required: None },
@@ -530,8 +855,12 @@ pub const PROVIDER_METADATA: &[ProviderMeta] = &[
                 .env(env::OPENAI_API_VERSION)
                 .section(Section::ProviderSpecific)
                 .default("2023-05-15"),
+            FieldMeta::new("auth", "Authentication mode: key (api-key header) or token (Entra ID bearer token from AZURE_OPENAI_AD_TOKEN or `az account get-access-token`)")
+                .env(env::AZURE_AUTH)
+                .section(Section::ProviderSpecific)
+                .default("key"),
         ],
-        skip_common: &["model"], // Azure uses deployment_name instead of model
+        skip_common: &["model", "chat_path"], // Azure uses deployment_name instead of model, in its own URL layout
     },
     ProviderMeta {
         name: "ollama",
@@ -559,6 +888,87 @@ pub const PROVIDER_METADATA: &[ProviderMeta] = &[
         extra_fields: &[],
         skip_common: &[],
     },
+    ProviderMeta {
+        name: "bedrock",
+        display_name: "Amazon Bedrock",
+        description: "Anthropic models on Amazon Bedrock (AWS SigV4 authentication)",
+        field_overrides: &[
+            FieldOverride { name: "api_key", env_var: None, default: None, required: None },
+            FieldOverride { name: "api_base", env_var: Some(env::BEDROCK_API_BASE), default: None, required: None },
+            FieldOverride { name: "model", env_var: Some(env::BEDROCK_MODEL), default: Some("anthropic.claude-3-5-sonnet-20240620-v1:0"), required: None },
+            FieldOverride { name: "max_tokens", env_var: Some(env::BEDROCK_MAX_TOKENS), default: None, required: None },
+        ],
+        extra_fields: &[
+            FieldMeta::new("region", "AWS region hosting the model (e.g., us-east-1)")
+                .env(env::AWS_REGION)
+                .section(Section::ProviderSpecific)
+                .required(),
+            FieldMeta::new("access_key_id", "AWS access key ID")
+                .env(env::AWS_ACCESS_KEY_ID)
+                .section(Section::ProviderSpecific)
+                .required()
+                .sensitive(),
+            FieldMeta::new("secret_access_key", "AWS secret access key")
+                .env(env::AWS_SECRET_ACCESS_KEY)
+                .section(Section::ProviderSpecific)
+                .required()
+                .sensitive(),
+            FieldMeta::new("session_token", "AWS session token (for temporary credentials)")
+                .env(env::AWS_SESSION_TOKEN)
+                .section(Section::ProviderSpecific)
+                .sensitive(),
+        ],
+        skip_common: &["api_key", "chat_path"], // Bedrock signs requests with AWS credentials instead, at its own URLs
+    },
+    ProviderMeta {
+        name: "perplexity",
+        display_name: "Perplexity",
+        description: "Perplexity API (web-grounded answers)",
+        field_overrides: &[
+            FieldOverride { name: "api_key", env_var: Some(env::PERPLEXITY_API_KEY), default: None, required: None },
+            FieldOverride { name: "api_base", env_var: Some(env::PERPLEXITY_API_BASE), default: Some("https://api.perplexity.ai"), required: None },
+            FieldOverride { name: "model", env_var: Some(env::PERPLEXITY_MODEL), default: Some("sonar"), required: None },
+            FieldOverride { name: "max_tokens", env_var: Some(env::PERPLEXITY_MAX_TOKENS), default: None, required: None },
+        ],
+        extra_fields: &[],
+        skip_common: &[],
+    },
+    ProviderMeta {
+        name: "deepseek",
+        display_name: "DeepSeek",
+        description: "DeepSeek API (deepseek-chat, or deepseek-reasoner for step-by-step reasoning)",
+        field_overrides: &[
+            FieldOverride { name: "api_key", env_var: Some(env::DEEPSEEK_API_KEY), default: None, required: None },
+            FieldOverride { name: "api_base", env_var: Some(env::DEEPSEEK_API_BASE), default: Some("https://api.deepseek.com"), required: None },
+            FieldOverride { name: "model", env_var: Some(env::DEEPSEEK_MODEL), default: Some("deepseek-chat"), required: None },
+            FieldOverride { name: "max_tokens", env_var: Some(env::DEEPSEEK_MAX_TOKENS), default: None, required: None },
+        ],
+        extra_fields: &[],
+        skip_common: &[],
+    },
+    ProviderMeta {
+        name: "local",
+        display_name: "Local server",
+        description: "Local OpenAI-compatible server such as llama.cpp or LM Studio (no API key required)",
+        field_overrides: &[
+            FieldOverride { name: "api_key", env_var: None, default: None, required: None },
+            FieldOverride { name: "api_base", env_var: Some(env::LOCAL_API_BASE), default: Some("http://localhost:8080"), required: None },
+            FieldOverride { name: "model", env_var: Some(env::LOCAL_MODEL), default: Some("local-model"), required: None },
+            FieldOverride { name: "max_tokens", env_var: Some(env::LOCAL_MAX_TOKENS), default: None, required: None },
+        ],
+        extra_fields: &[],
+        skip_common: &["api_key"], // Local servers don't require api_key
+    },
+    ProviderMeta {
+        name: "mock",
+        display_name: "Mock",
+        description: "Canned responses from simple keyword rules, without any network access (for demos and tests, not real use)",
+        field_overrides: &[
+            FieldOverride { name: "model", env_var: None, default: Some("mock"), required: None },
+        ],
+        extra_fields: &[],
+        skip_common: &["api_key", "api_base", "chat_path"], // Answered in-process
+    },
 ];
 
 impl Provider {
@@ -723,6 +1133,7 @@ fn source_to_hint(source: ConfigSource, field_path: &str, actual_env_var: Option
         }
         ConfigSource::JsonFile => "config.json".to_string(),
         ConfigSource::TomlFile => "config.toml".to_string(),
+        ConfigSource::ProjectFile => PROJECT_CONFIG_FILE.to_string(),
         ConfigSource::Default => "default".to_string(),
     }
 }
@@ -820,16 +1231,22 @@ fn env_to_json(builder: &mut ConfigBuilder) -> serde_json::Value {
         }
     }
 
+    // SHAI_NO_PROGRESS=true disables the spinner (`progress = false`)
+    if let Ok(v) = std::env::var(env::SHAI_NO_PROGRESS) {
+        if v.to_lowercase() == "true" || v == "1" {
+            obj.insert("progress".to_string(), serde_json::Value::Bool(false));
+            builder.record_env_var("progress", env::SHAI_NO_PROGRESS);
+        }
+    }
+
     // Handle legacy SHAI_SKIP_CONFIRM
     if let Ok(v) = std::env::var(env::SHAI_SKIP_CONFIRM) {
-        if v.to_lowercase() == "true" {
-            if std::env::var(env::SHAI_FRONTEND).is_err() {
-                obj.insert(
-                    "frontend".to_string(),
-                    serde_json::Value::String("noninteractive".to_string()),
-                );
-                builder.record_env_var("frontend", env::SHAI_SKIP_CONFIRM);
-            }
+        if v.to_lowercase() == "true" && std::env::var(env::SHAI_FRONTEND).is_err() {
+            obj.insert(
+                "frontend".to_string(),
+                serde_json::Value::String("noninteractive".to_string()),
+            );
+            builder.record_env_var("frontend", env::SHAI_SKIP_CONFIRM);
         }
     }
 
@@ -848,6 +1265,16 @@ pub struct CliOverrides {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub citation_min_confidence: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_usage: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_timing: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub frontend: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_format: Option<String>,
@@ -855,6 +1282,15 @@ pub struct CliOverrides {
     pub debug: Option<DebugLevel>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec_shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_execute: Option<bool>,
+    /// Extra request headers for the selected provider, from `--header`.
+    #[serde(skip)]
+    pub headers: Vec<(String, String)>,
 }
 
 /// Convert CLI arguments to a JSON object using serde.
@@ -873,11 +1309,23 @@ pub struct ProviderCredentials {
     pub model: Option<String>,
     #[serde(default, deserialize_with = "deserialize_flexible")]
     pub max_tokens: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub temperature: Option<f32>,
+    pub chat_path: Option<String>,
+    /// Headers sent with every request, from `[<provider>.extra_headers]` and `--header`.
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
     // OpenAI-specific
     pub organization: Option<String>,
     // Azure-specific
     pub deployment_name: Option<String>,
     pub api_version: Option<String>,
+    pub auth: Option<AzureAuth>,
+    // Bedrock-specific
+    pub region: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
 }
 
 impl ProviderCredentials {
@@ -889,13 +1337,37 @@ impl ProviderCredentials {
             "model" => self.model.clone(),
             "organization" => self.organization.clone(),
             "max_tokens" => self.max_tokens.map(|t| t.to_string()),
+            "temperature" => self.temperature.map(|t| t.to_string()),
+            "chat_path" => self.chat_path.clone(),
             "deployment_name" => self.deployment_name.clone(),
             "api_version" => self.api_version.clone(),
+            "auth" => self.auth.map(|a| a.to_string()),
+            "region" => self.region.clone(),
+            "access_key_id" => self.access_key_id.clone(),
+            "secret_access_key" => self.secret_access_key.clone(),
+            "session_token" => self.session_token.clone(),
             _ => None,
         }
     }
 }
 
+/// Per-model prices for estimating spend, from a `[pricing.<model>]` section.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ModelPricing {
+    /// Price per 1,000 prompt (input) tokens.
+    pub input_per_1k: f64,
+    /// Price per 1,000 completion (output) tokens.
+    pub output_per_1k: f64,
+}
+
+impl ModelPricing {
+    /// Estimated cost of the given token usage.
+    pub fn cost(&self, usage: &http::Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input_per_1k + usage.completion_tokens as f64 * self.output_per_1k)
+            / 1000.0
+    }
+}
+
 /// Result of validating configuration for a specific provider.
 #[derive(Debug)]
 pub struct ValidationError {
@@ -913,13 +1385,55 @@ pub struct TomlConfig {
     pub temperature: Option<f32>,
     #[serde(default, deserialize_with = "deserialize_flexible")]
     pub suggestion_count: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub suggest_concurrency: Option<u32>,
+    pub suggest_system_prompt: Option<String>,
+    pub suggest_prompt_file: Option<String>,
+    pub shell: Option<ShellType>,
+    pub exec_shell: Option<ShellType>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub auto_copy: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub confirm_before_execute: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub allow_execute: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub ctx_max_chars: Option<u32>,
+    pub fallback_model: Option<String>,
+    pub ctx_capture: Option<CtxCapture>,
+    pub menu_actions: Option<String>,
     pub frontend: Option<Frontend>,
     pub output_format: Option<OutputFormat>,
+    pub edit_mode: Option<EditMode>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub progress: Option<bool>,
+    pub spinner_frames: Option<String>,
+    pub spinner_template: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub show_usage: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub show_timing: Option<bool>,
     #[serde(default, deserialize_with = "deserialize_flexible")]
     pub max_reference_chars: Option<u32>,
+    pub explain_reference: Option<ExplainReference>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub reference_timeout: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub citation_min_confidence: Option<f32>,
     #[serde(default, deserialize_with = "deserialize_flexible")]
     pub max_tokens: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub request_timeout: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub max_retries: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub initial_backoff_ms: Option<u64>,
+    pub json_schema_mode: Option<JsonSchemaMode>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    #[serde(default, deserialize_with = "deserialize_flexible")]
+    pub offline: Option<bool>,
     pub debug: Option<DebugLevel>,
+    pub log_file: Option<String>,
     pub locale: Option<String>,
 
     // Provider-specific sections
@@ -928,6 +1442,15 @@ pub struct TomlConfig {
     pub azure: Option<ProviderCredentials>,
     pub ollama: Option<ProviderCredentials>,
     pub mistral: Option<ProviderCredentials>,
+    pub bedrock: Option<ProviderCredentials>,
+    pub perplexity: Option<ProviderCredentials>,
+    pub deepseek: Option<ProviderCredentials>,
+    pub local: Option<ProviderCredentials>,
+    pub mock: Option<ProviderCredentials>,
+
+    // Per-model prices, keyed by model name
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
 }
 
 /// Unified application configuration with source tracking.
@@ -941,18 +1464,46 @@ pub struct AppConfig {
     // UI settings
     pub frontend: ConfigValue<Frontend>,
     pub output_format: ConfigValue<OutputFormat>,
+    pub edit_mode: ConfigValue<EditMode>,
+    pub progress: ConfigValue<bool>,
+    pub spinner_frames: ConfigValue<String>,
+    pub spinner_template: ConfigValue<String>,
+    pub show_usage: ConfigValue<bool>,
+    pub show_timing: ConfigValue<bool>,
 
     // Suggest-specific settings
     pub suggestion_count: ConfigValue<u32>,
+    pub suggest_concurrency: ConfigValue<u32>,
+    pub suggest_system_prompt: ConfigValue<Option<String>>,
+    pub suggest_prompt_file: ConfigValue<Option<String>>,
+    pub shell: ConfigValue<Option<ShellType>>,
+    pub exec_shell: ConfigValue<Option<ShellType>>,
+    pub auto_copy: ConfigValue<bool>,
+    pub confirm_before_execute: ConfigValue<bool>,
+    pub allow_execute: ConfigValue<bool>,
+    pub ctx_max_chars: ConfigValue<u32>,
+    pub ctx_capture: ConfigValue<CtxCapture>,
+    pub menu_actions: ConfigValue<String>,
+    pub fallback_model: ConfigValue<Option<String>>,
 
     // Explain-specific settings
     pub max_reference_chars: ConfigValue<u32>,
+    pub explain_reference: ConfigValue<ExplainReference>,
+    pub reference_timeout: ConfigValue<u64>,
+    pub citation_min_confidence: ConfigValue<f32>,
 
     // API request settings
     pub max_tokens: ConfigValue<Option<u32>>,
+    pub request_timeout: ConfigValue<u64>,
+    pub max_retries: ConfigValue<u32>,
+    pub initial_backoff_ms: ConfigValue<u64>,
+    pub json_schema_mode: ConfigValue<JsonSchemaMode>,
+    pub reasoning_effort: ConfigValue<Option<ReasoningEffort>>,
+    pub offline: ConfigValue<bool>,
 
     // Debug/logging level
     pub debug: ConfigValue<Option<DebugLevel>>,
+    pub log_file: ConfigValue<Option<String>>,
 
     // Locale for AI responses
     pub locale: ConfigValue<Option<String>>,
@@ -960,11 +1511,15 @@ pub struct AppConfig {
     // Provider credentials (HashMap instead of individual fields)
     pub providers: HashMap<Provider, ProviderCredentials>,
 
+    // Per-model prices for estimating spend (config file only)
+    pub pricing: HashMap<String, ModelPricing>,
+
     // Source tracking for all config paths
     sources: HashMap<String, ConfigSource>,
 
     // Config file paths for reporting
     pub toml_path: Option<PathBuf>,
+    pub project_path: Option<PathBuf>,
     pub json_path: Option<PathBuf>,
 }
 
@@ -984,8 +1539,8 @@ impl<'a> ValidatedConfig<'a> {
         self.config.effective_model()
     }
 
-    pub fn temperature(&self) -> f32 {
-        self.config.temperature.value
+    pub fn effective_temperature(&self) -> f32 {
+        self.config.effective_temperature()
     }
 
     pub fn effective_max_tokens(&self) -> Option<u32> {
@@ -999,6 +1554,7 @@ impl AppConfig {
     pub fn load_with_cli(cli: CliOverrides) -> Self {
         let mut builder = ConfigBuilder::new();
         let mut toml_path: Option<PathBuf> = None;
+        let mut project_path: Option<PathBuf> = None;
         let mut json_path: Option<PathBuf> = None;
 
         // Layer 1: Defaults (from metadata)
@@ -1022,7 +1578,41 @@ impl AppConfig {
             }
         }
 
-        // Layer 3: JSON config (legacy)
+        // Layer 3: Project config (.shell-ai.toml in the current directory or a parent)
+        if let Some(path) = project_config_path() {
+            match load_toml_file_as_json(path) {
+                TomlJsonLoadResult::Loaded(mut project_json, path) => {
+                    log::debug!("Loaded project config: {}", path.display());
+                    if !project_config_trusted() {
+                        let ignored = strip_untrusted_project_settings(&mut project_json);
+                        if !ignored.is_empty() {
+                            log::warn!(
+                                "Ignoring {} from project config {}, which could send your requests elsewhere, read or write files, or skip safety checks.\n\
+                                 Hint: Review the file, then set {}=true to apply these settings.",
+                                ignored.join(", "),
+                                path.display(),
+                                env::SHAI_TRUST_PROJECT_CONFIG
+                            );
+                        }
+                    }
+                    project_path = Some(path);
+                    builder.merge_layer(&project_json, ConfigSource::ProjectFile);
+                }
+                TomlJsonLoadResult::NotFound => {}
+                TomlJsonLoadResult::ParseError(path, err) => {
+                    log::error!(
+                        "Failed to parse project config file: {}\n\n{}\n\n\
+                         Hint: Fix the syntax error above, or set {}=true to ignore project config files.",
+                        path.display(),
+                        err,
+                        env::SHAI_NO_PROJECT_CONFIG
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        // Layer 4: JSON config (legacy)
         match load_json_as_value() {
             JsonValueLoadResult::Loaded(json, path) => {
                 json_path = Some(path);
@@ -1040,12 +1630,23 @@ impl AppConfig {
             }
         }
 
-        // Layer 4: Environment variables
+        // Layer 5: Environment variables
         let env_json = env_to_json(&mut builder);
         builder.merge_layer(&env_json, ConfigSource::Environment);
 
-        // Layer 5: CLI arguments
+        // Layer 6: CLI arguments
         builder.merge_layer(&cli_to_json(&cli), ConfigSource::Cli);
+        // --header applies to whichever provider the layers above selected
+        let selected_provider = builder.config.get("provider")
+            .and_then(|p| p.as_str())
+            .and_then(|p| Provider::from_str(&p.to_lowercase()).ok());
+        if let (Some(provider), false) = (selected_provider, cli.headers.is_empty()) {
+            let headers: serde_json::Map<String, serde_json::Value> = cli.headers.iter()
+                .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+                .collect();
+            let layer = serde_json::json!({ provider.metadata().name: { "extra_headers": headers } });
+            builder.merge_layer(&layer, ConfigSource::Cli);
+        }
 
         // Parse merged JSON into TomlConfig
         let config_json = builder.config.clone();
@@ -1060,7 +1661,7 @@ impl AppConfig {
             }
         };
 
-        Self::from_parsed(parsed, builder, toml_path, json_path)
+        Self::from_parsed(parsed, builder, toml_path, project_path, json_path)
     }
 
     /// Convert parsed TomlConfig to AppConfig with source tracking from builder.
@@ -1068,6 +1669,7 @@ impl AppConfig {
         parsed: TomlConfig,
         builder: ConfigBuilder,
         toml_path: Option<PathBuf>,
+        project_path: Option<PathBuf>,
         json_path: Option<PathBuf>,
     ) -> Self {
         // Build providers HashMap
@@ -1087,6 +1689,21 @@ impl AppConfig {
         if let Some(creds) = parsed.mistral {
             providers.insert(Provider::Mistral, creds);
         }
+        if let Some(creds) = parsed.bedrock {
+            providers.insert(Provider::Bedrock, creds);
+        }
+        if let Some(creds) = parsed.perplexity {
+            providers.insert(Provider::Perplexity, creds);
+        }
+        if let Some(creds) = parsed.deepseek {
+            providers.insert(Provider::DeepSeek, creds);
+        }
+        if let Some(creds) = parsed.local {
+            providers.insert(Provider::Local, creds);
+        }
+        if let Some(creds) = parsed.mock {
+            providers.insert(Provider::Mock, creds);
+        }
 
         // Ensure all providers have at least default credentials
         for provider in Provider::iter() {
@@ -1113,29 +1730,143 @@ impl AppConfig {
                 parsed.output_format.unwrap_or(OutputFormat::Human),
                 sources.get("output_format").copied().unwrap_or(ConfigSource::Default),
             ),
+            edit_mode: ConfigValue::new(
+                parsed.edit_mode.unwrap_or_default(),
+                sources.get("edit_mode").copied().unwrap_or(ConfigSource::Default),
+            ),
+            progress: ConfigValue::new(
+                parsed.progress.unwrap_or(true),
+                sources.get("progress").copied().unwrap_or(ConfigSource::Default),
+            ),
+            spinner_frames: ConfigValue::new(
+                parsed.spinner_frames.unwrap_or_else(|| crate::progress::DEFAULT_FRAMES.to_string()),
+                sources.get("spinner_frames").copied().unwrap_or(ConfigSource::Default),
+            ),
+            spinner_template: ConfigValue::new(
+                parsed.spinner_template.unwrap_or_else(|| crate::progress::DEFAULT_TEMPLATE.to_string()),
+                sources.get("spinner_template").copied().unwrap_or(ConfigSource::Default),
+            ),
+            show_usage: ConfigValue::new(
+                parsed.show_usage.unwrap_or(false),
+                sources.get("show_usage").copied().unwrap_or(ConfigSource::Default),
+            ),
+            show_timing: ConfigValue::new(
+                parsed.show_timing.unwrap_or(false),
+                sources.get("show_timing").copied().unwrap_or(ConfigSource::Default),
+            ),
             suggestion_count: ConfigValue::new(
                 parsed.suggestion_count.unwrap_or(3),
                 sources.get("suggestion_count").copied().unwrap_or(ConfigSource::Default),
             ),
+            suggest_concurrency: ConfigValue::new(
+                parsed.suggest_concurrency.unwrap_or(4),
+                sources.get("suggest_concurrency").copied().unwrap_or(ConfigSource::Default),
+            ),
+            suggest_system_prompt: ConfigValue::new(
+                parsed.suggest_system_prompt,
+                sources.get("suggest_system_prompt").copied().unwrap_or(ConfigSource::Default),
+            ),
+            suggest_prompt_file: ConfigValue::new(
+                parsed.suggest_prompt_file,
+                sources.get("suggest_prompt_file").copied().unwrap_or(ConfigSource::Default),
+            ),
+            shell: ConfigValue::new(
+                parsed.shell,
+                sources.get("shell").copied().unwrap_or(ConfigSource::Default),
+            ),
+            exec_shell: ConfigValue::new(
+                parsed.exec_shell,
+                sources.get("exec_shell").copied().unwrap_or(ConfigSource::Default),
+            ),
+            auto_copy: ConfigValue::new(
+                parsed.auto_copy.unwrap_or(false),
+                sources.get("auto_copy").copied().unwrap_or(ConfigSource::Default),
+            ),
+            confirm_before_execute: ConfigValue::new(
+                parsed.confirm_before_execute.unwrap_or(false),
+                sources.get("confirm_before_execute").copied().unwrap_or(ConfigSource::Default),
+            ),
+            allow_execute: ConfigValue::new(
+                parsed.allow_execute.unwrap_or(true),
+                sources.get("allow_execute").copied().unwrap_or(ConfigSource::Default),
+            ),
+            ctx_max_chars: ConfigValue::new(
+                parsed.ctx_max_chars.unwrap_or(1500),
+                sources.get("ctx_max_chars").copied().unwrap_or(ConfigSource::Default),
+            ),
+            ctx_capture: ConfigValue::new(
+                parsed.ctx_capture.unwrap_or_default(),
+                sources.get("ctx_capture").copied().unwrap_or(ConfigSource::Default),
+            ),
+            menu_actions: ConfigValue::new(
+                parsed.menu_actions.unwrap_or_else(|| DEFAULT_MENU_ACTIONS.to_string()),
+                sources.get("menu_actions").copied().unwrap_or(ConfigSource::Default),
+            ),
+            fallback_model: ConfigValue::new(
+                parsed.fallback_model.filter(|m| !m.is_empty()),
+                sources.get("fallback_model").copied().unwrap_or(ConfigSource::Default),
+            ),
             max_reference_chars: ConfigValue::new(
                 parsed.max_reference_chars.unwrap_or(262144),
                 sources.get("max_reference_chars").copied().unwrap_or(ConfigSource::Default),
             ),
+            explain_reference: ConfigValue::new(
+                parsed.explain_reference.unwrap_or(ExplainReference::Man),
+                sources.get("explain_reference").copied().unwrap_or(ConfigSource::Default),
+            ),
+            reference_timeout: ConfigValue::new(
+                parsed.reference_timeout.unwrap_or(3),
+                sources.get("reference_timeout").copied().unwrap_or(ConfigSource::Default),
+            ),
+            citation_min_confidence: ConfigValue::new(
+                parsed.citation_min_confidence.unwrap_or(0.3),
+                sources.get("citation_min_confidence").copied().unwrap_or(ConfigSource::Default),
+            ),
             max_tokens: ConfigValue::new(
                 parsed.max_tokens,
                 sources.get("max_tokens").copied().unwrap_or(ConfigSource::Default),
             ),
+            request_timeout: ConfigValue::new(
+                parsed.request_timeout.unwrap_or(http::DEFAULT_TIMEOUT_SECS),
+                sources.get("request_timeout").copied().unwrap_or(ConfigSource::Default),
+            ),
+            max_retries: ConfigValue::new(
+                parsed.max_retries.unwrap_or(http::DEFAULT_MAX_RETRIES),
+                sources.get("max_retries").copied().unwrap_or(ConfigSource::Default),
+            ),
+            initial_backoff_ms: ConfigValue::new(
+                parsed.initial_backoff_ms.unwrap_or(http::DEFAULT_INITIAL_BACKOFF_MS),
+                sources.get("initial_backoff_ms").copied().unwrap_or(ConfigSource::Default),
+            ),
+            json_schema_mode: ConfigValue::new(
+                parsed.json_schema_mode.unwrap_or_default(),
+                sources.get("json_schema_mode").copied().unwrap_or(ConfigSource::Default),
+            ),
+            reasoning_effort: ConfigValue::new(
+                parsed.reasoning_effort,
+                sources.get("reasoning_effort").copied().unwrap_or(ConfigSource::Default),
+            ),
+            offline: ConfigValue::new(
+                parsed.offline.unwrap_or(false),
+                sources.get("offline").copied().unwrap_or(ConfigSource::Default),
+            ),
             debug: ConfigValue::new(
                 parsed.debug,
                 sources.get("debug").copied().unwrap_or(ConfigSource::Default),
             ),
+            log_file: ConfigValue::new(
+                parsed.log_file,
+                sources.get("log_file").copied().unwrap_or(ConfigSource::Default),
+            ),
             locale: ConfigValue::new(
                 parsed.locale,
                 sources.get("locale").copied().unwrap_or(ConfigSource::Default),
             ),
             providers,
+            pricing: parsed.pricing,
             sources,
             toml_path,
+            project_path,
             json_path,
         }
     }
@@ -1157,11 +1888,19 @@ impl AppConfig {
 
     /// Get the effective model for the current provider.
     pub fn effective_model(&self) -> String {
+        match self.provider.value {
+            Some(ref provider) => self.effective_model_for(provider),
+            None => self.model.value.clone(),
+        }
+    }
+
+    /// Get the model that would be used with a specific provider.
+    pub fn effective_model_for(&self, provider: &Provider) -> String {
         if !self.model.value.is_empty() {
             return self.model.value.clone();
         }
 
-        if let Some(creds) = self.current_provider_credentials() {
+        if let Some(creds) = self.get_credentials_for(provider) {
             if let Some(ref model) = creds.model {
                 if !model.is_empty() {
                     return model.clone();
@@ -1169,17 +1908,75 @@ impl AppConfig {
             }
         }
 
-        if let Some(ref provider) = self.provider.value {
-            if let Some(field) = provider.metadata().resolved_field("model") {
-                if let Some(default) = field.default {
-                    return default.to_string();
-                }
+        if let Some(field) = provider.metadata().resolved_field("model") {
+            if let Some(default) = field.default {
+                return default.to_string();
             }
         }
 
         String::new()
     }
 
+    /// Get the shell that executed commands run in: `exec_shell`, else
+    /// `shell`, else the detected shell. `None` means the platform default.
+    pub fn execution_shell(&self) -> Option<ShellType> {
+        self.exec_shell.value
+            .or(self.shell.value)
+            .or_else(ShellType::detect)
+    }
+
+    /// Get the actions in `menu_actions`, in order and without repeats.
+    ///
+    /// Execute is included even when `allow_execute` is false; the menus leave it out.
+    pub fn menu_actions(&self) -> anyhow::Result<Vec<MenuAction>> {
+        let mut actions = Vec::new();
+        for name in self.menu_actions.value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let action = MenuAction::from_str(&name.to_lowercase()).map_err(|_| {
+                let valid: Vec<String> = MenuAction::iter().map(|a| a.to_string()).collect();
+                anyhow::anyhow!("Invalid menu_actions entry '{}' (valid actions: {})", name, valid.join(", "))
+            })?;
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Get the user's customization for the suggest system prompt.
+    ///
+    /// `suggest_system_prompt` takes precedence over `suggest_prompt_file`.
+    /// Returns `None` when neither is set or the text is blank.
+    pub fn suggest_prompt_customization(&self) -> anyhow::Result<Option<String>> {
+        let text = match (&self.suggest_system_prompt.value, &self.suggest_prompt_file.value) {
+            (Some(prompt), _) => prompt.clone(),
+            (None, Some(path)) => {
+                let expanded = shellexpand::tilde(path).into_owned();
+                fs::read_to_string(&expanded)
+                    .map_err(|e| anyhow::anyhow!("Failed to read suggest_prompt_file {}: {}", expanded, e))?
+            }
+            (None, None) => return Ok(None),
+        };
+
+        let text = text.trim();
+        Ok((!text.is_empty()).then(|| text.to_string()))
+    }
+
+    /// Get the effective temperature for the current provider.
+    ///
+    /// A temperature set globally (CLI, env, or config file) wins over the
+    /// provider-specific one, which wins over the built-in default.
+    pub fn effective_temperature(&self) -> f32 {
+        if self.temperature.source != ConfigSource::Default {
+            return self.temperature.value;
+        }
+
+        if let Some(temperature) = self.current_provider_credentials().and_then(|c| c.temperature) {
+            return temperature;
+        }
+
+        self.temperature.value
+    }
+
     /// Get the effective max_tokens for the current provider.
     pub fn effective_max_tokens(&self) -> Option<u32> {
         if self.max_tokens.value.is_some() {
@@ -1195,17 +1992,26 @@ impl AppConfig {
         None
     }
 
+    /// How many characters of output context mode sends, with the default
+    /// capped to fit the configured `max_tokens`.
+    pub fn effective_ctx_max_chars(&self) -> u32 {
+        self.ctx_max_chars.value.min(ctx_max_chars_limit(self.effective_max_tokens()))
+    }
+
     // ========================================================================
     // Validation
     // ========================================================================
 
     /// Validate configuration for the current provider.
     pub fn validate_provider(&self) -> Vec<ValidationError> {
-        let provider = match &self.provider.value {
-            Some(p) => p,
-            None => return vec![],
-        };
+        match &self.provider.value {
+            Some(provider) => self.validate_provider_for(provider),
+            None => vec![],
+        }
+    }
 
+    /// Validate configuration for a specific provider.
+    pub fn validate_provider_for(&self, provider: &Provider) -> Vec<ValidationError> {
         let meta = provider.metadata();
         let creds = self.providers.get(provider).unwrap();
         let mut errors = Vec::new();
@@ -1236,6 +2042,35 @@ impl AppConfig {
             }
         }
 
+        // The region becomes part of the endpoint's host name
+        if *provider == Provider::Bedrock {
+            if let Some(region) = creds.region.as_deref().filter(|r| !is_aws_region(r)) {
+                errors.push(ValidationError {
+                    field: "region".to_string(),
+                    description: format!("AWS region {:?} (must be lowercase letters, digits, and dashes)", region),
+                    hint: format!("Set {} or [bedrock].region to a region such as us-east-1", env::AWS_REGION),
+                });
+            }
+        }
+
+        // With auth = token, the Entra ID token is fetched on the first
+        // request, so validation doesn't run `az`; `shell-ai doctor` checks it
+        if *provider == Provider::Azure && creds.auth.unwrap_or_default() == AzureAuth::Key {
+            let has_key = creds.api_key.as_deref()
+                .or_else(|| self.get_credentials_for(&Provider::OpenAI).and_then(|c| c.api_key.as_deref()))
+                .is_some_and(|k| !k.is_empty());
+            if !has_key {
+                errors.push(ValidationError {
+                    field: "api_key".to_string(),
+                    description: "API key (required when auth = key)".to_string(),
+                    hint: format!(
+                        "Set {} or add [azure].api_key to config.toml, or set [azure].auth = \"token\" to use Entra ID",
+                        env::AZURE_API_KEY
+                    ),
+                });
+            }
+        }
+
         errors
     }
 
@@ -1258,21 +2093,89 @@ impl AppConfig {
         }
 
         // Check for frontend + output_format mutual exclusion
-        // JSON output requires a non-interactive frontend (or automatic, which will resolve appropriately)
-        if self.output_format.value == OutputFormat::Json {
+        // Structured output requires a non-interactive frontend (or automatic, which will resolve appropriately)
+        if self.output_format.value.is_structured() {
             match self.frontend.value {
                 Frontend::Automatic | Frontend::Noninteractive => {}
                 Frontend::Dialog | Frontend::Readline => {
                     anyhow::bail!(
-                        "Configuration conflict: frontend={} cannot be used with output_format=json.\n\
-                         JSON output requires a non-interactive frontend.\n\
+                        "Configuration conflict: frontend={} cannot be used with output_format={}.\n\
+                         Structured output requires a non-interactive frontend.\n\
                          Hint: Use frontend=noninteractive or frontend=automatic (default).",
-                        self.frontend.value
+                        self.frontend.value,
+                        self.output_format.value
                     );
                 }
             }
         }
 
+        // Check HTTP request settings are in a sane range
+        if self.request_timeout.value > 3600 {
+            anyhow::bail!(
+                "Invalid request_timeout: {} (must be between 0 and 3600 seconds, 0 = no timeout)",
+                self.request_timeout.value
+            );
+        }
+        if self.max_retries.value > 10 {
+            anyhow::bail!("Invalid max_retries: {} (must be between 0 and 10)", self.max_retries.value);
+        }
+        if self.initial_backoff_ms.value > 60_000 {
+            anyhow::bail!(
+                "Invalid initial_backoff_ms: {} (must be between 0 and 60000)",
+                self.initial_backoff_ms.value
+            );
+        }
+
+        // Context mode output goes into every request's prompt, so keep it
+        // within the token budget. The default shrinks to fit instead.
+        let ctx_limit = ctx_max_chars_limit(self.effective_max_tokens());
+        if self.ctx_max_chars.source != ConfigSource::Default
+            && !(MIN_CTX_MAX_CHARS..=ctx_limit).contains(&self.ctx_max_chars.value)
+        {
+            anyhow::bail!(
+                "Invalid ctx_max_chars: {} (must be between {} and {}, about {} characters per token of max_tokens)",
+                self.ctx_max_chars.value,
+                MIN_CTX_MAX_CHARS,
+                ctx_limit,
+                CHARS_PER_TOKEN
+            );
+        }
+
+        // Check the action menu keeps at least one action
+        let actions = self.menu_actions()?;
+        if !actions.iter().any(|a| *a != MenuAction::Execute || self.allow_execute.value) {
+            anyhow::bail!(
+                "Invalid menu_actions: {:?} leaves no actions in the menu{}",
+                self.menu_actions.value,
+                if actions.is_empty() { "" } else { " (execute is hidden because allow_execute is false)" }
+            );
+        }
+
+        // Check the reference lookup timeout is in a sane range
+        if !(1..=60).contains(&self.reference_timeout.value) {
+            anyhow::bail!(
+                "Invalid reference_timeout: {} (must be between 1 and 60 seconds)",
+                self.reference_timeout.value
+            );
+        }
+
+        // Check the citation threshold is a valid confidence
+        if !(0.0..=1.0).contains(&self.citation_min_confidence.value) {
+            anyhow::bail!(
+                "Invalid citation_min_confidence: {} (must be between 0.0 and 1.0)",
+                self.citation_min_confidence.value
+            );
+        }
+
+        // Check prices are usable for cost estimates
+        for (model, pricing) in &self.pricing {
+            for (name, price) in [("input_per_1k", pricing.input_per_1k), ("output_per_1k", pricing.output_per_1k)] {
+                if !price.is_finite() || price < 0.0 {
+                    anyhow::bail!("Invalid pricing.\"{}\".{}: {} (must be 0 or more)", model, name, price);
+                }
+            }
+        }
+
         // Check if provider is set
         let provider = match &self.provider.value {
             Some(p) => p,
@@ -1282,8 +2185,9 @@ impl AppConfig {
                     "No provider configured.\n\n\
                      Quick start (choose one):\n  \
                      1. Set environment variable:  export {}=groq\n  \
-                     2. Generate config file:      shell-ai config init\n  \
-                     3. View all options:          shell-ai config schema\n\n\
+                     2. Answer a few questions:    shell-ai setup\n  \
+                     3. Generate config file:      shell-ai config init\n  \
+                     4. View all options:          shell-ai config schema\n\n\
                      Supported providers: {}",
                     env::SHAI_API_PROVIDER,
                     provider_names.join(", ")
@@ -1310,6 +2214,10 @@ impl AppConfig {
         let credentials = self.providers.get(provider)
             .expect("credentials exist after validate_provider passes");
 
+        if self.offline.value {
+            self.check_offline(provider, credentials)?;
+        }
+
         Ok(ValidatedConfig {
             config: self,
             provider,
@@ -1317,6 +2225,51 @@ impl AppConfig {
         })
     }
 
+    /// Reject a provider that would send requests off this machine.
+    ///
+    /// Only an `api_base` on a loopback address is allowed. Ollama's `-cloud`
+    /// models are rejected too, since the local daemon forwards them to
+    /// ollama.com.
+    fn check_offline(&self, provider: &Provider, credentials: &ProviderCredentials) -> anyhow::Result<()> {
+        // The mock provider never leaves the process
+        if *provider == Provider::Mock {
+            return Ok(());
+        }
+        let meta = provider.metadata();
+        let api_base = credentials.api_base.clone().or_else(|| {
+            meta.resolved_field("api_base")
+                .and_then(|f| f.default)
+                .map(|d| d.to_string())
+        });
+
+        match api_base {
+            Some(base) if http::is_loopback_url(&base) => {}
+            Some(base) => anyhow::bail!(
+                "Offline mode ({}=true) refuses to contact {}: {} is not a local address.\n\
+                 Hint: Point {}.api_base at a server on localhost, or use the ollama or local provider.",
+                env::SHAI_OFFLINE, meta.display_name, base, meta.name
+            ),
+            None => anyhow::bail!(
+                "Offline mode ({}=true) refuses to contact {}: no local api_base is configured.\n\
+                 Hint: Point {}.api_base at a server on localhost, or use the ollama or local provider.",
+                env::SHAI_OFFLINE, meta.display_name, meta.name
+            ),
+        }
+
+        if *provider == Provider::Ollama {
+            let model = self.effective_model();
+            if model.ends_with("-cloud") || model.ends_with(":cloud") {
+                anyhow::bail!(
+                    "Offline mode ({}=true) refuses to use Ollama cloud model {}: Ollama forwards it to ollama.com.\n\
+                     Hint: Set {} to a model that runs locally (see `ollama list`).",
+                    env::SHAI_OFFLINE, model, env::OLLAMA_MODEL
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     // ========================================================================
     // Field Accessors for Data-Driven Config Display
     // ========================================================================
@@ -1347,8 +2300,59 @@ impl AppConfig {
                 };
                 Some((display, source))
             }
-            "temperature" => Some((format!("{:.2}", self.temperature.value), self.temperature.source)),
+            "temperature" => {
+                // Track source: global temperature → provider-specific temperature → default
+                let source = if self.temperature.source != ConfigSource::Default {
+                    self.temperature.source
+                } else if let Some(provider) = self.provider.value.as_ref() {
+                    let path = format!("{}.temperature", provider.metadata().name);
+                    self.get_source(&path)
+                } else {
+                    ConfigSource::Default
+                };
+                Some((format!("{:.2}", self.effective_temperature()), source))
+            }
             "suggestion_count" => Some((self.suggestion_count.value.to_string(), self.suggestion_count.source)),
+            "suggest_concurrency" => Some((self.suggest_concurrency.value.to_string(), self.suggest_concurrency.source)),
+            "auto_copy" => Some((self.auto_copy.value.to_string(), self.auto_copy.source)),
+            "allow_execute" => Some((self.allow_execute.value.to_string(), self.allow_execute.source)),
+            "ctx_max_chars" => Some((self.ctx_max_chars.value.to_string(), self.ctx_max_chars.source)),
+            "ctx_capture" => Some((self.ctx_capture.value.to_string(), self.ctx_capture.source)),
+            "menu_actions" => Some((self.menu_actions.value.clone(), self.menu_actions.source)),
+            "fallback_model" => Some((
+                self.fallback_model.value.clone().unwrap_or_else(|| "(not set)".to_string()),
+                self.fallback_model.source,
+            )),
+            "confirm_before_execute" => Some((
+                self.confirm_before_execute.value.to_string(),
+                self.confirm_before_execute.source,
+            )),
+            "suggest_system_prompt" => Some((
+                self.suggest_system_prompt.value.clone().unwrap_or_else(|| "(not set)".to_string()),
+                self.suggest_system_prompt.source,
+            )),
+            "suggest_prompt_file" => Some((
+                self.suggest_prompt_file.value.clone().unwrap_or_else(|| "(not set)".to_string()),
+                self.suggest_prompt_file.source,
+            )),
+            "shell" => {
+                let display = match self.shell.value {
+                    Some(shell) => shell.to_string(),
+                    None => match ShellType::detect() {
+                        Some(shell) => format!("{} (auto)", shell),
+                        None => "(auto, none found)".to_string(),
+                    },
+                };
+                Some((display, self.shell.source))
+            }
+            "exec_shell" => {
+                let display = match (self.exec_shell.value, self.execution_shell()) {
+                    (Some(shell), _) => shell.to_string(),
+                    (None, Some(shell)) => format!("{} (auto)", shell),
+                    (None, None) => format!("{} (auto)", if cfg!(windows) { "cmd" } else { "sh" }),
+                };
+                Some((display, self.exec_shell.source))
+            }
             "skip_confirm" => {
                 if let Ok(v) = std::env::var(env::SHAI_SKIP_CONFIRM) {
                     if v.to_lowercase() == "true" {
@@ -1359,7 +2363,19 @@ impl AppConfig {
             }
             "frontend" => Some((self.frontend.value.to_string(), self.frontend.source)),
             "output_format" => Some((self.output_format.value.to_string(), self.output_format.source)),
+            "edit_mode" => Some((self.edit_mode.value.to_string(), self.edit_mode.source)),
+            "progress" => Some((self.progress.value.to_string(), self.progress.source)),
+            "spinner_frames" => Some((self.spinner_frames.value.clone(), self.spinner_frames.source)),
+            "spinner_template" => Some((self.spinner_template.value.clone(), self.spinner_template.source)),
+            "show_usage" => Some((self.show_usage.value.to_string(), self.show_usage.source)),
+            "show_timing" => Some((self.show_timing.value.to_string(), self.show_timing.source)),
             "max_reference_chars" => Some((self.max_reference_chars.value.to_string(), self.max_reference_chars.source)),
+            "explain_reference" => Some((self.explain_reference.value.to_string(), self.explain_reference.source)),
+            "reference_timeout" => Some((self.reference_timeout.value.to_string(), self.reference_timeout.source)),
+            "citation_min_confidence" => Some((
+                self.citation_min_confidence.value.to_string(),
+                self.citation_min_confidence.source,
+            )),
             "max_tokens" => {
                 let effective = self.effective_max_tokens();
                 // Track source: global max_tokens → provider-specific max_tokens → default
@@ -1376,12 +2392,25 @@ impl AppConfig {
                     .unwrap_or_else(|| "(not set)".to_string());
                 Some((display, source))
             }
+            "request_timeout" => Some((self.request_timeout.value.to_string(), self.request_timeout.source)),
+            "max_retries" => Some((self.max_retries.value.to_string(), self.max_retries.source)),
+            "initial_backoff_ms" => Some((self.initial_backoff_ms.value.to_string(), self.initial_backoff_ms.source)),
+            "json_schema_mode" => Some((self.json_schema_mode.value.to_string(), self.json_schema_mode.source)),
+            "reasoning_effort" => Some((
+                self.reasoning_effort.value.map(|r| r.to_string()).unwrap_or_else(|| "(not set)".to_string()),
+                self.reasoning_effort.source,
+            )),
+            "offline" => Some((self.offline.value.to_string(), self.offline.source)),
             "debug" => {
                 let value = self.debug.value
                     .map(|d| d.to_string())
                     .unwrap_or_else(|| "(not set)".to_string());
                 Some((value, self.debug.source))
             }
+            "log_file" => Some((
+                self.log_file.value.clone().unwrap_or_else(|| "(not set)".to_string()),
+                self.log_file.source,
+            )),
             "locale" => {
                 let display = match &self.locale.value {
                     None => {
@@ -1453,9 +2482,17 @@ impl AppConfig {
     /// Print configuration in human-readable format.
     pub fn print_human(&self) {
         const HEADING: &str = "Shell-AI Configuration";
-        println!("{}", HEADING.bold());
-        println!("{}", "=".repeat(HEADING.len()));
-        println!();
+        outln!("{}", HEADING.bold());
+        outln!("{}", "=".repeat(HEADING.len()));
+        outln!();
+
+        if self.provider.value == Some(Provider::Mock) {
+            outln!(
+                "{}",
+                "The mock provider is active: responses are canned keyword matches, not AI output.".yellow().bold()
+            );
+            outln!();
+        }
 
         let sections = [
             Section::Provider,
@@ -1465,7 +2502,7 @@ impl AppConfig {
         ];
 
         for section in sections {
-            println!("{}:", section.title().cyan());
+            outln!("{}:", section.title().cyan());
             for field in GLOBAL_SETTINGS_METADATA.iter().filter(|f| f.section == section) {
                 if let Some((value, source)) = self.get_global_field_display(field.name) {
                     if field.deprecated && source == ConfigSource::Default {
@@ -1479,14 +2516,14 @@ impl AppConfig {
                     print_config_line_deprecated(field.name, &display_value, source, field.deprecated);
                 }
             }
-            println!();
+            outln!();
         }
 
         // Provider-specific settings
         let providers_to_show = self.get_providers_to_display();
         for provider in providers_to_show {
             let meta = provider.metadata();
-            println!("{}:", format!("{} Settings", meta.display_name).cyan());
+            outln!("{}:", format!("{} Settings", meta.display_name).cyan());
             if let Some(creds) = self.providers.get(&provider) {
                 for field in meta.all_fields() {
                     let (value, source) = self.get_provider_field_display(&field, creds, meta.name);
@@ -1497,19 +2534,44 @@ impl AppConfig {
                     };
                     print_config_line(field.name, &display_value, source);
                 }
+                for (name, value) in &creds.extra_headers {
+                    let display_value = if http::is_sensitive_header(name) {
+                        mask_value(value)
+                    } else {
+                        value.clone()
+                    };
+                    let path = format!("{}.extra_headers.{}", meta.name, name);
+                    print_config_line(&format!("extra_headers.{}", name), &display_value, self.get_source(&path));
+                }
             }
-            println!();
+            outln!();
+        }
+
+        // Pricing section, only when configured
+        if !self.pricing.is_empty() {
+            outln!("{}:", "Pricing (per 1K tokens)".cyan());
+            let mut models: Vec<_> = self.pricing.iter().collect();
+            models.sort_by(|a, b| a.0.cmp(b.0));
+            for (model, pricing) in models {
+                let value = format!("input {}, output {}", pricing.input_per_1k, pricing.output_per_1k);
+                outln!("  {:20} {}", model.white(), value.green());
+            }
+            outln!();
         }
 
         // Config files section
-        println!("{}:", "Config Files".cyan());
+        outln!("{}:", "Config Files".cyan());
         let toml_path = toml_config_path();
         let toml_status = match (&self.toml_path, &toml_path) {
             (Some(p), _) => format!("{} (loaded)", p.display()),
             (None, Some(p)) => format!("{} {}", p.display(), file_status(p).dimmed()),
             (None, None) => "(path unavailable)".to_string(),
         };
-        println!("  {}: {}", "TOML".white(), toml_status);
+        outln!("  {}: {}", "TOML".white(), toml_status);
+
+        if let Some(ref p) = self.project_path {
+            outln!("  {}: {} (loaded)", "Project".white(), p.display());
+        }
 
         let json_path = json_config_path();
         let json_status = match (&self.json_path, &json_path) {
@@ -1517,11 +2579,11 @@ impl AppConfig {
             (None, Some(p)) => format!("{} {}", p.display(), file_status(p).dimmed()),
             (None, None) => "(path unavailable)".to_string(),
         };
-        println!("  {}: {}", "JSON".white(), json_status);
+        outln!("  {}: {}", "JSON".white(), json_status);
     }
 
-    /// Print configuration in JSON format.
-    pub fn print_json(&self) {
+    /// Print the effective configuration as JSON or YAML.
+    pub fn print_structured(&self, output_format: OutputFormat) {
         let mut global_settings = serde_json::Map::new();
         for field in GLOBAL_SETTINGS_METADATA {
             if let Some((value, source)) = self.get_global_field_display(field.name) {
@@ -1558,25 +2620,145 @@ impl AppConfig {
                         "source": source.to_string(),
                     }));
                 }
+                for (name, value) in &creds.extra_headers {
+                    let display_value = if http::is_sensitive_header(name) {
+                        mask_value(value)
+                    } else {
+                        value.clone()
+                    };
+                    let path = format!("{}.extra_headers.{}", meta.name, name);
+                    fields.insert(format!("extra_headers.{}", name), serde_json::json!({
+                        "value": display_value,
+                        "source": self.get_source(&path).to_string(),
+                    }));
+                }
                 provider_settings.insert(meta.name.to_string(), serde_json::Value::Object(fields));
             }
         }
 
-        let json = serde_json::json!({
+        let output = serde_json::json!({
             "global": global_settings,
             "providers": provider_settings,
+            "pricing": self.pricing,
             "config_files": {
                 "toml": {
                     "path": toml_config_path().map(|p| p.display().to_string()),
                     "exists": self.toml_path.is_some(),
                 },
+                "project": {
+                    "path": self.project_path.as_ref().map(|p| p.display().to_string()),
+                    "exists": self.project_path.is_some(),
+                },
                 "json": {
                     "path": json_config_path().map(|p| p.display().to_string()),
                     "exists": self.json_path.is_some(),
                 },
             },
         });
-        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        output_format.print_serialized(&output).unwrap();
+    }
+
+    /// Print every provider with its model and whether its required settings are present.
+    pub fn print_providers(&self, output_format: OutputFormat) {
+        let rows: Vec<(Provider, String, Vec<ValidationError>)> = PROVIDER_METADATA
+            .iter()
+            .map(|meta| {
+                let provider = Provider::from_str(meta.name).unwrap();
+                let model = self.effective_model_for(&provider);
+                let errors = self.validate_provider_for(&provider);
+                (provider, model, errors)
+            })
+            .collect();
+
+        match output_format {
+            OutputFormat::Human => {
+                const HEADING: &str = "Shell-AI Providers";
+                outln!("{}", HEADING.bold());
+                outln!("{}", "=".repeat(HEADING.len()));
+                let model_width = rows.iter().map(|(_, m, _)| m.len().max(9)).max().unwrap_or(9);
+                for (provider, model, errors) in &rows {
+                    let meta = provider.metadata();
+                    let active = self.provider.value.as_ref() == Some(provider);
+                    let marker = if active { "*".cyan().bold().to_string() } else { " ".to_string() };
+                    let model = if model.is_empty() { "(not set)".to_string() } else { model.clone() };
+                    let status = if errors.is_empty() {
+                        "[ready]".green().to_string()
+                    } else {
+                        "[not ready]".red().to_string()
+                    };
+                    outln!("{} {:10} {:width$} {}", marker, meta.name.white().bold(), model, status, width = model_width);
+                    for error in errors {
+                        outln!("             {} {}", format!("missing {}:", error.field).yellow(), error.hint.dimmed());
+                    }
+                }
+                outln!();
+                outln!("{}", "* = active provider".dimmed());
+            }
+            format => {
+                let providers: Vec<serde_json::Value> = rows
+                    .iter()
+                    .map(|(provider, model, errors)| {
+                        serde_json::json!({
+                            "name": provider.metadata().name,
+                            "active": self.provider.value.as_ref() == Some(provider),
+                            "model": model,
+                            "ready": errors.is_empty(),
+                            "missing": errors.iter().map(|e| serde_json::json!({
+                                "field": e.field,
+                                "hint": e.hint,
+                            })).collect::<Vec<_>>(),
+                        })
+                    })
+                    .collect();
+                format.print_serialized(&serde_json::json!({ "providers": providers })).unwrap();
+            }
+        }
+    }
+
+    /// Print the detected and effective locale, and common values to set.
+    pub fn print_locales(&self, output_format: OutputFormat) {
+        let detected = detect_system_locale();
+        let effective = resolve_locale(self.locale.value.as_deref());
+
+        match output_format {
+            OutputFormat::Human => {
+                const HEADING: &str = "Shell-AI Locales";
+                outln!("{}", HEADING.bold());
+                outln!("{}", "=".repeat(HEADING.len()));
+                let configured = match self.locale.value.as_deref() {
+                    None => "(not set, auto-detect)".to_string(),
+                    Some("") => "(empty, disabled)".to_string(),
+                    Some(locale) => locale.to_string(),
+                };
+                outln!("  {:10} {} [{}]", "Configured".white().bold(), configured, self.locale.source);
+                outln!("  {:10} {}", "Detected".white().bold(), detected.as_deref().unwrap_or("(none)"));
+                outln!("  {:10} {}", "Effective".white().bold(), effective.as_deref().unwrap_or("(none)"));
+                outln!();
+                outln!("{}", "Common values".cyan().bold());
+                for (tag, name) in COMMON_LOCALES {
+                    outln!("  {:8} {}", tag, name.dimmed());
+                }
+                outln!();
+                outln!(
+                    "{}",
+                    "Any language tag the model understands works. Set an empty string to send no locale.".dimmed()
+                );
+            }
+            format => {
+                format
+                    .print_serialized(&serde_json::json!({
+                        "configured": self.locale.value,
+                        "source": self.locale.source.to_string(),
+                        "detected": detected,
+                        "effective": effective,
+                        "common": COMMON_LOCALES.iter().map(|(tag, name)| serde_json::json!({
+                            "tag": tag,
+                            "name": name,
+                        })).collect::<Vec<_>>(),
+                    }))
+                    .unwrap();
+            }
+        }
     }
 
     // ========================================================================
@@ -1630,23 +2812,32 @@ impl AppConfig {
                 if field.required {
                     writeln!(output, "# REQUIRED").unwrap();
                 }
-                let placeholder = if field.sensitive { Some("your-api-key-here") } else { None };
-                write_field_default(&mut output, &field, placeholder);
+                let placeholder = field.sensitive.then(|| format!("your-{}-here", field.name.replace('_', "-")));
+                write_field_default(&mut output, &field, placeholder.as_deref());
                 writeln!(output).unwrap();
             }
             writeln!(output).unwrap();
         }
 
+        // Pricing section (a table per model, so only an example)
+        writeln!(output, "# ===========================================================================").unwrap();
+        writeln!(output, "# Pricing").unwrap();
+        writeln!(output, "# ===========================================================================").unwrap();
+        writeln!(output, "# Prices per 1,000 tokens, used to estimate spend with --show-usage and").unwrap();
+        writeln!(output, "# `shell-ai usage`. Add a section for each model you use.").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "# [pricing.\"gpt-5\"]").unwrap();
+        writeln!(output, "# input_per_1k = 0.00125").unwrap();
+        writeln!(output, "# output_per_1k = 0.01").unwrap();
+
         output
     }
 
     pub fn write_init_config(to_stdout: bool) -> anyhow::Result<()> {
-        use std::io::Write;
-
         let content = Self::generate_init_config();
 
         if to_stdout {
-            print!("{}", content);
+            out!("{}", content);
             return Ok(());
         }
 
@@ -1660,11 +2851,23 @@ impl AppConfig {
             );
         }
 
+        Self::create_config_file(&path, &content)?;
+
+        outln!("Created config file at: {}", path.display());
+        outln!("Edit this file to configure your providers.");
+
+        Ok(())
+    }
+
+    /// Write a new config file, readable only by the owner on Unix.
+    pub fn create_config_file(path: &std::path::Path, content: &str) -> anyhow::Result<()> {
+        use std::io::Write;
+
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let mut file = fs::File::create(&path)?;
+        let mut file = fs::File::create(path)?;
         file.write_all(content.as_bytes())?;
 
         #[cfg(unix)]
@@ -1672,12 +2875,54 @@ impl AppConfig {
             use std::os::unix::fs::PermissionsExt;
             let mut perms = file.metadata()?.permissions();
             perms.set_mode(0o600);
-            fs::set_permissions(&path, perms)?;
+            fs::set_permissions(path, perms)?;
+        }
+
+        Ok(())
+    }
+
+    /// Open config.toml in the user's editor, creating it first if missing.
+    ///
+    /// Uses `$EDITOR`, then `$VISUAL`, then `vi` (`notepad` on Windows). After the
+    /// editor exits, the file is re-parsed so mistakes are reported immediately.
+    pub fn edit_config() -> anyhow::Result<()> {
+        let path = toml_config_path()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+
+        if !path.exists() {
+            Self::create_config_file(&path, &Self::generate_init_config())?;
+            outln!("Created config file at: {}", path.display());
         }
 
-        println!("Created config file at: {}", path.display());
-        println!("Edit this file to configure your providers.");
+        let editor = [std::env::var("EDITOR"), std::env::var("VISUAL")]
+            .into_iter()
+            .flatten()
+            .find(|e| !e.trim().is_empty())
+            .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+
+        // Allow editors with arguments, e.g. EDITOR="code --wait"
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or("vi");
+        let status = std::process::Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to launch editor '{}': {}", editor, e))?;
+
+        if !status.success() {
+            anyhow::bail!("Editor '{}' exited with status: {}", editor, status);
+        }
+
+        if let Err(err) = check_toml_config(&path) {
+            anyhow::bail!(
+                "Config file has errors: {}\n\n{}\n\n\
+                 Hint: Run `shell-ai config edit` again to fix them.",
+                path.display(),
+                err
+            );
+        }
 
+        outln!("Config file OK: {}", path.display());
         Ok(())
     }
 
@@ -1685,45 +2930,62 @@ impl AppConfig {
         let provider_values: Vec<&str> = PROVIDER_METADATA.iter().map(|p| p.name).collect();
         let frontend_values: Vec<String> = Frontend::iter().map(|f| f.to_string()).collect();
         let output_format_values: Vec<String> = OutputFormat::iter().map(|o| o.to_string()).collect();
+        let explain_reference_values: Vec<String> = ExplainReference::iter().map(|r| r.to_string()).collect();
+        let json_schema_mode_values: Vec<String> = JsonSchemaMode::iter().map(|m| m.to_string()).collect();
+        let reasoning_effort_values: Vec<String> = ReasoningEffort::iter().map(|r| r.to_string()).collect();
+        let edit_mode_values: Vec<String> = EditMode::iter().map(|m| m.to_string()).collect();
+        let shell_values: Vec<String> = ShellType::iter().map(|s| s.to_string()).collect();
+        let ctx_capture_values: Vec<String> = CtxCapture::iter().map(|c| c.to_string()).collect();
+        let menu_action_values: Vec<String> = MenuAction::iter().map(|a| a.to_string()).collect();
+        let azure_auth_values: Vec<String> = AzureAuth::iter().map(|a| a.to_string()).collect();
 
         match output_format {
             OutputFormat::Human => {
-                println!("{}", "Shell-AI Configuration Schema".bold());
-                println!("{}", "=".repeat(60));
-                println!();
+                outln!("{}", "Shell-AI Configuration Schema".bold());
+                outln!("{}", "=".repeat(60));
+                outln!();
 
-                println!("{}", "Global Settings".cyan().bold());
-                println!("{}", "-".repeat(40));
+                outln!("{}", "Global Settings".cyan().bold());
+                outln!("{}", "-".repeat(40));
                 for field in GLOBAL_SETTINGS_METADATA {
                     if field.virtual_field {
                         continue;
                     }
-                    println!("  {}", field.name.white().bold());
-                    println!("    {}", field.description);
+                    outln!("  {}", field.name.white().bold());
+                    outln!("    {}", field.description);
                     if let Some(env) = field.env_var {
-                        println!("    Env: {}", env.green());
+                        outln!("    Env: {}", env.green());
                     }
                     if let Some(default) = field.default {
-                        println!("    Default: {}", default.dimmed());
+                        outln!("    Default: {}", default.dimmed());
                     }
-                    println!();
+                    outln!();
                 }
 
-                println!("{}", "Valid Values".cyan().bold());
-                println!("{}", "-".repeat(40));
-                println!("  {}: {}", "provider".white().bold(), provider_values.join(", "));
-                println!("  {}: {}", "frontend".white().bold(), frontend_values.join(", "));
-                println!("  {}: {}", "output_format".white().bold(), output_format_values.join(", "));
-                println!();
-
-                println!("{}", "Provider Settings".cyan().bold());
-                println!("{}", "-".repeat(40));
+                outln!("{}", "Valid Values".cyan().bold());
+                outln!("{}", "-".repeat(40));
+                outln!("  {}: {}", "provider".white().bold(), provider_values.join(", "));
+                outln!("  {}: {}", "frontend".white().bold(), frontend_values.join(", "));
+                outln!("  {}: {}", "output_format".white().bold(), output_format_values.join(", "));
+                outln!("  {}: {}", "explain_reference".white().bold(), explain_reference_values.join(", "));
+                outln!("  {}: {}", "json_schema_mode".white().bold(), json_schema_mode_values.join(", "));
+                outln!("  {}: {}", "reasoning_effort".white().bold(), reasoning_effort_values.join(", "));
+                outln!("  {}: {}", "edit_mode".white().bold(), edit_mode_values.join(", "));
+                outln!("  {}: {}", "shell".white().bold(), shell_values.join(", "));
+                outln!("  {}: {}", "exec_shell".white().bold(), shell_values.join(", "));
+                outln!("  {}: {}", "ctx_capture".white().bold(), ctx_capture_values.join(", "));
+                outln!("  {}: {}", "menu_actions".white().bold(), menu_action_values.join(", "));
+                outln!("  {}: {}", "azure.auth".white().bold(), azure_auth_values.join(", "));
+                outln!();
+
+                outln!("{}", "Provider Settings".cyan().bold());
+                outln!("{}", "-".repeat(40));
 
                 for provider in PROVIDER_METADATA {
-                    println!();
-                    println!("  {} [{}]", provider.display_name.white().bold(), provider.name);
-                    println!("    {}", provider.description.dimmed());
-                    println!();
+                    outln!();
+                    outln!("  {} [{}]", provider.display_name.white().bold(), provider.name);
+                    outln!("    {}", provider.description.dimmed());
+                    outln!();
 
                     for field in provider.all_fields() {
                         let req_marker = if field.required {
@@ -1731,19 +2993,19 @@ impl AppConfig {
                         } else {
                             String::new()
                         };
-                        println!("    {}{}", field.name.white(), req_marker);
-                        println!("      {}", field.description);
+                        outln!("    {}{}", field.name.white(), req_marker);
+                        outln!("      {}", field.description);
                         if let Some(env) = field.env_var {
-                            println!("      Env: {}", env.green());
+                            outln!("      Env: {}", env.green());
                         }
                         if let Some(default) = field.default {
-                            println!("      Default: {}", default.dimmed());
+                            outln!("      Default: {}", default.dimmed());
                         }
                     }
                 }
-                println!();
+                outln!();
             }
-            OutputFormat::Json => {
+            OutputFormat::Json | OutputFormat::Yaml => {
                 let schema = serde_json::json!({
                     "global_settings": GLOBAL_SETTINGS_METADATA.iter()
                         .filter(|f| !f.virtual_field)
@@ -1760,6 +3022,15 @@ impl AppConfig {
                         "provider": provider_values,
                         "frontend": frontend_values,
                         "output_format": output_format_values,
+                        "explain_reference": explain_reference_values,
+                        "json_schema_mode": json_schema_mode_values,
+                        "reasoning_effort": reasoning_effort_values,
+                        "edit_mode": edit_mode_values,
+                        "shell": shell_values,
+                        "exec_shell": shell_values,
+                        "ctx_capture": ctx_capture_values,
+                        "menu_actions": menu_action_values,
+                        "azure.auth": azure_auth_values,
                     },
                     "providers": PROVIDER_METADATA.iter().map(|p| {
                         serde_json::json!({
@@ -1779,7 +3050,7 @@ impl AppConfig {
                     }).collect::<Vec<_>>(),
                 });
 
-                println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+                output_format.print_serialized(&schema).unwrap();
             }
         }
     }
@@ -1787,7 +3058,7 @@ impl AppConfig {
 
 fn print_config_line(name: &str, value: &str, source: ConfigSource) {
     let source_str = format!("[{}]", source);
-    println!(
+    outln!(
         "  {:20} {:20} {}",
         name.white(),
         value.green(),
@@ -1798,7 +3069,7 @@ fn print_config_line(name: &str, value: &str, source: ConfigSource) {
 fn print_config_line_deprecated(name: &str, value: &str, source: ConfigSource, deprecated: bool) {
     let source_str = format!("[{}]", source);
     let deprecated_marker = if deprecated { " (deprecated)".yellow().to_string() } else { String::new() };
-    println!(
+    outln!(
         "  {:20} {:20} {}{}",
         name.white(),
         value.green(),
@@ -1849,20 +3120,211 @@ fn write_field_default(output: &mut String, field: &FieldMeta, placeholder: Opti
     }
 }
 
+/// The `SHAI_CONFIG_DIR` override, which holds all of shell-ai's files.
+fn config_dir_override() -> Option<PathBuf> {
+    std::env::var_os(env::SHAI_CONFIG_DIR)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The directory named by an XDG base directory variable, honored on every
+/// platform when set to an absolute path, otherwise `fallback`.
+fn xdg_dir(var: &str, fallback: fn() -> Option<PathBuf>) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(fallback)
+}
+
+/// `shell-ai` under the directory named by an XDG base directory variable,
+/// or under the `platform` directory.
+///
+/// Files created before XDG variables were honored on macOS and Windows live
+/// under the platform directory, so it is kept while it exists and the XDG
+/// one doesn't.
+fn app_dir(var: &str, platform: fn() -> Option<PathBuf>) -> Option<PathBuf> {
+    let platform_dir = platform().map(|p| p.join("shell-ai"));
+    let xdg_dir = std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .map(|p| p.join("shell-ai"));
+    match xdg_dir {
+        Some(dir) => Some(prefer_existing(dir, platform_dir)),
+        None => platform_dir,
+    }
+}
+
+/// `preferred`, unless only `legacy` exists yet.
+fn prefer_existing(preferred: PathBuf, legacy: Option<PathBuf>) -> PathBuf {
+    match legacy {
+        Some(legacy) if !preferred.exists() && legacy.exists() => legacy,
+        _ => preferred,
+    }
+}
+
+/// Directory for config files and shell integration scripts.
+///
+/// `SHAI_CONFIG_DIR` if set, else `shell-ai` under `XDG_CONFIG_HOME` or the
+/// platform config directory.
+pub fn config_dir() -> Option<PathBuf> {
+    config_dir_override().or_else(|| app_dir("XDG_CONFIG_HOME", dirs::config_dir))
+}
+
+/// Directory for history and usage records.
+///
+/// `SHAI_CONFIG_DIR` if set, else `shell-ai` under `XDG_DATA_HOME` or the
+/// platform data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    config_dir_override().or_else(|| app_dir("XDG_DATA_HOME", dirs::data_dir))
+}
+
+/// Directory for caches.
+///
+/// `cache` under `SHAI_CONFIG_DIR` if set, else `shell-ai` under
+/// `XDG_CACHE_HOME` or the platform cache directory.
+pub fn cache_dir() -> Option<PathBuf> {
+    match config_dir_override() {
+        Some(dir) => Some(dir.join("cache")),
+        None => Some(xdg_dir("XDG_CACHE_HOME", dirs::cache_dir)?.join("shell-ai")),
+    }
+}
+
 pub fn toml_config_path() -> Option<PathBuf> {
-    let mut base = dirs::config_dir()?;
-    base.push("shell-ai");
+    let mut base = config_dir()?;
     base.push("config.toml");
     Some(base)
 }
 
+/// File name of the per-project config file.
+pub const PROJECT_CONFIG_FILE: &str = ".shell-ai.toml";
+
+/// Find the project config file for the current directory.
+///
+/// Looks for [`PROJECT_CONFIG_FILE`] in the current directory and each parent
+/// up to (but not including) the home directory, or up to the root outside
+/// the home directory. Returns `None` when `SHAI_NO_PROJECT_CONFIG=true`.
+pub fn project_config_path() -> Option<PathBuf> {
+    if matches!(std::env::var(env::SHAI_NO_PROJECT_CONFIG), Ok(v) if v.to_lowercase() == "true" || v == "1") {
+        return None;
+    }
+
+    let cwd = std::env::current_dir().ok()?;
+    let home = dirs::home_dir();
+    cwd.ancestors()
+        .take_while(|dir| Some(*dir) != home.as_deref())
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Whether `SHAI_TRUST_PROJECT_CONFIG=true` allows project config files to
+/// set everything the global config can.
+fn project_config_trusted() -> bool {
+    matches!(std::env::var(env::SHAI_TRUST_PROJECT_CONFIG), Ok(v) if v.to_lowercase() == "true" || v == "1")
+}
+
+/// Settings an untrusted project config may set. Anything that chooses where
+/// requests go, reads or writes files, or skips a safety check is left out.
+const PROJECT_SETTINGS: &[&str] = &[
+    "model",
+    "fallback_model",
+    "temperature",
+    "suggestion_count",
+    "suggest_concurrency",
+    "suggest_system_prompt",
+    "shell",
+    "auto_copy",
+    "ctx_max_chars",
+    "ctx_capture",
+    "menu_actions",
+    "frontend",
+    "output_format",
+    "edit_mode",
+    "progress",
+    "spinner_frames",
+    "spinner_template",
+    "show_usage",
+    "show_timing",
+    "max_reference_chars",
+    "explain_reference",
+    "reference_timeout",
+    "citation_min_confidence",
+    "max_tokens",
+    "request_timeout",
+    "max_retries",
+    "initial_backoff_ms",
+    "json_schema_mode",
+    "reasoning_effort",
+    "offline",
+    "locale",
+    "pricing",
+];
+
+/// Settings an untrusted project config may set in a provider's section.
+const PROJECT_PROVIDER_SETTINGS: &[&str] = &["model", "max_tokens", "temperature"];
+
+/// Remove everything but [`PROJECT_SETTINGS`] and each provider's
+/// [`PROJECT_PROVIDER_SETTINGS`] from an untrusted project config layer,
+/// returning the paths of the settings that were removed.
+fn strip_untrusted_project_settings(layer: &mut serde_json::Value) -> Vec<String> {
+    let mut ignored = Vec::new();
+    let Some(obj) = layer.as_object_mut() else {
+        return ignored;
+    };
+
+    obj.retain(|key, value| {
+        let is_provider = Provider::iter().any(|p| p.metadata().name == key);
+        match value.as_object_mut() {
+            Some(section) if is_provider => {
+                section.retain(|field, _| {
+                    let keep = PROJECT_PROVIDER_SETTINGS.contains(&field.as_str());
+                    if !keep {
+                        ignored.push(format!("{}.{}", key, field));
+                    }
+                    keep
+                });
+                true
+            }
+            _ => {
+                let keep = PROJECT_SETTINGS.contains(&key.as_str());
+                if !keep {
+                    ignored.push(key.clone());
+                }
+                keep
+            }
+        }
+    });
+    ignored
+}
+
+/// Whether `region` looks like an AWS region (`us-east-1`), so it can't change
+/// the Bedrock endpoint's host beyond picking the region.
+fn is_aws_region(region: &str) -> bool {
+    !region.is_empty() && region.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
 pub fn json_config_path() -> Option<PathBuf> {
-    let mut base = dirs::config_dir()?;
-    base.push("shell-ai");
+    let mut base = config_dir()?;
     base.push("config.json");
     Some(base)
 }
 
+/// Parse a TOML config file and check its values against the config schema.
+///
+/// Returns a formatted error message on the first syntax or type error.
+fn check_toml_config(path: &std::path::Path) -> Result<(), String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let toml_value: toml::Value = toml::from_str(&data).map_err(|e| e.to_string())?;
+
+    let mut builder = ConfigBuilder::new();
+    builder.merge_layer(&toml_to_json(&toml_value), ConfigSource::TomlFile);
+
+    let config_str = builder.config.to_string();
+    let mut deserializer = serde_json::Deserializer::from_str(&config_str);
+    serde_path_to_error::deserialize::<_, TomlConfig>(&mut deserializer)
+        .map(|_| ())
+        .map_err(|e| format_serde_error(e, &builder))
+}
+
 enum TomlJsonLoadResult {
     Loaded(serde_json::Value, PathBuf),
     NotFound,
@@ -1870,11 +3332,13 @@ enum TomlJsonLoadResult {
 }
 
 fn load_toml_as_json() -> TomlJsonLoadResult {
-    let path = match toml_config_path() {
-        Some(p) => p,
-        None => return TomlJsonLoadResult::NotFound,
-    };
+    match toml_config_path() {
+        Some(path) => load_toml_file_as_json(path),
+        None => TomlJsonLoadResult::NotFound,
+    }
+}
 
+fn load_toml_file_as_json(path: PathBuf) -> TomlJsonLoadResult {
     let data = match fs::read_to_string(&path) {
         Ok(d) => d,
         Err(_) => return TomlJsonLoadResult::NotFound,
@@ -1951,6 +3415,43 @@ pub fn detect_system_locale() -> Option<String> {
     None
 }
 
+/// Locales listed by `shell-ai config locales`, as examples of the accepted format.
+const COMMON_LOCALES: &[(&str, &str)] = &[
+    ("en_US", "English (United States)"),
+    ("en_GB", "English (United Kingdom)"),
+    ("de_DE", "German"),
+    ("da_DK", "Danish"),
+    ("es_ES", "Spanish (Spain)"),
+    ("es_MX", "Spanish (Mexico)"),
+    ("fr_FR", "French"),
+    ("it_IT", "Italian"),
+    ("ja_JP", "Japanese"),
+    ("ko_KR", "Korean"),
+    ("nl_NL", "Dutch"),
+    ("pl_PL", "Polish"),
+    ("pt_BR", "Portuguese (Brazil)"),
+    ("ru_RU", "Russian"),
+    ("sv_SE", "Swedish"),
+    ("tr_TR", "Turkish"),
+    ("uk_UA", "Ukrainian"),
+    ("zh_CN", "Chinese (Simplified)"),
+    ("zh_TW", "Chinese (Traditional)"),
+];
+
+/// Whether a locale looks like a language tag: a 2-3 letter language code
+/// and optional subtags, separated by `_` or `-` (e.g., `fr`, `pt_BR`,
+/// `zh-Hant-TW`). A POSIX encoding or modifier (`.UTF-8`, `@latin`) is allowed.
+///
+/// Only used to warn about likely typos; any value is still sent as is.
+pub fn is_well_formed_locale(locale: &str) -> bool {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default();
+    let mut subtags = tag.split(['_', '-']);
+    let language_ok = subtags
+        .next()
+        .is_some_and(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    language_ok && subtags.all(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
 /// Resolve the effective locale value.
 /// - None → auto-detect from system LANG/LC_ALL
 /// - Some("") → disabled, no locale hint
@@ -1962,3 +3463,99 @@ pub fn resolve_locale(config_value: Option<&str>) -> Option<String> {
         Some(locale) => Some(locale.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untrusted_project_config_keeps_only_harmless_settings() {
+        let mut layer = serde_json::json!({
+            "provider": "openai",
+            "model": "gpt-4o",
+            "suggestion_count": 3,
+            "log_file": "/home/user/.bashrc",
+            "suggest_prompt_file": "/home/user/.ssh/id_rsa",
+            "exec_shell": "bash",
+            "allow_execute": true,
+            "confirm_before_execute": false,
+            "debug": "trace",
+            "openai": {
+                "api_base": "https://attacker.example",
+                "api_key": "sk-project",
+                "model": "gpt-4o-mini",
+                "organization": "org-other",
+                "extra_headers": { "X-Team": "infra" }
+            },
+            "azure": { "deployment_name": "other", "api_version": "2024-01-01", "auth": "token" },
+            "bedrock": { "region": "evil.example/x?", "temperature": 0.2 },
+            "ollama": { "chat_path": "/proxy" }
+        });
+
+        let ignored = strip_untrusted_project_settings(&mut layer);
+
+        assert_eq!(
+            ignored,
+            [
+                "allow_execute",
+                "azure.api_version",
+                "azure.auth",
+                "azure.deployment_name",
+                "bedrock.region",
+                "confirm_before_execute",
+                "debug",
+                "exec_shell",
+                "log_file",
+                "ollama.chat_path",
+                "openai.api_base",
+                "openai.api_key",
+                "openai.extra_headers",
+                "openai.organization",
+                "provider",
+                "suggest_prompt_file",
+            ]
+        );
+        assert_eq!(
+            layer,
+            serde_json::json!({
+                "model": "gpt-4o",
+                "suggestion_count": 3,
+                "openai": { "model": "gpt-4o-mini" },
+                "azure": {},
+                "bedrock": { "temperature": 0.2 },
+                "ollama": {}
+            })
+        );
+    }
+
+    #[test]
+    fn bedrock_regions_cannot_change_the_host() {
+        assert!(is_aws_region("us-east-1"));
+        assert!(!is_aws_region(""));
+        assert!(!is_aws_region("evil.example/x?"));
+        assert!(!is_aws_region("US-EAST-1"));
+    }
+
+    #[test]
+    fn keeps_the_platform_dir_until_the_xdg_dir_exists() {
+        let root = std::env::temp_dir().join(format!("shell-ai-dirs-{}", std::process::id()));
+        let xdg = root.join("xdg");
+        let platform = root.join("platform");
+
+        assert_eq!(prefer_existing(xdg.clone(), Some(platform.clone())), xdg);
+        fs::create_dir_all(&platform).unwrap();
+        assert_eq!(prefer_existing(xdg.clone(), Some(platform.clone())), platform);
+        fs::create_dir_all(&xdg).unwrap();
+        assert_eq!(prefer_existing(xdg.clone(), Some(platform)), xdg);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn ctx_max_chars_limit_follows_max_tokens() {
+        assert_eq!(ctx_max_chars_limit(None), 100_000);
+        assert_eq!(ctx_max_chars_limit(Some(1000)), 4000);
+        assert_eq!(ctx_max_chars_limit(Some(10)), MIN_CTX_MAX_CHARS);
+        assert_eq!(ctx_max_chars_limit(Some(u32::MAX)), u32::MAX);
+    }
+}
@@ -0,0 +1,82 @@
+//! UTC calendar conversions for Unix timestamps.
+//!
+//! History timestamps, `Retry-After` dates, and SigV4 signing all need a
+//! calendar date without pulling in a date-time crate, so they share Howard
+//! Hinnant's civil-from-days and days-from-civil algorithms from here.
+
+/// A UTC calendar date and time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+}
+
+impl UtcDateTime {
+    /// Break seconds since the Unix epoch into a UTC date and time.
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let rem = (secs % 86_400) as i64;
+
+        // Civil-from-days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem % 3600 / 60,
+            second: rem % 60,
+        }
+    }
+
+    /// Seconds since the Unix epoch, or `None` for times before it.
+    pub fn to_unix(self) -> Option<u64> {
+        // Days-from-civil
+        let y = if self.month <= 2 { self.year - 1 } else { self.year };
+        let era = y.div_euclid(400);
+        let yoe = y.rem_euclid(400);
+        let mp = (self.month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        u64::try_from(days * 86_400 + self.hour * 3600 + self.minute * 60 + self.second).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_known_timestamps() {
+        let cases = [
+            (0, UtcDateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 }),
+            (951_868_799, UtcDateTime { year: 2000, month: 2, day: 29, hour: 23, minute: 59, second: 59 }),
+            (1_440_938_160, UtcDateTime { year: 2015, month: 8, day: 30, hour: 12, minute: 36, second: 0 }),
+        ];
+        for (secs, date) in cases {
+            assert_eq!(UtcDateTime::from_unix(secs), date);
+            assert_eq!(date.to_unix(), Some(secs));
+        }
+    }
+
+    #[test]
+    fn rejects_times_before_the_epoch() {
+        let date = UtcDateTime { year: 1969, month: 12, day: 31, hour: 23, minute: 59, second: 59 };
+        assert_eq!(date.to_unix(), None);
+    }
+}
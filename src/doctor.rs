@@ -0,0 +1,182 @@
+//! Self-diagnosis for common setup problems.
+//!
+//! `shell-ai doctor` checks the configuration, credentials, provider
+//! connectivity, and optional helper tools, and prints a pass/warn/fail report.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{env, AppConfig, AzureAuth, OutputFormat, Provider};
+use crate::explain;
+use crate::http;
+use crate::output::outln;
+use crate::provider::{self, ProviderConfig};
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+}
+
+fn check_config_files(config: &AppConfig) -> Check {
+    let loaded: Vec<String> = [&config.toml_path, &config.project_path, &config.json_path]
+        .into_iter()
+        .flatten()
+        .map(|p| p.display().to_string())
+        .collect();
+
+    if loaded.is_empty() {
+        Check::new("config files", Status::Warn, "none loaded (using environment variables and defaults)")
+    } else {
+        Check::new("config files", Status::Pass, loaded.join(", "))
+    }
+}
+
+fn check_provider(config: &AppConfig) -> Check {
+    match &config.provider.value {
+        Some(provider) => Check::new("provider", Status::Pass, provider.to_string()),
+        None => Check::new("provider", Status::Fail, "not configured (run `shell-ai setup`)"),
+    }
+}
+
+fn check_credentials(config: &AppConfig) -> Check {
+    if config.provider.value.is_none() {
+        return Check::new("credentials", Status::Fail, "skipped (no provider)");
+    }
+
+    let errors = config.validate_provider();
+    if errors.is_empty() {
+        // Validation leaves fetching an Azure Entra ID token to the first request
+        let token_auth = config.provider.value == Some(Provider::Azure)
+            && config.current_provider_credentials().and_then(|c| c.auth).unwrap_or_default() == AzureAuth::Token;
+        if token_auth {
+            if let Err(e) = provider::azure_ad_token() {
+                return Check::new(
+                    "credentials",
+                    Status::Fail,
+                    format!("no Entra ID access token: {} (set {} or sign in with `az login`)", e, env::AZURE_OPENAI_AD_TOKEN),
+                );
+            }
+        }
+        Check::new("credentials", Status::Pass, "all required fields present")
+    } else {
+        let missing: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{} ({})", e.field, e.hint))
+            .collect();
+        Check::new("credentials", Status::Fail, format!("missing {}", missing.join("; ")))
+    }
+}
+
+/// Reach the provider with a cheap models-list request.
+fn check_endpoint(config: &AppConfig) -> Check {
+    let validated = match config.validate() {
+        Ok(v) => v,
+        Err(_) => return Check::new("endpoint", Status::Fail, "skipped (configuration incomplete)"),
+    };
+
+    let provider = ProviderConfig::from_validated(&validated);
+    if provider.mock {
+        return Check::new("endpoint", Status::Warn, "none (the mock provider answers in-process with canned responses)");
+    }
+    if provider.aws.is_some() {
+        // Bedrock needs signed requests, and bedrock-runtime has no models list to probe
+        return Check::new("endpoint", Status::Warn, format!("{} (not probed for Bedrock)", provider.base_url));
+    }
+    let url = provider.models_url();
+    let extra_headers = provider.extra_headers_ref();
+
+    let bearer_token = match provider.bearer_token() {
+        Ok(token) => token,
+        Err(_) => return Check::new("endpoint", Status::Fail, "skipped (no access token)"),
+    };
+
+    let result = http::get_json(&url, bearer_token, &extra_headers, &provider.request);
+    // How long the probe took helps tell a slow network from a slow model
+    let timing = http::last_timing().map(|t| format!("; {}", t)).unwrap_or_default();
+
+    match result {
+        Ok((status, _)) if (200..300).contains(&status) => {
+            Check::new("endpoint", Status::Pass, format!("{} (HTTP {}{})", url, status, timing))
+        }
+        Ok((status @ (401 | 403), _)) => Check::new(
+            "endpoint",
+            Status::Fail,
+            format!("{} rejected the credentials (HTTP {}{})", url, status, timing),
+        ),
+        Ok((status, _)) => Check::new(
+            "endpoint",
+            Status::Warn,
+            format!("{} is reachable but returned HTTP {}{}", url, status, timing),
+        ),
+        Err(e) => Check::new("endpoint", Status::Fail, format!("{}: {}{}", url, e, timing)),
+    }
+}
+
+fn check_tool(name: &'static str, purpose: &str) -> Check {
+    match explain::find_in_path(name) {
+        Some(path) => Check::new(name, Status::Pass, path.display().to_string()),
+        None => Check::new(name, Status::Warn, format!("not found on PATH ({})", purpose)),
+    }
+}
+
+fn check_clipboard() -> Check {
+    match arboard::Clipboard::new() {
+        Ok(_) => Check::new("clipboard", Status::Pass, "available"),
+        Err(e) => Check::new("clipboard", Status::Warn, format!("unavailable: {}", e)),
+    }
+}
+
+fn print_human(checks: &[Check]) {
+    outln!("{}", "Shell-AI Doctor".bold());
+    outln!("{}", "=".repeat(60));
+    for check in checks {
+        let label = match check.status {
+            Status::Pass => "[pass]".green(),
+            Status::Warn => "[warn]".yellow(),
+            Status::Fail => "[fail]".red(),
+        };
+        outln!("{} {:14} {}", label, check.name.white().bold(), check.detail);
+    }
+}
+
+/// Main entry point for the doctor subcommand.
+pub fn run(config: &AppConfig, output_format: OutputFormat) -> Result<()> {
+    let checks = vec![
+        check_config_files(config),
+        check_provider(config),
+        check_credentials(config),
+        check_endpoint(config),
+        check_tool("man", "used by explain"),
+        check_tool("tldr", "optional explain reference"),
+        check_clipboard(),
+    ];
+
+    match output_format {
+        OutputFormat::Human => print_human(&checks),
+        format => format.print_serialized(&checks)?,
+    }
+
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failures > 0 {
+        return Err(anyhow!("{} check(s) failed", failures));
+    }
+    Ok(())
+}
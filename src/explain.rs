@@ -1,15 +1,37 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use colored::Colorize;
 use is_terminal::IsTerminal;
 use serde::{Deserialize, Serialize};
 use std::io::Read;
-use std::process::{Command, Stdio};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
 use serde_json::json;
 
-use crate::config::{resolve_locale, OutputFormat, ValidatedConfig};
+use crate::builtins;
+use crate::clipboard;
+use crate::config::{resolve_locale, ExplainReference, OutputFormat, ValidatedConfig};
 use crate::http;
+use crate::man_cache;
+use crate::output::outln;
 use crate::progress::Progress;
 use crate::provider::ProviderConfig;
+use crate::ui;
+
+/// Citation confidence at or above which the citation is shown as high confidence.
+const HIGH_CONFIDENCE: f32 = 0.8;
+
+/// Citation confidence at or above which the citation is shown as medium confidence.
+const MEDIUM_CONFIDENCE: f32 = 0.5;
+
+/// Where a reference's documentation came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReferenceSource {
+    ManPage,
+    HelpText,
+    Tldr,
+    Builtin,
+}
 
 /// A man page reference with metadata for sorting.
 #[derive(Debug, Clone)]
@@ -17,6 +39,7 @@ struct ManReference {
     command: String,
     content: String,
     char_count: usize,
+    source: ReferenceSource,
 }
 
 /// Extract potential command names from shell syntax.
@@ -42,6 +65,17 @@ fn extract_command_names(shell_cmd: &str) -> Vec<String> {
         let words: Vec<&str> = segment.split_whitespace().collect();
 
         for word in words {
+            // Keywords like `if` or `do` are followed by the actual command
+            if builtins::is_command_prefix(word) {
+                if builtins::reference(word).is_some() {
+                    commands.push(word.to_string());
+                }
+                continue;
+            }
+            // Nothing after `done`, `fi`, etc. is a command
+            if builtins::is_closing_keyword(word) {
+                break;
+            }
             // Skip env var assignments (VAR=value)
             if word.contains('=') && !word.starts_with('-') {
                 continue;
@@ -71,42 +105,214 @@ fn extract_command_names(shell_cmd: &str) -> Vec<String> {
     commands
 }
 
-/// Check if a man page exists for a command using `man -w`.
-fn has_man_page(cmd: &str) -> bool {
-    Command::new("man")
-        .args(["-w", cmd])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or_else(|e| {
-            log::debug!("Failed to check man page for '{}': {}", cmd, e);
-            false
-        })
+/// Locate the man page file for a command using `man -w`.
+/// Returns None if no man page exists or `man` doesn't answer within `timeout`.
+fn man_page_path(cmd: &str, timeout: Duration) -> Option<PathBuf> {
+    let output = run_with_timeout(Command::new("man").args(["-w", cmd]), timeout)?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| PathBuf::from(line.trim()))
 }
 
 /// Fetch man page for a command, extracting primarily the OPTIONS section.
-/// Returns None if the command has no man page or fetching fails.
-fn get_man_page(cmd: &str, max_chars: usize) -> Option<String> {
-    // First check if man page exists
-    if !has_man_page(cmd) {
-        return None;
+/// Falls back to `<cmd> --help` for [`HELP_TEXT_COMMANDS`] when no man page exists.
+/// Returns None if neither source yields any text.
+/// When `use_cache` is true, the extracted reference is read from and stored
+/// in the on-disk man page cache. Each subprocess is limited to `timeout`.
+fn get_man_page(cmd: &str, max_chars: usize, use_cache: bool, timeout: Duration) -> Option<(String, ReferenceSource)> {
+    let Some(man_path) = man_page_path(cmd, timeout) else {
+        return get_help_text(cmd, max_chars, use_cache, timeout)
+            .map(|text| (text, ReferenceSource::HelpText));
+    };
+
+    if use_cache {
+        if let Some(cached) = man_cache::get(cmd, &man_path, max_chars) {
+            return Some((cached, ReferenceSource::ManPage));
+        }
     }
 
-    // Fetch the man page with wide width to reduce line breaks (saves tokens)
-    let output = match Command::new("man")
-        .arg(cmd)
-        .env("MANWIDTH", "100000")
-        .env("LANG", "C")
-        .env("LC_ALL", "C")
-        .output()
-    {
-        Ok(o) => o,
-        Err(e) => {
-            log::debug!("Failed to run man command for '{}': {}", cmd, e);
-            return None;
+    let reference = fetch_man_page(cmd, max_chars, timeout)?;
+    if use_cache {
+        man_cache::put(cmd, &man_path, max_chars, &reference);
+    }
+    Some((reference, ReferenceSource::ManPage))
+}
+
+/// Find an executable by name on `PATH`.
+pub fn find_in_path(cmd: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var).find_map(|dir| {
+        let candidate = dir.join(cmd);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        #[cfg(windows)]
+        {
+            let exe = dir.join(format!("{}.exe", cmd));
+            if exe.is_file() {
+                return Some(exe);
+            }
         }
+        None
+    })
+}
+
+/// Run a command, killing it if it does not finish within `timeout`.
+/// Returns None if the command could not be started or timed out.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Option<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| log::debug!("Failed to spawn {:?}: {}", command.get_program(), e))
+        .ok()?;
+
+    // Drain pipes on separate threads so a chatty child can't block on a full buffer
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut p) = pipe {
+                let _ = p.read_to_end(&mut buf);
+            }
+            buf
+        })
     };
+    let stdout_reader = read_pipe(stdout.map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr_reader = read_pipe(stderr.map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                log::debug!(
+                    "{:?} timed out after {:?}, killing it and dropping its reference",
+                    command.get_program(),
+                    timeout
+                );
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Err(e) => {
+                log::debug!("Failed to wait for {:?}: {}", command.get_program(), e);
+                return None;
+            }
+        }
+    };
+
+    Some(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// Commands whose `--help` output may be fetched when they have no man page.
+/// Any other program could treat `--help` as something else, so it isn't run
+/// just because it appears in a suggested command.
+const HELP_TEXT_COMMANDS: &[&str] = &[
+    "aws",
+    "az",
+    "bun",
+    "cargo",
+    "deno",
+    "docker",
+    "gcloud",
+    "gh",
+    "go",
+    "helm",
+    "jq",
+    "kubectl",
+    "node",
+    "npm",
+    "npx",
+    "pip",
+    "pip3",
+    "pnpm",
+    "podman",
+    "poetry",
+    "rustup",
+    "terraform",
+    "uv",
+    "yarn",
+    "yq",
+];
+
+/// Fetch `<cmd> --help` output as a reference.
+///
+/// Only runs [`HELP_TEXT_COMMANDS`] found on `PATH`, with pagers disabled and a
+/// wall-clock timeout so tools that hang or open a pager can't stall explain.
+fn get_help_text(cmd: &str, max_chars: usize, use_cache: bool, timeout: Duration) -> Option<String> {
+    if !HELP_TEXT_COMMANDS.contains(&cmd) {
+        return None;
+    }
+    let binary = find_in_path(cmd)?;
+    let cache_key = format!("{}--help", cmd);
+
+    if use_cache {
+        if let Some(cached) = man_cache::get(&cache_key, &binary, max_chars) {
+            return Some(cached);
+        }
+    }
+
+    let output = run_with_timeout(
+        Command::new(&binary)
+            .arg("--help")
+            .env("PAGER", "cat")
+            .env("GIT_PAGER", "cat")
+            .env("MANPAGER", "cat")
+            .env("LANG", "C")
+            .env("LC_ALL", "C"),
+        timeout,
+    )?;
+
+    // Many tools print help to stderr, and some exit non-zero after printing it
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&stderr);
+    }
+
+    let capped = truncate_to_limit(text.trim(), max_chars);
+    if capped.is_empty() {
+        return None;
+    }
+    let reference = format!("# {} --help (help output)\n\n{}", cmd, capped);
+
+    if use_cache {
+        man_cache::put(&cache_key, &binary, max_chars, &reference);
+    }
+    Some(reference)
+}
+
+/// Run `man` for a command and extract the reference text.
+/// Pagers are disabled and `man` is killed if it runs longer than `timeout`.
+fn fetch_man_page(cmd: &str, max_chars: usize, timeout: Duration) -> Option<String> {
+    // Fetch the man page with wide width to reduce line breaks (saves tokens)
+    let output = run_with_timeout(
+        Command::new("man")
+            .arg(cmd)
+            .env("MANWIDTH", "100000")
+            .env("PAGER", "cat")
+            .env("MANPAGER", "cat")
+            .env("LANG", "C")
+            .env("LC_ALL", "C"),
+        timeout,
+    )?;
 
     if !output.status.success() {
         return None;
@@ -186,23 +392,118 @@ fn truncate_to_limit(text: &str, max_chars: usize) -> String {
     }
 }
 
+/// Strip ANSI escape sequences (colors, cursor movement) from text.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                // Skip parameters until the final byte (@ through ~)
+                for next in chars.by_ref() {
+                    if ('@'..='~').contains(&next) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Fetch the tldr page for a command as a compact reference.
+/// Returns None if `tldr` isn't installed or has no page for the command.
+fn get_tldr_page(cmd: &str, max_chars: usize, timeout: Duration) -> Option<String> {
+    let tldr = find_in_path("tldr")?;
+    let output = run_with_timeout(
+        Command::new(tldr)
+            .arg(cmd)
+            .env("NO_COLOR", "1")
+            .env("TERM", "dumb")
+            .env("PAGER", "cat"),
+        timeout,
+    )?;
+
+    if !output.status.success() {
+        log::debug!("No tldr page for '{}'", cmd);
+        return None;
+    }
+
+    let text = strip_ansi(&String::from_utf8_lossy(&output.stdout));
+    let capped = truncate_to_limit(text.trim(), max_chars);
+    if capped.is_empty() {
+        None
+    } else {
+        Some(format!("# {} (tldr)\n\n{}", cmd, capped))
+    }
+}
+
 /// Gather man page references for commands in a shell command string.
-fn gather_man_references(shell_cmd: &str, max_total_chars: u32) -> Vec<ManReference> {
+///
+/// `mode` selects man pages, tldr pages (falling back to man pages per
+/// command when no tldr page exists), or both. Each `man`, `--help`, or
+/// `tldr` subprocess is limited to `timeout`.
+///
+/// `on_command` is called with each command name, its 1-based position, and
+/// the total before its documentation is read, for progress reporting.
+fn gather_man_references(
+    shell_cmd: &str,
+    max_total_chars: u32,
+    use_cache: bool,
+    mode: ExplainReference,
+    timeout: Duration,
+    mut on_command: impl FnMut(&str, usize, usize),
+) -> Vec<ManReference> {
     let commands = extract_command_names(shell_cmd);
     let max_per_page = (max_total_chars as usize) / 2; // Cap each page at half of total
 
-    let mut references: Vec<ManReference> = commands
-        .iter()
-        .filter_map(|cmd| {
-            get_man_page(cmd, max_per_page).map(|content| ManReference {
+    let mut references: Vec<ManReference> = Vec::new();
+    let mut builtins_seen: Vec<&str> = Vec::new();
+    for (i, cmd) in commands.iter().enumerate() {
+        on_command(cmd, i + 1, commands.len());
+        let mut push = |content: String, source: ReferenceSource| {
+            references.push(ManReference {
                 command: cmd.clone(),
                 char_count: content.len(),
                 content,
-            })
-        })
-        .collect();
+                source,
+            });
+        };
+
+        // Builtins and keywords have no man page of their own; use the embedded reference
+        if let Some(text) = builtins::reference(cmd) {
+            // `while` and `until` share an entry
+            if !builtins_seen.contains(&text) {
+                builtins_seen.push(text);
+                let content = format!("# {} (shell builtin or keyword)\n\n{}", cmd, text);
+                push(truncate_to_limit(&content, max_per_page), ReferenceSource::Builtin);
+            }
+            continue;
+        }
 
-    // Sort by size ascending (shortest first = dropped first when over limit)
+        let tldr = match mode {
+            ExplainReference::Man => None,
+            ExplainReference::Tldr | ExplainReference::Both => get_tldr_page(cmd, max_per_page, timeout),
+        };
+        let want_man = match mode {
+            ExplainReference::Man | ExplainReference::Both => true,
+            ExplainReference::Tldr => tldr.is_none(),
+        };
+
+        if let Some(content) = tldr {
+            push(content, ReferenceSource::Tldr);
+        }
+        if want_man {
+            if let Some((content, source)) = get_man_page(cmd, max_per_page, use_cache, timeout) {
+                push(content, source);
+            }
+        }
+    }
+
+    // Sort by size ascending, so the pre-filter keeps as many pages as fit
     references.sort_by_key(|r| r.char_count);
 
     // Pre-filter to stay under max_total_chars
@@ -303,6 +604,81 @@ fn build_explain_schema(with_citations: bool) -> serde_json::Value {
     })
 }
 
+/// Commands `--with-output` may run without `--i-know-this-runs-it`: they
+/// only report on the system or on file metadata, whatever their arguments.
+/// File readers like `cat` are left out, since their output could send any
+/// file's contents to the provider, and so is `git`, whose options can run
+/// other programs. Each entry is a leading word sequence.
+const SAFE_SAMPLE_COMMANDS: &[&[&str]] = &[
+    &["ls"],
+    &["pwd"],
+    &["whoami"],
+    &["id"],
+    &["uname"],
+    &["uptime"],
+    &["df"],
+    &["du"],
+    &["free"],
+    &["ps"],
+    &["lsblk"],
+    &["wc"],
+    &["stat"],
+    &["which"],
+    &["echo"],
+];
+
+/// Characters that could make the shell do more than run one simple command.
+const SHELL_METACHARACTERS: &[char] = &[
+    '|', '&', ';', '<', '>', '(', ')', '$', '`', '\\', '"', '\'', '*', '?', '[', ']', '{', '}', '~', '!', '#', '\n',
+];
+
+/// Longest output sample sent to the AI, in characters.
+const MAX_SAMPLE_CHARS: usize = 2000;
+
+/// Whether `command` is a single simple command on the safe list.
+fn is_safe_sample_command(command: &str) -> bool {
+    if command.contains(SHELL_METACHARACTERS) {
+        return false;
+    }
+    let words: Vec<&str> = command.split_whitespace().collect();
+    SAFE_SAMPLE_COMMANDS.iter().any(|prefix| words.starts_with(prefix))
+}
+
+/// Run `command` to capture a truncated sample of its output for `--with-output`.
+///
+/// Commands off the safe list are refused unless `allow_unsafe` is set. The
+/// command runs in the execution shell with stdin closed and is killed after
+/// `timeout`.
+fn sample_output(command: &str, allow_unsafe: bool, timeout: Duration) -> Result<String> {
+    if !allow_unsafe && !is_safe_sample_command(command) {
+        bail!(
+            "Not running `{}` for --with-output: only simple read-only commands (e.g., `ls`, `df`, `ps`) run by default.\n\
+             Hint: Add --i-know-this-runs-it to run it anyway, with all of its side effects.",
+            command
+        );
+    }
+
+    log::info!("Running `{}` for a sample of its output", command);
+    let output = run_with_timeout(&mut crate::suggest::shell_command(command)?, timeout)
+        .with_context(|| format!("`{}` failed to start or didn't finish within {:?}", command, timeout))?;
+
+    let mut sample = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        sample.push_str("\n[stderr]\n");
+        sample.push_str(&stderr);
+    }
+    if !output.status.success() {
+        sample.push_str(&format!("\n[{}]", output.status));
+    }
+
+    if sample.chars().count() > MAX_SAMPLE_CHARS {
+        sample = sample.chars().take(MAX_SAMPLE_CHARS).collect();
+        sample.push_str("\n[truncated]");
+    }
+    Ok(sample)
+}
+
 /// Build the system prompt for the explain command.
 /// When `with_citations` is true, includes citation instructions.
 /// When `locale` is Some, includes a hint to respond in that language.
@@ -320,6 +696,12 @@ fn build_system_prompt(with_citations: bool, locale: Option<&str>) -> String {
     }
 
     if with_citations {
+        prompt.push_str(
+            "Each documentation message is a man page (headed \"# <command>(1)\"), \
+             the command's own help output (headed \"# <command> --help (help output)\"), \
+             or a tldr page of usage examples (headed \"# <command> (tldr)\"). \
+             Help output and tldr pages are terser than man pages; cite them the same way.\n\n"
+        );
         prompt.push_str(
             "For each segment, you MUST:\n\
              1. First identify the exact segment from the command\n\
@@ -385,11 +767,30 @@ fn build_system_prompt(with_citations: bool, locale: Option<&str>) -> String {
 #[derive(Debug)]
 pub struct ExplainOptions {
     pub command: Vec<String>,
+    pub no_cache: bool,
+    /// Browse the explanation as a collapsible tree (human output on a terminal only)
+    pub interactive: bool,
+    /// Print each segment's man page citation and confidence beneath it
+    pub citations: bool,
+    /// Explain each line of stdin separately
+    pub batch: bool,
+    /// Read the command from the clipboard instead of args or stdin
+    pub clipboard: bool,
+    /// Run the command and show the AI a sample of its output
+    pub with_output: bool,
+    /// Let `with_output` run commands that aren't on the safe list
+    pub allow_unsafe_run: bool,
 }
 
 pub async fn run_explain(validated: &ValidatedConfig<'_>, opts: ExplainOptions) -> Result<()> {
-    // Determine command input: from args, or from stdin when piped.
-    let mut command_to_explain = if !opts.command.is_empty() {
+    if opts.batch {
+        return run_batch(validated, !opts.no_cache, opts.citations).await;
+    }
+
+    // Determine command input: from the clipboard, args, or stdin when piped.
+    let mut command_to_explain = if opts.clipboard {
+        clipboard::paste().map_err(|e| anyhow!("Failed to read the command from the clipboard: {}", e))?
+    } else if !opts.command.is_empty() {
         opts.command.join(" ")
     } else {
         let mut buf = String::new();
@@ -405,13 +806,167 @@ pub async fn run_explain(validated: &ValidatedConfig<'_>, opts: ExplainOptions)
         bail!("Command to explain is empty");
     }
 
-    explain_command(&command_to_explain, validated).await
+    let sample = if opts.with_output {
+        let timeout = Duration::from_secs(validated.app_config().reference_timeout.value);
+        Some(sample_output(&command_to_explain, opts.allow_unsafe_run, timeout)?)
+    } else {
+        None
+    };
+
+    let explanation = request_explanation(&command_to_explain, sample.as_deref(), validated, !opts.no_cache).await?;
+    print_explanation(&command_to_explain, &explanation, validated, opts.interactive, opts.citations)
 }
 
-/// Explain a command directly (callable from other modules)
-pub async fn explain_command(command_to_explain: &str, validated: &ValidatedConfig<'_>) -> Result<()> {
-    let config = validated.app_config();
+/// One explained line of a `--batch` run.
+#[derive(Debug, Serialize)]
+struct BatchEntry {
+    /// 1-based line number in the input where the command starts
+    line: usize,
+    command: String,
+    #[serde(flatten)]
+    explanation: ExplainResult,
+}
+
+/// The commands in a script, with the line each starts on.
+///
+/// Blank lines, comments, and the shebang are skipped, and lines continued
+/// with a trailing backslash are joined into one command.
+fn batch_commands(input: &str) -> Vec<(usize, String)> {
+    let mut commands = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, line) in input.lines().enumerate() {
+        let trimmed = line.trim();
+        if pending.is_none() && (trimmed.is_empty() || trimmed.starts_with('#')) {
+            continue;
+        }
+        let (start, mut command) = pending.take().unwrap_or((i + 1, String::new()));
+        match trimmed.strip_suffix('\\') {
+            Some(continued) => {
+                command.push_str(continued);
+                pending = Some((start, command));
+            }
+            None => {
+                command.push_str(trimmed);
+                commands.push((start, command.trim().to_string()));
+            }
+        }
+    }
+    if let Some((start, command)) = pending.filter(|(_, c)| !c.trim().is_empty()) {
+        commands.push((start, command.trim().to_string()));
+    }
+    commands
+}
+
+/// Explain every command piped on stdin, one at a time.
+///
+/// A line that fails is reported and skipped so the rest still get explained;
+/// the run fails at the end if any did.
+async fn run_batch(validated: &ValidatedConfig<'_>, use_cache: bool, citations: bool) -> Result<()> {
+    if std::io::stdin().is_terminal() {
+        bail!("--batch reads commands from stdin.\nHint: Pipe a script in, e.g. `shell-ai explain --batch < script.sh`.");
+    }
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let commands = batch_commands(&input);
+    if commands.is_empty() {
+        bail!("No commands to explain on stdin");
+    }
+
+    let output_format = validated.app_config().output_format.value;
+    let mut entries = Vec::new();
+    let mut failed = 0;
+    for (line, command) in commands.iter() {
+        let explanation = match request_explanation(command, None, validated, use_cache).await {
+            Ok(explanation) => explanation,
+            Err(e) => {
+                log::error!("Line {}: failed to explain `{}`: {}", line, command, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if output_format == OutputFormat::Human {
+            outln!();
+            outln!("{} {}", format!("{}:", line).cyan(), command.green());
+            print_explanation(command, &explanation, validated, false, citations)?;
+        } else {
+            entries.push(BatchEntry { line: *line, command: command.clone(), explanation });
+        }
+    }
+
+    if output_format != OutputFormat::Human {
+        output_format.print_serialized(&entries)?;
+    }
+    if failed > 0 {
+        bail!("Failed to explain {} of {} commands", failed, commands.len());
+    }
+    Ok(())
+}
+
+/// Explain a command directly (callable from other modules).
+/// `use_cache` controls whether the on-disk man page cache is used.
+pub async fn explain_command(command_to_explain: &str, validated: &ValidatedConfig<'_>, use_cache: bool) -> Result<()> {
     let command_to_explain = command_to_explain.trim();
+    let explanation = request_explanation(command_to_explain, None, validated, use_cache).await?;
+    print_explanation(command_to_explain, &explanation, validated, false, false)
+}
+
+/// Render an explanation based on the configured output format.
+/// `interactive` shows human output as a collapsible tree when stderr is a terminal;
+/// `citations` prints citations beneath each segment in the flat human output.
+fn print_explanation(
+    command_to_explain: &str,
+    explanation: &ExplainResult,
+    validated: &ValidatedConfig<'_>,
+    interactive: bool,
+    citations: bool,
+) -> Result<()> {
+    let config = validated.app_config();
+    match config.output_format.value {
+        format @ (OutputFormat::Json | OutputFormat::Yaml) => {
+            format.print_serialized(explanation)?;
+        }
+        OutputFormat::Human if interactive && std::io::stderr().is_terminal() => {
+            eprintln!();
+            eprintln!("{}", "Explanation:".white().bold());
+            eprintln!();
+            let mut tree = ui::TreeView::new(format!("  {}", explanation.synopsis)).detail_name("citation");
+            for node in &explanation.explanations {
+                tree = tree.item(tree_item(command_to_explain, node));
+            }
+            tree.run()?;
+            eprintln!();
+        }
+        OutputFormat::Human => {
+            outln!();
+            outln!("{}", "Explanation:".white().bold());
+            outln!();
+            outln!("  {}", explanation.synopsis.dimmed());
+            outln!();
+            for node in &explanation.explanations {
+                render_node(command_to_explain, node, 1, citations);
+            }
+            outln!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate only the one-line synopsis of what a command does.
+pub async fn synopsis(command: &str, validated: &ValidatedConfig<'_>) -> Result<String> {
+    let explanation = request_explanation(command.trim(), None, validated, true).await?;
+    Ok(explanation.synopsis)
+}
+
+/// Gather references and ask the AI to explain a command.
+/// `sample` is example output of the command, from `--with-output`.
+async fn request_explanation(
+    command_to_explain: &str,
+    sample: Option<&str>,
+    validated: &ValidatedConfig<'_>,
+    use_cache: bool,
+) -> Result<ExplainResult> {
+    let config = validated.app_config();
     if command_to_explain.is_empty() {
         bail!("Command to explain is empty");
     }
@@ -419,15 +974,29 @@ pub async fn explain_command(command_to_explain: &str, validated: &ValidatedConf
     // Use the shared provider configuration
     let provider = ProviderConfig::from_validated(validated);
     let url = provider.chat_completions_url();
-    let bearer_token = provider.api_key.as_deref();
-    let extra_headers = provider.extra_headers_ref();
 
     // Create progress indicator
     let progress = Progress::new("Gathering documentation...");
 
     // Gather man page references for context
     let mut references = if config.max_reference_chars.value > 0 {
-        gather_man_references(command_to_explain, config.max_reference_chars.value)
+        gather_man_references(
+            command_to_explain,
+            config.max_reference_chars.value,
+            use_cache,
+            config.explain_reference.value,
+            Duration::from_secs(config.reference_timeout.value),
+            |cmd, n, total| {
+                if let Some(ref p) = progress {
+                    let source = match config.explain_reference.value {
+                        ExplainReference::Man => "man page",
+                        ExplainReference::Tldr => "tldr page",
+                        ExplainReference::Both => "documentation",
+                    };
+                    p.set_message(&format!("Reading {} for {} ({}/{})...", source, cmd, n, total));
+                }
+            },
+        )
     } else {
         Vec::new()
     };
@@ -435,93 +1004,87 @@ pub async fn explain_command(command_to_explain: &str, validated: &ValidatedConf
     log::debug!("Extracted commands: {:?}", extract_command_names(command_to_explain));
     log::debug!("Man page references gathered: {}", references.len());
     for r in &references {
-        log::debug!("  - {} ({} chars)", r.command, r.char_count);
+        log::debug!("  - {} ({} chars, {:?})", r.command, r.char_count, r.source);
     }
 
     // Resolve the effective locale for AI responses
     let locale = resolve_locale(config.locale.value.as_deref());
 
-    // Retry loop: on 413, drop the shortest man page reference and retry
-    loop {
-        // Determine if we have documentation to cite
-        let with_citations = !references.is_empty();
-
-        // Build schema and prompt dynamically based on whether we have docs
-        let schema_value = build_explain_schema(with_citations);
-        let system_prompt = build_system_prompt(with_citations, locale.as_deref());
-
-        // Build messages array:
-        // 1. System message with instructions
-        // 2. System messages with man page documentation (if any)
-        // 3. User message with just the command to explain
-        let mut messages: Vec<serde_json::Value> = Vec::new();
-
-        // Instructions system message
-        messages.push(json!({"role": "system", "content": system_prompt}));
-
-        // Man page documentation system messages
-        for r in &references {
-            messages.push(json!({"role": "system", "content": r.content}));
-        }
-
-        // User message is just the command
-        messages.push(json!({"role": "user", "content": command_to_explain}));
-
-        let mut payload = json!({
-            "model": provider.model,
-            "messages": messages,
-            "temperature": provider.temperature,
-            "response_format": {
-                "type": "json_schema",
-                "json_schema": {
-                    "name": "command_explanation",
-                    "strict": true,
-                    "schema": schema_value
-                }
+    // On 413, drop the largest man page reference and retry
+    let response = post_shrinking_on_413(
+        &mut references,
+        |references| {
+            // Determine if we have documentation to cite
+            let with_citations = !references.is_empty();
+
+            // Build schema and prompt dynamically based on whether we have docs
+            let schema_value = build_explain_schema(with_citations);
+            let system_prompt = build_system_prompt(with_citations, locale.as_deref());
+
+            // Build messages array:
+            // 1. System message with instructions
+            // 2. System messages with man page documentation (if any)
+            // 3. System message with sample output (if any)
+            // 4. User message with just the command to explain
+            let mut messages: Vec<serde_json::Value> = Vec::new();
+
+            // Instructions system message
+            messages.push(json!({"role": "system", "content": system_prompt}));
+
+            // Man page documentation system messages
+            for r in references {
+                messages.push(json!({"role": "system", "content": r.content}));
             }
-        });
-
-        // Add max_tokens if configured
-        if let Some(max_tokens) = provider.max_tokens {
-            payload["max_tokens"] = json!(max_tokens);
-        }
 
-        let payload_str = serde_json::to_string(&payload)
-            .unwrap_or_else(|e| format!("<serialization error: {}>", e));
-        log::debug!("Sending request to: {}", url);
-        log::debug!("Payload size: {} chars", payload_str.len());
-        log::debug!("System messages: {} (1 instructions + {} man pages), User messages: 1",
-                  1 + references.len(), references.len());
+            // Sample output system message
+            if let Some(sample) = sample {
+                messages.push(json!({
+                    "role": "system",
+                    "content": format!(
+                        "Example output from running the command here (may be truncated). \
+                         Use it to make the explanation concrete, e.g., what each column or line means:\n{}",
+                        sample
+                    )
+                }));
+            }
 
-        // Update progress for API call
-        if let Some(ref p) = progress {
-            p.set_message("Waiting for AI response...");
-        }
+            // User message is just the command
+            messages.push(json!({"role": "user", "content": command_to_explain}));
+
+            let mut payload = json!({
+                "model": provider.model,
+                "messages": messages,
+                "temperature": provider.temperature,
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "command_explanation",
+                        "strict": true,
+                        "schema": schema_value
+                    }
+                }
+            });
 
-        let (status, body) = http::post_json_raw(&url, bearer_token, &extra_headers, &payload)?;
+            // Add max_tokens if configured
+            if let Some(max_tokens) = provider.max_tokens {
+                payload["max_tokens"] = json!(max_tokens);
+            }
 
-        // Handle 413 Request Entity Too Large
-        if status == 413 {
-            log::debug!("HTTP 413 response body: {}", body);
+            let payload_str = serde_json::to_string(&payload)
+                .unwrap_or_else(|e| format!("<serialization error: {}>", e));
+            log::debug!("Sending request to: {}", url);
+            log::debug!("Payload size: {} chars", payload_str.len());
+            log::debug!("System messages: {} (1 instructions + {} man pages{}), User messages: 1",
+                      messages.len() - 1, references.len(), if sample.is_some() { " + 1 sample output" } else { "" });
 
-            if references.is_empty() {
-                // Clear progress before error
-                if let Some(ref p) = progress {
-                    p.finish_and_clear();
-                }
-                // No more references to drop, fail with the error
-                bail!(
-                    "Request too large (HTTP 413): {}",
-                    if body.is_empty() {
-                        "context length exceeded".to_string()
-                    } else {
-                        body
-                    }
-                );
+            // Update progress for API call
+            if let Some(ref p) = progress {
+                p.set_message("Waiting for AI response...");
             }
 
-            // Drop the shortest reference (first in sorted list) and retry
-            let dropped = references.remove(0);
+            provider.post_chat_raw(&payload)
+        },
+        |dropped| {
             log::info!(
                 "Context too large, dropping man page for '{}' and retrying...",
                 dropped.command
@@ -529,83 +1092,138 @@ pub async fn explain_command(command_to_explain: &str, validated: &ValidatedConf
             if let Some(ref p) = progress {
                 p.set_message(&format!("Retrying without '{}'...", dropped.command));
             }
-            continue;
-        }
-
-        // Handle other errors
-        if status < 200 || status >= 300 {
+        },
+    );
+    let (status, body) = match response {
+        Ok(response) => response,
+        Err(e) => {
             // Clear progress before error
             if let Some(ref p) = progress {
                 p.finish_and_clear();
             }
-            bail!(
-                "HTTP {} error: {}",
-                status,
-                if body.is_empty() {
-                    "Unknown error".to_string()
-                } else {
-                    body
-                }
-            );
+            return Err(e);
         }
+    };
 
-        // Parse response
-        let resp_json: serde_json::Value = serde_json::from_str(&body)
-            .context("failed to parse API response as JSON")?;
-
-        if let Some(msg) = http::extract_api_error(&resp_json) {
-            bail!("API error: {}", msg);
+    // Handle other errors
+    if !(200..300).contains(&status) {
+        // Clear progress before error
+        if let Some(ref p) = progress {
+            p.finish_and_clear();
         }
+        bail!(
+            "HTTP {} error: {}",
+            status,
+            if body.is_empty() {
+                "Unknown error".to_string()
+            } else {
+                body
+            }
+        );
+    }
 
-        let content = http::extract_content_from_response(&resp_json)?;
+    // Parse response
+    let resp_json: serde_json::Value = serde_json::from_str(&body)
+        .context("failed to parse API response as JSON")?;
 
-        log::trace!("Raw model response ({} chars):\n{}", content.len(), content);
+    if let Some(msg) = http::extract_api_error(&resp_json) {
+        bail!("API error: {}", msg);
+    }
 
-        let explanation: ExplainResult = serde_json::from_str(content)
-            .context("failed to parse explanation JSON from model")?;
+    let content = http::extract_content_from_response(&resp_json)?;
 
-        // Clear progress before output
-        if let Some(ref p) = progress {
-            p.finish_and_clear();
-        }
+    log::trace!("Raw model response ({} chars):\n{}", content.len(), content);
 
-        // Render output based on output format from config
-        match config.output_format.value {
-            OutputFormat::Json => {
-                println!("{}", serde_json::to_string_pretty(&explanation)?);
-            }
-            OutputFormat::Human => {
-                println!();
-                println!("{}", "Explanation:".white().bold());
-                println!();
-                println!("  {}", explanation.synopsis.dimmed());
-                println!();
-                for node in &explanation.explanations {
-                    render_node(command_to_explain, node, 1);
-                }
-                println!();
-            }
+    let mut explanation: ExplainResult = serde_json::from_str(content)
+        .context("failed to parse explanation JSON from model")?;
+    drop_low_confidence_citations(&mut explanation.explanations, config.citation_min_confidence.value);
+
+    // Clear progress before output
+    if let Some(ref p) = progress {
+        p.finish_and_clear();
+    }
+    provider.report_usage();
+
+    Ok(explanation)
+}
+
+/// Send a request built from `references`, and while the provider answers
+/// 413 (Request Entity Too Large), drop the largest reference and retry.
+///
+/// `references` must be sorted by size, smallest first, as gathered.
+/// Returns the status and body of the first response that isn't a 413.
+fn post_shrinking_on_413(
+    references: &mut Vec<ManReference>,
+    mut send: impl FnMut(&[ManReference]) -> Result<(u16, String)>,
+    mut on_drop: impl FnMut(&ManReference),
+) -> Result<(u16, String)> {
+    loop {
+        let (status, body) = send(references)?;
+        if status != 413 {
+            return Ok((status, body));
         }
+        log::debug!("HTTP 413 response body: {}", body);
 
-        return Ok(());
+        // Dropping the largest reference shrinks the payload the most per attempt
+        let Some(dropped) = references.pop() else {
+            bail!(
+                "Request too large (HTTP 413): {}",
+                if body.is_empty() {
+                    "context length exceeded".to_string()
+                } else {
+                    body
+                }
+            );
+        };
+        on_drop(&dropped);
     }
 }
 
-fn render_node(original_command: &str, node: &ExplanationNode, indent: usize) {
-    let indent_str = "  ".repeat(indent);
+/// Remove citations (and their confidence) scored below `min_confidence`,
+/// so likely-fabricated quotes are neither shown nor emitted as structured output.
+fn drop_low_confidence_citations(nodes: &mut [ExplanationNode], min_confidence: f32) {
+    for node in nodes {
+        if node.citation_confidence.is_some_and(|c| c < min_confidence) {
+            log::debug!("Dropping citation for '{}' below confidence {}", node.segment, min_confidence);
+            node.citation = None;
+            node.citation_confidence = None;
+        }
+        drop_low_confidence_citations(&mut node.children, min_confidence);
+    }
+}
 
-    // Build the line: {prefix} {segment} {suffix}
-    let mut line = format!("{}• ", indent_str);
+/// Build the plain and colored display text for a node: `{prefix} {segment} {suffix}`.
+fn node_label(original_command: &str, node: &ExplanationNode) -> (String, String) {
+    let mut plain = String::new();
     if let Some(prefix) = &node.prefix {
         if !prefix.is_empty() {
-            line.push_str(prefix);
-            line.push(' ');
+            plain.push_str(prefix);
+            plain.push(' ');
         }
     }
+    let mut styled = plain.clone();
+
+    let segment = node_segment(original_command, node);
+    plain.push_str(&segment);
+    styled.push_str(&segment.cyan().to_string());
+
+    if let Some(suffix) = &node.suffix {
+        if !suffix.is_empty() {
+            plain.push(' ');
+            plain.push_str(suffix);
+            styled.push(' ');
+            styled.push_str(suffix);
+        }
+    }
+
+    (plain, styled)
+}
 
+/// The node's segment as it appears in the original command.
+fn node_segment(original_command: &str, node: &ExplanationNode) -> String {
     // Handle potential double-escaping from the model: if segment isn't found
     // in the original command, try JSON-decoding it once more
-    let segment = if original_command.contains(&node.segment) {
+    if original_command.contains(&node.segment) {
         node.segment.clone()
     } else if let Ok(decoded) = serde_json::from_str::<String>(&format!("\"{}\"", &node.segment)) {
         if original_command.contains(&decoded) {
@@ -615,20 +1233,212 @@ fn render_node(original_command: &str, node: &ExplanationNode, indent: usize) {
         }
     } else {
         node.segment.clone()
+    }
+}
+
+/// Word-wrap a node's `{prefix} {segment} {suffix}` to fit `width` columns
+/// after `indent`, returning the colored lines without indentation.
+///
+/// Words are never split, so a word longer than the line gets a line of its
+/// own. Each word of the segment is colored separately, so the color holds
+/// across line breaks.
+fn wrap_node_label(original_command: &str, node: &ExplanationNode, width: usize) -> Vec<String> {
+    let words = |text: Option<&str>| {
+        text.unwrap_or_default()
+            .split_whitespace()
+            .map(|w| (w.to_string(), false))
+            .collect::<Vec<_>>()
     };
+    // Split the segment on single spaces so runs of spaces (e.g., in quotes) survive
+    let segment = node_segment(original_command, node);
+    let segment_words = segment.trim().split(' ').map(|w| (w.to_string(), true));
+    let all_words = words(node.prefix.as_deref())
+        .into_iter()
+        .chain(segment_words)
+        .chain(words(node.suffix.as_deref()));
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for (word, highlight) in all_words {
+        let word_width = word.chars().count();
+        if line_width > 0 && line_width + 1 + word_width > width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if line_width > 0 {
+            line.push(' ');
+            line_width += 1;
+        }
+        if highlight {
+            line.push_str(&word.cyan().to_string());
+        } else {
+            line.push_str(&word);
+        }
+        line_width += word_width;
+    }
+    lines.push(line);
+    lines
+}
 
-    line.push_str(&segment.cyan().to_string());
+fn render_node(original_command: &str, node: &ExplanationNode, indent: usize, citations: bool) {
+    let indent_str = "  ".repeat(indent);
+    // Continuation lines hang under the text after the bullet
+    let width = ui::terminal_width().saturating_sub(indent_str.len() + 2).max(20);
 
-    if let Some(suffix) = &node.suffix {
-        if !suffix.is_empty() {
-            line.push(' ');
-            line.push_str(suffix);
+    for (i, line) in wrap_node_label(original_command, node, width).iter().enumerate() {
+        let bullet = if i == 0 { "•" } else { " " };
+        outln!("{}{} {}", indent_str, bullet, line);
+    }
+
+    if citations {
+        if let Some(citation) = node.citation.as_deref().filter(|c| !c.is_empty()) {
+            let dot = node.citation_confidence.map_or_else(|| " ".to_string(), confidence_dot);
+            for (i, line) in citation.lines().enumerate() {
+                let marker = if i == 0 { dot.as_str() } else { " " };
+                outln!("{}  {} {}", indent_str, marker, line.dimmed());
+            }
         }
     }
 
-    println!("{}", line);
+    for child in &node.children {
+        render_node(original_command, child, indent + 1, citations);
+    }
+}
+
+/// Colored dot for a citation's confidence: green (high), yellow (medium), or red (low).
+fn confidence_dot(confidence: f32) -> String {
+    if confidence >= HIGH_CONFIDENCE {
+        "●".green().to_string()
+    } else if confidence >= MEDIUM_CONFIDENCE {
+        "●".yellow().to_string()
+    } else {
+        "●".red().to_string()
+    }
+}
+
+/// Convert a node and its children into an interactive tree item, with the citation as detail.
+fn tree_item(original_command: &str, node: &ExplanationNode) -> ui::TreeItem {
+    let (plain, styled) = node_label(original_command, node);
+    let citation = node.citation.as_ref().filter(|c| !c.is_empty()).map(|c| match node.citation_confidence {
+        Some(confidence) => format!("{} ({:.0}% confidence)", c, confidence * 100.0),
+        None => c.clone(),
+    });
 
+    let mut item = ui::TreeItem::new(plain).styled(styled).detail(citation);
     for child in &node.children {
-        render_node(original_command, child, indent + 1);
+        item = item.child(tree_item(original_command, child));
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cited(segment: &str, confidence: f32, children: Vec<ExplanationNode>) -> ExplanationNode {
+        ExplanationNode {
+            segment: segment.to_string(),
+            citation: Some(format!("quote for {}", segment)),
+            citation_confidence: Some(confidence),
+            prefix: None,
+            suffix: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn drops_citations_below_the_threshold_only() {
+        let mut nodes = vec![
+            cited("below", 0.49, vec![]),
+            cited("equal", 0.5, vec![]),
+            cited("above", 0.9, vec![cited("nested", 0.2, vec![])]),
+        ];
+        drop_low_confidence_citations(&mut nodes, 0.5);
+
+        assert_eq!(nodes[0].citation, None);
+        assert_eq!(nodes[0].citation_confidence, None);
+        assert_eq!(nodes[1].citation.as_deref(), Some("quote for equal"));
+        assert_eq!(nodes[1].citation_confidence, Some(0.5));
+        assert_eq!(nodes[2].citation.as_deref(), Some("quote for above"));
+        assert_eq!(nodes[2].children[0].citation, None);
+    }
+
+    fn reference(command: &str, char_count: usize) -> ManReference {
+        ManReference {
+            command: command.to_string(),
+            content: "x".repeat(char_count),
+            char_count,
+            source: ReferenceSource::ManPage,
+        }
+    }
+
+    #[test]
+    fn repeated_413s_drop_the_largest_references_first() {
+        let mut references = vec![reference("ls", 10), reference("grep", 20), reference("find", 30)];
+        let mut sent = Vec::new();
+        let mut dropped = Vec::new();
+
+        let response = post_shrinking_on_413(
+            &mut references,
+            |refs| {
+                sent.push(refs.iter().map(|r| r.command.clone()).collect::<Vec<_>>());
+                Ok(if refs.len() > 1 { (413, String::new()) } else { (200, "ok".to_string()) })
+            },
+            |r| dropped.push(r.command.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(response, (200, "ok".to_string()));
+        assert_eq!(sent, [vec!["ls", "grep", "find"], vec!["ls", "grep"], vec!["ls"]]);
+        assert_eq!(dropped, ["find", "grep"]);
+        assert_eq!(references.len(), 1);
+    }
+
+    #[test]
+    fn gives_up_when_a_request_without_references_is_too_large() {
+        let mut references = vec![reference("ls", 10)];
+        let mut attempts = 0;
+
+        let err = post_shrinking_on_413(
+            &mut references,
+            |_| {
+                attempts += 1;
+                Ok((413, String::new()))
+            },
+            |_| {},
+        )
+        .unwrap_err();
+
+        assert_eq!(attempts, 2);
+        assert!(references.is_empty());
+        assert_eq!(err.to_string(), "Request too large (HTTP 413): context length exceeded");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn only_simple_listed_commands_are_safe_to_sample() {
+        for command in ["ls -la", "df -h", "ps aux", "du -sh src", "uname -a"] {
+            assert!(is_safe_sample_command(command), "{}", command);
+        }
+        for command in [
+            "cat ~/.ssh/id_rsa",
+            "head -n 5 /etc/shadow",
+            "git log --output=/tmp/x",
+            "git status",
+            "ls; rm -rf build",
+            "ls $(whoami)",
+            "echo hi > file",
+            "rm -rf build",
+        ] {
+            assert!(!is_safe_sample_command(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn help_text_is_only_fetched_for_known_commands() {
+        // `shutdown -h` halts, so unknown commands must never be run
+        for cmd in ["shutdown", "halt", "sh"] {
+            assert!(get_help_text(cmd, 1000, false, Duration::from_secs(1)).is_none());
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Persistent history of accepted suggestions.
+//!
+//! Each executed or copied command is appended as one JSON object per line to
+//! `history.jsonl` in the data directory (see [`config::data_dir`]). The most recent prompt and
+//! accepted command are also kept in `last.json` for `--last` and `--repeat`.
+//! Set `SHAI_NO_HISTORY=true` to disable recording.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, env, OutputFormat, ValidatedConfig};
+use crate::datetime::UtcDateTime;
+use crate::output::outln;
+
+/// Arguments for the history subcommand.
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub action: Option<HistoryAction>,
+
+    /// Only show entries whose prompt or command contains this text (case-insensitive).
+    #[arg(long = "grep", value_name = "TEXT")]
+    pub grep: Option<String>,
+
+    /// Show at most this many of the most recent entries.
+    #[arg(long = "limit", value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+/// History subcommand actions.
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Delete all recorded history.
+    Clear,
+}
+
+/// How the user accepted a suggestion.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Executed,
+    Copied,
+}
+
+/// A single recorded suggestion.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub prompt: String,
+    pub command: String,
+    pub outcome: Outcome,
+    pub provider: String,
+    pub model: String,
+}
+
+/// The most recent suggest prompt and accepted command, updated independently.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct LastInvocation {
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Get the history file path.
+pub fn history_file_path() -> Option<PathBuf> {
+    let mut base = config::data_dir()?;
+    base.push("history.jsonl");
+    Some(base)
+}
+
+/// Get the last-invocation file path.
+fn last_file_path() -> Option<PathBuf> {
+    let mut base = config::data_dir()?;
+    base.push("last.json");
+    Some(base)
+}
+
+/// Load the last invocation record, if one has been saved.
+pub fn load_last() -> Option<LastInvocation> {
+    let data = fs::read_to_string(last_file_path()?).ok()?;
+    match serde_json::from_str(&data) {
+        Ok(last) => Some(last),
+        Err(e) => {
+            log::debug!("Ignoring malformed last invocation record: {}", e);
+            None
+        }
+    }
+}
+
+/// Remember the most recent suggest prompt, for `--last`.
+pub fn save_last_prompt(prompt: &str) {
+    update_last(|last| last.prompt = Some(prompt.to_string()));
+}
+
+/// Remember the most recently accepted command, for `--repeat`.
+pub fn save_last_command(command: &str) {
+    update_last(|last| last.command = Some(command.to_string()));
+}
+
+/// Apply `change` to the last invocation record and write it back.
+///
+/// Like [`record`], failures are logged at debug level and otherwise ignored.
+fn update_last(change: impl FnOnce(&mut LastInvocation)) {
+    if !is_enabled() {
+        return;
+    }
+
+    let mut last = load_last().unwrap_or_default();
+    change(&mut last);
+    if let Err(e) = write_private(last_file_path(), &serde_json::to_string(&last).unwrap_or_default(), false) {
+        log::debug!("Failed to save last invocation: {}", e);
+    }
+}
+
+/// Whether history recording is enabled (opt out with `SHAI_NO_HISTORY=true`).
+pub fn is_enabled() -> bool {
+    !matches!(std::env::var(env::SHAI_NO_HISTORY), Ok(v) if v.to_lowercase() == "true" || v == "1")
+}
+
+/// Record an accepted suggestion.
+///
+/// Failures are logged at debug level and otherwise ignored, so a read-only
+/// data directory never interrupts the suggest flow.
+pub fn record(validated: &ValidatedConfig<'_>, prompt: &str, command: &str, outcome: Outcome) {
+    if !is_enabled() {
+        return;
+    }
+
+    let entry = HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        prompt: prompt.to_string(),
+        command: command.to_string(),
+        outcome,
+        provider: validated.provider.to_string(),
+        model: validated.effective_model(),
+    };
+
+    if let Err(e) = append(&entry) {
+        log::debug!("Failed to record history: {}", e);
+    }
+}
+
+fn append(entry: &HistoryEntry) -> Result<()> {
+    write_private(history_file_path(), &serde_json::to_string(entry)?, true)
+}
+
+/// Write a line to a file only the user can read, appending or replacing it.
+pub fn write_private(path: Option<PathBuf>, line: &str, append: bool) -> Result<()> {
+    let path = path.context("Could not determine data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.create(true);
+    if append {
+        options.append(true);
+    } else {
+        options.write(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Load all history entries, oldest first. Malformed lines are skipped.
+pub fn load() -> Vec<HistoryEntry> {
+    let Some(path) = history_file_path() else {
+        return Vec::new();
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::debug!("Skipping malformed history line: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM` (UTC).
+fn format_timestamp(secs: u64) -> String {
+    let t = UtcDateTime::from_unix(secs);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", t.year, t.month, t.day, t.hour, t.minute)
+}
+
+fn run_list(args: &HistoryArgs, output_format: OutputFormat) -> Result<()> {
+    let mut entries = load();
+
+    if let Some(ref needle) = args.grep {
+        let needle = needle.to_lowercase();
+        entries.retain(|e| {
+            e.prompt.to_lowercase().contains(&needle) || e.command.to_lowercase().contains(&needle)
+        });
+    }
+
+    if let Some(limit) = args.limit {
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+    }
+
+    match output_format {
+        format @ (OutputFormat::Json | OutputFormat::Yaml) => {
+            format.print_serialized(&entries)?;
+        }
+        OutputFormat::Human => {
+            if entries.is_empty() {
+                outln!("{}", "(no history)".dimmed());
+                return Ok(());
+            }
+            for entry in &entries {
+                outln!(
+                    "{}  {}",
+                    format_timestamp(entry.timestamp).dimmed(),
+                    entry.command.green()
+                );
+                outln!("                  {}", entry.prompt.dimmed());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_clear() -> Result<()> {
+    if let Some(last) = last_file_path().filter(|p| p.exists()) {
+        fs::remove_file(&last).with_context(|| format!("Failed to remove {}", last.display()))?;
+    }
+
+    let path = history_file_path().context("Could not determine data directory")?;
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        outln!("{} {}", "Cleared:".green(), path.display());
+    } else {
+        outln!("No history to clear.");
+    }
+    Ok(())
+}
+
+/// Main entry point for the history subcommand.
+pub fn run(args: HistoryArgs, output_format: OutputFormat) -> Result<()> {
+    match args.action {
+        Some(HistoryAction::Clear) => run_clear(),
+        None => run_list(&args, output_format),
+    }
+}
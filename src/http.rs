@@ -1,24 +1,305 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use ureq::Proxy;
 
-/// Maximum number of retry attempts for transient errors
-const MAX_RETRIES: u32 = 3;
+use crate::config::env;
+use crate::datetime::UtcDateTime;
+use crate::logger;
+use crate::progress;
 
-/// Initial backoff delay in milliseconds
-const INITIAL_BACKOFF_MS: u64 = 1000;
+/// Default maximum number of retry attempts for transient errors
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
 
-/// Request timeout in seconds
-const TIMEOUT_SECS: u64 = 60;
+/// Default initial backoff delay in milliseconds
+pub const DEFAULT_INITIAL_BACKOFF_MS: u64 = 1000;
+
+/// Default request timeout in seconds
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Upper bound on any delay between retries in seconds, whether requested by
+/// the server's `Retry-After` or reached by exponential backoff
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Timeout and retry settings for API requests.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestSettings {
+    /// Request timeout in seconds (0 = no timeout)
+    pub timeout_secs: u64,
+    /// Maximum number of retry attempts for transient errors
+    pub max_retries: u32,
+    /// Initial backoff delay in milliseconds, doubled after each retry
+    pub initial_backoff_ms: u64,
+}
+
+impl Default for RequestSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+        }
+    }
+}
+
+impl RequestSettings {
+    /// The timeout to give the HTTP agent, with 0 meaning none.
+    fn timeout(&self) -> Option<u64> {
+        (self.timeout_secs > 0).then_some(self.timeout_secs)
+    }
+}
+
+/// A non-success HTTP response, keeping the body for callers that inspect it.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    pub status: u16,
+    pub body: String,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {}: {}", self.status, status_description(self.status))
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// A request that never got a response: connection, DNS, TLS, or timeout.
+#[derive(Debug)]
+pub struct NetworkError(pub String);
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Network error: {}", self.0)
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+// ============================================================================
+// Request Timing
+// ============================================================================
+
+/// Whether to print each request's timing to stderr (set from `show_timing`).
+static SHOW_TIMING: AtomicBool = AtomicBool::new(false);
+
+/// Timing of the most recent request, for `doctor`.
+static LAST_TIMING: Mutex<Option<RequestTiming>> = Mutex::new(None);
+
+/// Print the timing of each API request to stderr.
+pub fn set_show_timing(enabled: bool) {
+    SHOW_TIMING.store(enabled, Ordering::Relaxed);
+}
+
+/// Timing of the most recent API request made by this process.
+pub fn last_timing() -> Option<RequestTiming> {
+    *LAST_TIMING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Wall-clock timing of one API request, including its retries.
+///
+/// ureq doesn't expose DNS, connect, or TLS durations, so the time until the
+/// response headers arrive stands in for time to first byte.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestTiming {
+    /// From the start of the last attempt to its response headers, if it got a response
+    pub first_byte: Option<Duration>,
+    /// From the first attempt until the body was read, including retry delays
+    pub total: Duration,
+    /// Attempts repeated after a rate limit, server error, or network error
+    pub retries: u32,
+}
+
+impl std::fmt::Display for RequestTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}ms total, ", self.total.as_millis())?;
+        match self.first_byte {
+            Some(first_byte) => write!(f, "first byte after {}ms", first_byte.as_millis())?,
+            None => write!(f, "no response")?,
+        }
+        match self.retries {
+            1 => write!(f, ", 1 retry"),
+            n => write!(f, ", {} retries", n),
+        }
+    }
+}
+
+/// Times the attempts of one request.
+struct Stopwatch {
+    started: Instant,
+    attempt_started: Instant,
+    first_byte: Option<Duration>,
+    retries: u32,
+}
+
+impl Stopwatch {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self { started: now, attempt_started: now, first_byte: None, retries: 0 }
+    }
+
+    /// Note the start of attempt number `attempt`, counting from 0.
+    fn attempt(&mut self, attempt: u32) {
+        self.attempt_started = Instant::now();
+        self.first_byte = None;
+        self.retries = attempt;
+    }
+
+    /// Note that the current attempt's response headers arrived.
+    fn responded(&mut self) {
+        self.first_byte = Some(self.attempt_started.elapsed());
+    }
+}
+
+/// Run `send` with a stopwatch, then log its timing (and print it with `show_timing`).
+fn timed<T>(url: &str, send: impl FnOnce(&mut Stopwatch) -> Result<T>) -> Result<T> {
+    let mut stopwatch = Stopwatch::new();
+    let result = send(&mut stopwatch);
+    let timing = RequestTiming {
+        first_byte: stopwatch.first_byte,
+        total: stopwatch.started.elapsed(),
+        retries: stopwatch.retries,
+    };
+
+    log::debug!("Request to {}: {}", logger::redact(url), timing);
+    *LAST_TIMING.lock().unwrap_or_else(|e| e.into_inner()) = Some(timing);
+    if SHOW_TIMING.load(Ordering::Relaxed) {
+        // A spinner may still be running in the caller
+        progress::with_suspended(|| eprintln!("{}", format!("Timing: {}", timing).dimmed()));
+    }
+    result
+}
+
+// ============================================================================
+// Request Recording
+// ============================================================================
+
+/// One request and the response it got, as stored by `--record`.
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedExchange {
+    url: String,
+    request: Value,
+    status: u16,
+    response: String,
+}
+
+/// Where API exchanges are written to or read back from.
+#[derive(Debug)]
+enum Recording {
+    Record { dir: PathBuf, seq: AtomicU64, started: u128 },
+    Replay { dir: PathBuf, files: Mutex<VecDeque<PathBuf>> },
+}
+
+static RECORDING: OnceLock<Recording> = OnceLock::new();
+
+/// Turn on recording or replay of API requests for this process.
+///
+/// `record` and `replay` come from `--record` and `--replay`, falling back to
+/// `SHAI_RECORD_DIR` and `SHAI_REPLAY_DIR`. When recording, each chat request
+/// and its raw response are written (with secrets redacted) to a timestamped
+/// file in the directory. When replaying, requests don't touch the network;
+/// each one is answered with the next recorded response, in file name order.
+pub fn init_recording(record: Option<PathBuf>, replay: Option<PathBuf>) -> Result<()> {
+    let from_env = |var: &str| std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let record = record.or_else(|| from_env(env::SHAI_RECORD_DIR));
+    let replay = replay.or_else(|| from_env(env::SHAI_REPLAY_DIR));
+
+    let recording = match (record, replay) {
+        (None, None) => return Ok(()),
+        (Some(_), Some(_)) => bail!("Cannot record and replay API requests at the same time"),
+        (Some(dir), None) => {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create record directory {}", dir.display()))?;
+            let started = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis())
+                .unwrap_or(0);
+            Recording::Record { dir, seq: AtomicU64::new(0), started }
+        }
+        (None, Some(dir)) => {
+            let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+                .with_context(|| format!("Failed to read replay directory {}", dir.display()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            if files.is_empty() {
+                bail!("No recorded requests (*.json) in {}", dir.display());
+            }
+            files.sort();
+            Recording::Replay { dir, files: Mutex::new(files.into()) }
+        }
+    };
+
+    let _ = RECORDING.set(recording);
+    Ok(())
+}
+
+/// Send a request through `send`, or answer it from a recording.
+///
+/// Network errors are returned as is and not recorded.
+fn exchange(url: &str, body: &Value, send: impl FnOnce() -> Result<(u16, String)>) -> Result<(u16, String)> {
+    match RECORDING.get() {
+        Some(Recording::Replay { dir, files }) => replay_next(dir, files),
+        Some(Recording::Record { dir, seq, started }) => {
+            let (status, response) = send()?;
+            let n = seq.fetch_add(1, Ordering::Relaxed);
+            let path = dir.join(format!("{}-{:03}.json", started, n));
+            if let Err(e) = write_exchange(&path, url, body, status, &response) {
+                log::warn!("Failed to record request to {}: {}", path.display(), e);
+            }
+            Ok((status, response))
+        }
+        None => send(),
+    }
+}
+
+fn write_exchange(path: &Path, url: &str, body: &Value, status: u16, response: &str) -> Result<()> {
+    // Redaction only masks tokens, so a redacted JSON body stays valid JSON.
+    // The response is kept verbatim so `--replay` reproduces it exactly.
+    let request = serde_json::from_str(&logger::redact(&body.to_string())).unwrap_or(Value::Null);
+    let exchange = RecordedExchange {
+        url: logger::redact(url),
+        request,
+        status,
+        response: response.to_string(),
+    };
+    fs::write(path, serde_json::to_string_pretty(&exchange)?)?;
+    Ok(())
+}
+
+fn replay_next(dir: &Path, files: &Mutex<VecDeque<PathBuf>>) -> Result<(u16, String)> {
+    let path = files
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pop_front()
+        .ok_or_else(|| anyhow!("No recorded responses left to replay in {}", dir.display()))?;
+    let data = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let exchange: RecordedExchange = serde_json::from_str(&data)
+        .with_context(|| format!("Invalid recording {}", path.display()))?;
+    log::debug!("Replaying {} (HTTP {})", path.display(), exchange.status);
+    Ok((exchange.status, exchange.response))
+}
 
 /// Create an HTTP agent with proxy support from environment variables.
 ///
 /// Respects standard proxy environment variables: HTTP_PROXY, HTTPS_PROXY, NO_PROXY
 /// (and lowercase variants http_proxy, https_proxy, no_proxy).
-fn create_agent(http_status_as_error: bool) -> ureq::Agent {
+///
+/// `timeout_secs` of `None` disables the global timeout (for long downloads).
+fn create_agent(http_status_as_error: bool, timeout_secs: Option<u64>) -> ureq::Agent {
     let mut config = ureq::Agent::config_builder()
-        .timeout_global(Some(Duration::from_secs(TIMEOUT_SECS)))
+        .timeout_global(timeout_secs.map(Duration::from_secs))
         .http_status_as_error(http_status_as_error);
 
     // Try to get proxy from environment variables
@@ -42,20 +323,198 @@ fn status_description(status: u16) -> &'static str {
     }
 }
 
+/// Whether `url` points at this machine: `localhost` or a loopback IP.
+///
+/// Accepts URLs with or without a scheme, userinfo, port, or path.
+pub fn is_loopback_url(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = if let Some(bracketed) = host_port.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or("")
+    } else {
+        host_port.split(':').next().unwrap_or("")
+    };
+
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Whether a header's name suggests its value is a secret that shouldn't be
+/// shown in logs or config output.
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "key", "token", "secret", "password", "cookie", "signature", "credential"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+/// Parse a `Retry-After` header value into a delay.
+///
+/// Accepts both forms from RFC 9110: delay-seconds (`120`) and an HTTP-date
+/// (`Wed, 21 Oct 2015 07:28:00 GMT`), measured from `now`. Dates in the past
+/// yield a zero delay. Returns `None` if the value is unparseable.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let target = parse_http_date(value)?;
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(target.saturating_sub(now)))
+}
+
+/// Parse an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`) into seconds since the Unix epoch.
+fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_weekday, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+
+    let mut hms = time.split(':').map(|p| p.parse::<i64>().ok());
+    UtcDateTime {
+        year: year.parse().ok()?,
+        month: MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? as i64 + 1,
+        day: day.parse().ok()?,
+        hour: hms.next()??,
+        minute: hms.next()??,
+        second: hms.next()??,
+    }
+    .to_unix()
+}
+
+/// Pick the delay before the next retry.
+///
+/// Uses the server's `Retry-After` value when present and parseable,
+/// otherwise the exponential backoff delay, capped at [`MAX_RETRY_AFTER_SECS`].
+fn retry_delay(retry_after: Option<&str>, backoff_ms: u64, now: SystemTime) -> Duration {
+    retry_after
+        .and_then(|v| parse_retry_after(v, now))
+        .unwrap_or(Duration::from_millis(backoff_ms))
+        .min(Duration::from_secs(MAX_RETRY_AFTER_SECS))
+}
+
+/// Double the backoff delay for the next retry, up to [`MAX_RETRY_AFTER_SECS`].
+fn next_backoff(backoff_ms: u64) -> u64 {
+    backoff_ms.saturating_mul(2).min(MAX_RETRY_AFTER_SECS * 1000)
+}
+
+/// The parts of a response that decide whether [`send_with_retry`] tries again.
+trait RetryableResponse {
+    fn status_code(&self) -> u16;
+    fn retry_after(&self) -> Option<&str>;
+}
+
+impl RetryableResponse for ureq::http::Response<ureq::Body> {
+    fn status_code(&self) -> u16 {
+        self.status().as_u16()
+    }
+
+    fn retry_after(&self) -> Option<&str> {
+        self.headers().get("retry-after").and_then(|v| v.to_str().ok())
+    }
+}
+
+/// Make a request with `send`, retrying network errors and 429 and 5xx
+/// responses with exponential backoff, honoring the server's `Retry-After`
+/// header, up to `settings.max_retries` times.
+///
+/// `send` returns `Ok(Err(_))` for a network error and `Err(_)` for a failure
+/// that retrying can't fix. Returns the last response whatever its status, or
+/// a [`NetworkError`] if the last attempt got no response.
+fn send_with_retry<R: RetryableResponse, E: std::fmt::Display>(
+    settings: &RequestSettings,
+    stopwatch: &mut Stopwatch,
+    mut send: impl FnMut() -> Result<std::result::Result<R, E>>,
+) -> Result<R> {
+    let max_retries = settings.max_retries;
+    let mut backoff_ms = settings.initial_backoff_ms;
+
+    for attempt in 0..=max_retries {
+        stopwatch.attempt(attempt);
+        let delay = match send()? {
+            Ok(response) => {
+                stopwatch.responded();
+                let status = response.status_code();
+
+                // Rate limit (429) or server error (5xx) - retry with backoff
+                if !(status == 429 || (500..600).contains(&status)) || attempt == max_retries {
+                    return Ok(response);
+                }
+                let delay = retry_delay(response.retry_after(), backoff_ms, SystemTime::now());
+                log::warn!(
+                    "{} (HTTP {}) - attempt {}/{}, retrying in {}ms...",
+                    status_description(status),
+                    status,
+                    attempt + 1,
+                    max_retries + 1,
+                    delay.as_millis()
+                );
+                delay
+            }
+            Err(e) => {
+                if attempt == max_retries {
+                    return Err(NetworkError(e.to_string()).into());
+                }
+                let delay = retry_delay(None, backoff_ms, SystemTime::now());
+                log::warn!(
+                    "Network error (attempt {}/{}): {}, retrying in {}ms...",
+                    attempt + 1,
+                    max_retries + 1,
+                    e,
+                    delay.as_millis()
+                );
+                delay
+            }
+        };
+        std::thread::sleep(delay);
+        backoff_ms = next_backoff(backoff_ms);
+    }
+
+    Err(anyhow!("Max retries exceeded"))
+}
+
 /// Send a POST request with JSON body and return parsed JSON response.
-/// Includes exponential backoff retry for 429 and 5xx errors.
+/// Includes exponential backoff retry for 429 and 5xx errors, honoring the
+/// server's `Retry-After` header when present. Other non-2xx statuses fail
+/// with an [`HttpStatusError`].
 /// Respects HTTP_PROXY/HTTPS_PROXY environment variables.
 pub fn post_json(
     url: &str,
     bearer_token: Option<&str>,
     extra_headers: &[(&str, &str)],
     body: &Value,
+    settings: &RequestSettings,
 ) -> Result<Value> {
-    let agent = create_agent(true);
+    let (status, body_str) = exchange(url, body, || {
+        timed(url, |stopwatch| post_json_with_retry(url, bearer_token, extra_headers, body, settings, stopwatch))
+    })?;
+    if !(200..300).contains(&status) {
+        return Err(HttpStatusError { status, body: body_str }.into());
+    }
+    serde_json::from_str(&body_str).map_err(|e| anyhow!("Failed to parse JSON: {}", e))
+}
 
-    let mut backoff_ms = INITIAL_BACKOFF_MS;
+/// The network half of [`post_json`]: returns the final status and body.
+fn post_json_with_retry(
+    url: &str,
+    bearer_token: Option<&str>,
+    extra_headers: &[(&str, &str)],
+    body: &Value,
+    settings: &RequestSettings,
+    stopwatch: &mut Stopwatch,
+) -> Result<(u16, String)> {
+    // Statuses are handled here rather than as errors so Retry-After can be read
+    let agent = create_agent(false, settings.timeout());
 
-    for attempt in 0..=MAX_RETRIES {
+    let response = send_with_retry(settings, stopwatch, || {
         let mut request = agent.post(url);
 
         if let Some(token) = bearer_token {
@@ -66,53 +525,16 @@ pub fn post_json(
             request = request.header(*k, *v);
         }
 
-        return match request.send_json(body) {
-            Ok(response) => {
-                let body_str = response.into_body().read_to_string()?;
-                let json: Value = serde_json::from_str(&body_str)
-                    .map_err(|e| anyhow!("Failed to parse JSON: {}", e))?;
-                Ok(json)
-            }
-            Err(ureq::Error::StatusCode(status)) => {
-                // Rate limit (429) or server error (5xx) - retry with backoff
-                if status == 429 || (500..600).contains(&status) {
-                    if attempt < MAX_RETRIES {
-                        log::warn!(
-                            "{} (HTTP {}) - attempt {}/{}, retrying in {}ms...",
-                            status_description(status),
-                            status,
-                            attempt + 1,
-                            MAX_RETRIES + 1,
-                            backoff_ms
-                        );
-                        std::thread::sleep(Duration::from_millis(backoff_ms));
-                        backoff_ms *= 2;
-                        continue;
-                    }
-                }
-
-                Err(anyhow!("HTTP {}: {}", status, status_description(status)))
-            }
-            Err(e) => {
-                // Network error - retry
-                if attempt < MAX_RETRIES {
-                    log::warn!(
-                        "Network error (attempt {}/{}): {}, retrying in {}ms...",
-                        attempt + 1,
-                        MAX_RETRIES + 1,
-                        e,
-                        backoff_ms
-                    );
-                    std::thread::sleep(Duration::from_millis(backoff_ms));
-                    backoff_ms *= 2;
-                    continue;
-                }
-                Err(anyhow!("Network error: {}", e))
-            }
-        }
-    }
+        Ok(request.send_json(body))
+    })?;
 
-    Err(anyhow!("Max retries exceeded"))
+    let status = response.status().as_u16();
+    let body_str = if response.status().is_success() {
+        response.into_body().read_to_string()?
+    } else {
+        response.into_body().read_to_string().unwrap_or_default()
+    };
+    Ok((status, body_str))
 }
 
 /// Send a POST request with JSON body and return the response status and body.
@@ -124,9 +546,24 @@ pub fn post_json_raw(
     bearer_token: Option<&str>,
     extra_headers: &[(&str, &str)],
     body: &Value,
+    settings: &RequestSettings,
+) -> Result<(u16, String)> {
+    exchange(url, body, || {
+        timed(url, |stopwatch| send_json_once(url, bearer_token, extra_headers, body, settings, stopwatch))
+    })
+}
+
+/// The network half of [`post_json_raw`].
+fn send_json_once(
+    url: &str,
+    bearer_token: Option<&str>,
+    extra_headers: &[(&str, &str)],
+    body: &Value,
+    settings: &RequestSettings,
+    stopwatch: &mut Stopwatch,
 ) -> Result<(u16, String)> {
     // Use create_agent with http_status_as_error=false to get response body for all status codes
-    let agent = create_agent(false);
+    let agent = create_agent(false, settings.timeout());
 
     let mut request = agent.post(url);
 
@@ -140,6 +577,7 @@ pub fn post_json_raw(
 
     match request.send_json(body) {
         Ok(response) => {
+            stopwatch.responded();
             let status = response.status().as_u16();
             let body_str = response
                 .into_body()
@@ -147,17 +585,286 @@ pub fn post_json_raw(
                 .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
             Ok((status, body_str))
         }
-        Err(e) => Err(anyhow!("Network error: {}", e)),
+        Err(e) => Err(NetworkError(e.to_string()).into()),
     }
 }
 
+/// Send a GET request and return the response status and parsed JSON body.
+/// Retries network errors and 429 and 5xx responses with exponential backoff,
+/// honoring the server's `Retry-After` header, like [`post_json`]; the last
+/// status is returned for the caller to handle.
+/// Respects HTTP_PROXY/HTTPS_PROXY environment variables.
+pub fn get_json(
+    url: &str,
+    bearer_token: Option<&str>,
+    extra_headers: &[(&str, &str)],
+    settings: &RequestSettings,
+) -> Result<(u16, Value)> {
+    timed(url, |stopwatch| get_json_with_retry(url, bearer_token, extra_headers, settings, stopwatch))
+}
+
+/// The retry loop of [`get_json`].
+fn get_json_with_retry(
+    url: &str,
+    bearer_token: Option<&str>,
+    extra_headers: &[(&str, &str)],
+    settings: &RequestSettings,
+    stopwatch: &mut Stopwatch,
+) -> Result<(u16, Value)> {
+    let agent = create_agent(false, settings.timeout());
+
+    let response = send_with_retry(settings, stopwatch, || {
+        let mut request = agent.get(url);
+
+        if let Some(token) = bearer_token {
+            request = request.header("Authorization", &format!("Bearer {}", token));
+        }
+
+        for (k, v) in extra_headers {
+            request = request.header(*k, *v);
+        }
+
+        Ok(request.call())
+    })?;
+
+    let status = response.status().as_u16();
+    let body_str = response
+        .into_body()
+        .read_to_string()
+        .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+    let json = serde_json::from_str(&body_str).unwrap_or(Value::Null);
+    Ok((status, json))
+}
+
+/// Send a POST request with JSON body and pass each line of the streamed
+/// response body to `on_line` as it arrives (e.g., NDJSON progress events).
+/// Has no overall timeout, since streams may legitimately run for minutes.
+/// Respects HTTP_PROXY/HTTPS_PROXY environment variables.
+pub fn post_json_lines(
+    url: &str,
+    body: &Value,
+    mut on_line: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let agent = create_agent(false, None);
+
+    let response = agent
+        .post(url)
+        .send_json(body)
+        .map_err(|e| NetworkError(e.to_string()))?;
+
+    let status = response.status().as_u16();
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("HTTP {}: {}", status, status_description(status)));
+    }
+
+    let reader = BufReader::new(response.into_body().into_reader());
+    for line in reader.lines() {
+        on_line(&line?)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// AWS Signature Version 4
+// ============================================================================
+
+/// AWS credentials and signing scope for SigV4-signed requests.
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Session token for temporary (STS) credentials
+    pub session_token: Option<String>,
+    pub region: String,
+    /// Signing service name (e.g., `bedrock`)
+    pub service: String,
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex_encode(&Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything except the RFC 3986 unreserved characters
+/// (and `/` when `keep_slash` is set), as SigV4 canonicalization requires.
+pub fn aws_uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Format a time as an ISO 8601 basic timestamp (`20150830T123600Z`).
+fn amz_timestamp(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let t = UtcDateTime::from_unix(secs);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    )
+}
+
+/// Compute the SigV4 headers (`x-amz-date`, `x-amz-content-sha256`,
+/// `x-amz-security-token`, `authorization`) for a request.
+fn sigv4_headers(
+    method: &str,
+    url: &str,
+    payload: &[u8],
+    credentials: &AwsCredentials,
+    now: SystemTime,
+) -> Result<Vec<(String, String)>> {
+    let uri: ureq::http::Uri = url.parse().map_err(|e| anyhow!("Invalid URL {}: {}", url, e))?;
+    let host = uri.authority().map(|a| a.as_str()).ok_or_else(|| anyhow!("URL has no host: {}", url))?;
+
+    let amz_date = amz_timestamp(now);
+    let payload_hash = sha256_hex(payload);
+
+    let mut headers = vec![
+        ("host".to_string(), host.to_string()),
+        ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+    ];
+    if let Some(ref token) = credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+
+    let signed_headers = headers.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = sigv4_canonical_request(method, &uri, &headers, &payload_hash);
+    log::trace!("SigV4 canonical request:\n{}", canonical_request);
+    let (scope, signature) = sigv4_sign(&canonical_request, &amz_date, credentials);
+
+    // Host is set by the HTTP client itself
+    headers.remove(0);
+    headers.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    Ok(headers)
+}
+
+/// Build the SigV4 canonical request. `headers` must have lowercase names,
+/// sorted by name, and are all signed.
+fn sigv4_canonical_request(
+    method: &str,
+    uri: &ureq::http::Uri,
+    headers: &[(String, String)],
+    payload_hash: &str,
+) -> String {
+    // Non-S3 services sign the already-encoded path, encoded once more
+    let canonical_uri = aws_uri_encode(uri.path(), true);
+    let mut query: Vec<&str> = uri.query().unwrap_or("").split('&').filter(|p| !p.is_empty()).collect();
+    query.sort_unstable();
+    let canonical_query = query
+        .iter()
+        .map(|p| if p.contains('=') { p.to_string() } else { format!("{}=", p) })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join(";");
+
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, canonical_uri, canonical_query, canonical_headers, signed_headers, payload_hash
+    )
+}
+
+/// Sign a canonical request made at `amz_date`, returning the credential
+/// scope and the hex signature.
+fn sigv4_sign(canonical_request: &str, amz_date: &str, credentials: &AwsCredentials) -> (String, String) {
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, credentials.region, credentials.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", credentials.secret_access_key).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, &credentials.region);
+    let k_service = hmac_sha256(&k_region, &credentials.service);
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    (scope, hex_encode(&hmac_sha256(&k_signing, &string_to_sign)))
+}
+
+/// Send a SigV4-signed POST request with JSON body and return the response
+/// status and body. Each attempt is signed afresh.
+/// Includes exponential backoff retry for 429 and 5xx errors, honoring the
+/// server's `Retry-After` header when present; other statuses are returned
+/// to the caller.
+/// Respects HTTP_PROXY/HTTPS_PROXY environment variables.
+pub fn post_json_signed(
+    url: &str,
+    body: &Value,
+    credentials: &AwsCredentials,
+    extra_headers: &[(&str, &str)],
+    settings: &RequestSettings,
+) -> Result<(u16, String)> {
+    exchange(url, body, || {
+        timed(url, |stopwatch| send_signed_with_retry(url, body, credentials, extra_headers, settings, stopwatch))
+    })
+}
+
+/// The network half of [`post_json_signed`].
+fn send_signed_with_retry(
+    url: &str,
+    body: &Value,
+    credentials: &AwsCredentials,
+    extra_headers: &[(&str, &str)],
+    settings: &RequestSettings,
+    stopwatch: &mut Stopwatch,
+) -> Result<(u16, String)> {
+    let agent = create_agent(false, settings.timeout());
+    let payload = serde_json::to_vec(body)?;
+
+    let response = send_with_retry(settings, stopwatch, || {
+        let mut request = agent.post(url).header("content-type", "application/json");
+        for (k, v) in extra_headers {
+            request = request.header(*k, *v);
+        }
+        for (k, v) in sigv4_headers("POST", url, &payload, credentials, SystemTime::now())? {
+            request = request.header(k, v);
+        }
+
+        Ok(request.send(&payload[..]))
+    })?;
+
+    let status = response.status().as_u16();
+    let body_str = response
+        .into_body()
+        .read_to_string()
+        .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+    Ok((status, body_str))
+}
+
 // ============================================================================
 // API Response Utilities
 // ============================================================================
 
 /// Extract the content string from an OpenAI-compatible chat completion response.
 ///
-/// Looks for `choices[0].message.content` in the response JSON.
+/// Looks for `choices[0].message.content` in the response JSON. A separate
+/// `reasoning_content` field (DeepSeek's reasoner) is ignored, and a leading
+/// `<think>...</think>` block that reasoning models put in the content is
+/// stripped.
 pub fn extract_content_from_response(resp_json: &Value) -> Result<&str> {
     resp_json
         .get("choices")
@@ -165,9 +872,34 @@ pub fn extract_content_from_response(resp_json: &Value) -> Result<&str> {
         .and_then(|c| c.get("message"))
         .and_then(|m| m.get("content"))
         .and_then(|c| c.as_str())
+        .map(strip_thinking)
         .ok_or_else(|| anyhow!("API response missing choices[0].message.content"))
 }
 
+/// Remove a leading `<think>...</think>` block of model reasoning.
+fn strip_thinking(content: &str) -> &str {
+    let trimmed = content.trim_start();
+    match trimmed.strip_prefix("<think>").and_then(|rest| rest.split_once("</think>")) {
+        Some((_, answer)) => answer.trim_start(),
+        None => content,
+    }
+}
+
+/// Remove a Markdown code fence the model may wrap JSON in despite instructions.
+pub fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => rest
+            .split_once('\n')
+            .map(|(_, body)| body)
+            .unwrap_or(rest)
+            .trim_end()
+            .trim_end_matches("```")
+            .trim(),
+        None => trimmed,
+    }
+}
+
 /// Check if the response was truncated due to max_tokens limit.
 ///
 /// Returns `true` if `choices[0].finish_reason` is "length",
@@ -182,6 +914,47 @@ pub fn is_truncated(resp_json: &Value) -> bool {
         .unwrap_or(false)
 }
 
+/// Token counts reported by a provider for one or more requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    /// Add another request's counts to these.
+    pub fn add(&mut self, other: Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Extract token usage from a chat completion response, if the provider reported it.
+///
+/// Reads the top-level `usage` object, which streaming providers put on the
+/// final chunk. Accepts OpenAI's `prompt_tokens`/`completion_tokens` as well
+/// as Anthropic's `input_tokens`/`output_tokens`; a missing `total_tokens`
+/// is computed from the other two.
+pub fn extract_usage(resp_json: &Value) -> Option<Usage> {
+    let usage = resp_json.get("usage").filter(|u| u.is_object())?;
+    let count = |keys: &[&str]| keys.iter().find_map(|k| usage.get(*k).and_then(|v| v.as_u64()));
+
+    let prompt_tokens = count(&["prompt_tokens", "input_tokens"]);
+    let completion_tokens = count(&["completion_tokens", "output_tokens"]);
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+    let prompt_tokens = prompt_tokens.unwrap_or(0);
+    let completion_tokens = completion_tokens.unwrap_or(0);
+    Some(Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: count(&["total_tokens"]).unwrap_or(prompt_tokens + completion_tokens),
+    })
+}
+
 /// Extract an error message from an API error response, if present.
 ///
 /// Looks for `error.message` or `error.error` in the response JSON.
@@ -193,4 +966,173 @@ pub fn extract_api_error(resp_json: &Value) -> Option<String> {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Wed, 21 Oct 2015 07:28:00 GMT`
+    const RETRY_DATE_SECS: u64 = 1_445_412_480;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn retry_after_accepts_delay_seconds() {
+        assert_eq!(parse_retry_after("2", at(0)), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_after(" 120 ", at(0)), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let value = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(parse_retry_after(value, at(RETRY_DATE_SECS - 30)), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after(value, at(RETRY_DATE_SECS + 30)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_rejects_other_values() {
+        let now = at(RETRY_DATE_SECS);
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 UTC", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Foo 2015 07:28:00 GMT", now), None);
+    }
+
+    #[test]
+    fn retry_delay_prefers_retry_after_up_to_the_cap() {
+        let now = at(RETRY_DATE_SECS);
+        assert_eq!(retry_delay(Some("2"), 1000, now), Duration::from_secs(2));
+        assert_eq!(retry_delay(Some("86400"), 1000, now), Duration::from_secs(MAX_RETRY_AFTER_SECS));
+        assert_eq!(retry_delay(Some("soon"), 1000, now), Duration::from_millis(1000));
+        assert_eq!(retry_delay(None, 500, now), Duration::from_millis(500));
+        assert_eq!(retry_delay(None, 600_000, now), Duration::from_secs(MAX_RETRY_AFTER_SECS));
+    }
+
+    struct FakeResponse(u16);
+
+    impl RetryableResponse for FakeResponse {
+        fn status_code(&self) -> u16 {
+            self.0
+        }
+
+        fn retry_after(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    /// Run [`send_with_retry`] over scripted attempts, returning the result
+    /// and how many attempts were made.
+    fn retry_script(mut attempts: Vec<Result<std::result::Result<u16, &'static str>>>) -> (Result<u16>, usize) {
+        let settings = RequestSettings { max_retries: 2, initial_backoff_ms: 0, ..Default::default() };
+        let total = attempts.len();
+        attempts.reverse();
+        let result = send_with_retry(&settings, &mut Stopwatch::new(), || {
+            attempts.pop().expect("too many attempts").map(|r| r.map(FakeResponse))
+        });
+        (result.map(|r| r.0), total - attempts.len())
+    }
+
+    #[test]
+    fn retries_network_errors_and_transient_statuses() {
+        let (result, attempts) = retry_script(vec![Ok(Err("connection refused")), Ok(Ok(503)), Ok(Ok(200))]);
+        assert_eq!((result.unwrap(), attempts), (200, 3));
+
+        let (result, attempts) = retry_script(vec![Ok(Ok(429)), Ok(Ok(500)), Ok(Ok(503))]);
+        assert_eq!((result.unwrap(), attempts), (503, 3));
+
+        let (result, attempts) = retry_script(vec![Ok(Err("timed out")), Ok(Err("timed out")), Ok(Err("timed out"))]);
+        assert!(result.unwrap_err().downcast_ref::<NetworkError>().is_some());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn returns_other_statuses_and_failures_without_retrying() {
+        let (result, attempts) = retry_script(vec![Ok(Ok(404))]);
+        assert_eq!((result.unwrap(), attempts), (404, 1));
+
+        let (result, attempts) = retry_script(vec![Err(anyhow!("Invalid URL"))]);
+        assert_eq!((result.unwrap_err().to_string(), attempts), ("Invalid URL".to_string(), 1));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(next_backoff(1000), 2000);
+        assert_eq!(next_backoff(40_000), MAX_RETRY_AFTER_SECS * 1000);
+        assert_eq!(next_backoff(u64::MAX), MAX_RETRY_AFTER_SECS * 1000);
+    }
+
+    /// Credentials from the AWS Signature Version 4 test suite.
+    fn example_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            region: "us-east-1".to_string(),
+            service: "service".to_string(),
+        }
+    }
+
+    fn example_headers() -> Vec<(String, String)> {
+        vec![
+            ("host".to_string(), "example.amazonaws.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ]
+    }
+
+    #[test]
+    fn amz_timestamp_uses_the_basic_format() {
+        assert_eq!(amz_timestamp(at(1_440_938_160)), "20150830T123600Z");
+    }
+
+    #[test]
+    fn sigv4_matches_the_aws_get_vanilla_example() {
+        let uri = "https://example.amazonaws.com/".parse().unwrap();
+        let canonical = sigv4_canonical_request("GET", &uri, &example_headers(), &sha256_hex(b""));
+        assert_eq!(
+            canonical,
+            "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let (scope, signature) = sigv4_sign(&canonical, "20150830T123600Z", &example_credentials());
+        assert_eq!(scope, "20150830/us-east-1/service/aws4_request");
+        assert_eq!(signature, "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31");
+    }
+
+    #[test]
+    fn sigv4_sorts_query_parameters() {
+        let uri = "https://example.amazonaws.com/?Param2=value2&Param1=value1".parse().unwrap();
+        let canonical = sigv4_canonical_request("GET", &uri, &example_headers(), &sha256_hex(b""));
+        assert!(canonical.starts_with("GET\n/\nParam1=value1&Param2=value2\n"));
+
+        let (_, signature) = sigv4_sign(&canonical, "20150830T123600Z", &example_credentials());
+        assert_eq!(signature, "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500");
+    }
+
+    #[test]
+    fn sigv4_headers_sign_the_payload_and_session_token() {
+        let credentials = AwsCredentials {
+            session_token: Some("token".to_string()),
+            ..example_credentials()
+        };
+        let headers = sigv4_headers(
+            "POST",
+            "https://example.amazonaws.com/model/invoke",
+            b"{}",
+            &credentials,
+            at(1_440_938_160),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = headers.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, ["x-amz-content-sha256", "x-amz-date", "x-amz-security-token", "authorization"]);
+        assert_eq!(headers[0].1, sha256_hex(b"{}"));
+        assert!(headers[3].1.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token, Signature="
+        ));
+    }
+}
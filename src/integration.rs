@@ -4,20 +4,24 @@
 //! - completions: Tab completion for shell-ai commands
 //! - aliases: ?? for suggest, explain for explain
 //! - keybinding: Ctrl+G inline transform with progress indicator
+//! - explain-keybinding: Alt+E explanation of the current line, printed below it
 
 use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell as ClapShell};
+use clap_complete_nushell::Nushell;
 use colored::Colorize;
 use serde::Serialize;
 use strum::{Display, EnumIter, IntoEnumIterator};
 
-use crate::config::OutputFormat;
+use crate::config::{self, OutputFormat};
+use crate::output::outln;
+use crate::shell::ShellType;
 use crate::Cli;
 
 /// Arguments for the integration subcommand.
@@ -36,11 +40,17 @@ pub enum IntegrationAction {
     Update(IntegrationUpdateArgs),
     /// Show available features, presets, and installed integrations.
     List,
+    /// Check whether installed integration files match what this version would generate.
+    Status(IntegrationStatusArgs),
+    /// Add a line sourcing the integration file to your shell's rc file.
+    Install(IntegrationInstallArgs),
+    /// Remove the line added by `install` from your shell's rc file.
+    Uninstall(IntegrationInstallArgs),
 }
 
 #[derive(Parser, Debug)]
 pub struct IntegrationGenerateArgs {
-    /// Target shell: bash, zsh, fish, powershell
+    /// Target shell: bash, zsh, fish, powershell, nu
     #[arg(value_enum)]
     pub shell: ShellType,
 
@@ -63,6 +73,30 @@ pub struct IntegrationGenerateArgs {
     /// Overwrite existing file without confirmation.
     #[arg(long, short = 'y')]
     pub overwrite: bool,
+
+    /// Key chord for the transform keybinding, e.g. ctrl-g (default), ctrl-o, alt-g
+    #[arg(long, value_name = "CHORD", default_value = "ctrl-g")]
+    pub key: KeyChord,
+
+    /// Key chord for the explain keybinding, e.g. alt-e (default), alt-x
+    #[arg(long, value_name = "CHORD", default_value = "alt-e")]
+    pub explain_key: KeyChord,
+
+    /// Show a static "thinking..." message in the transform keybinding instead of
+    /// the animated spinner, for less shell overhead on slow machines.
+    #[arg(long)]
+    pub no_animation: bool,
+
+    /// Also add a line sourcing the file to your shell's rc file.
+    #[arg(long, conflicts_with = "stdout")]
+    pub install: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct IntegrationInstallArgs {
+    /// Target shell. If omitted, the shell is detected from the environment.
+    #[arg(value_enum)]
+    pub shell: Option<ShellType>,
 }
 
 #[derive(Parser, Debug)]
@@ -72,16 +106,11 @@ pub struct IntegrationUpdateArgs {
     pub shell: Option<ShellType>,
 }
 
-/// Supported shell types.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Display, EnumIter)]
-#[strum(serialize_all = "lowercase")]
-pub enum ShellType {
-    Bash,
-    Zsh,
-    Fish,
-    #[clap(name = "powershell")]
-    #[strum(serialize = "powershell")]
-    PowerShell,
+#[derive(Parser, Debug)]
+pub struct IntegrationStatusArgs {
+    /// Target shell. If omitted, checks all existing integration files.
+    #[arg(value_enum)]
+    pub shell: Option<ShellType>,
 }
 
 impl ShellType {
@@ -92,6 +121,9 @@ impl ShellType {
             ShellType::Zsh => "zsh",
             ShellType::Fish => "fish",
             ShellType::PowerShell => "ps1",
+            ShellType::Nu => "nu",
+            ShellType::Elvish => "elv",
+            ShellType::Xonsh => "xsh",
         }
     }
 
@@ -102,20 +134,227 @@ impl ShellType {
             ShellType::Zsh => "~/.zshrc",
             ShellType::Fish => "~/.config/fish/config.fish",
             ShellType::PowerShell => "$PROFILE",
+            ShellType::Nu => "$nu.config-path",
+            ShellType::Elvish => "~/.config/elvish/rc.elv",
+            ShellType::Xonsh => "~/.xonshrc",
+        }
+    }
+
+    /// Resolve the rc file that `integration install` edits.
+    ///
+    /// PowerShell and Nushell are asked for their profile/config path, since it
+    /// varies by platform and installation; if that fails, the usual default is used.
+    pub fn rc_file_path(&self) -> Option<PathBuf> {
+        let home = dirs::home_dir()?;
+        match self {
+            ShellType::Bash => Some(home.join(".bashrc")),
+            ShellType::Zsh => {
+                let dir = std::env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or(home);
+                Some(dir.join(".zshrc"))
+            }
+            ShellType::Fish => {
+                let config = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| home.join(".config"));
+                Some(config.join("fish").join("config.fish"))
+            }
+            ShellType::PowerShell => {
+                query_shell_path("pwsh", &["-NoProfile", "-Command", "$PROFILE"]).or_else(|| {
+                    let dir = if cfg!(windows) {
+                        home.join("Documents").join("PowerShell")
+                    } else {
+                        home.join(".config").join("powershell")
+                    };
+                    Some(dir.join("Microsoft.PowerShell_profile.ps1"))
+                })
+            }
+            ShellType::Nu => query_shell_path("nu", &["--no-config-file", "-c", "$nu.config-path"])
+                .or_else(|| Some(dirs::config_dir()?.join("nushell").join("config.nu"))),
+            ShellType::Elvish if cfg!(windows) => Some(dirs::config_dir()?.join("elvish").join("rc.elv")),
+            ShellType::Elvish => {
+                let config = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| home.join(".config"));
+                Some(config.join("elvish").join("rc.elv"))
+            }
+            ShellType::Xonsh => Some(home.join(".xonshrc")),
+        }
+    }
+}
+
+/// Run a shell to print one of its own paths, returning `None` on any failure.
+fn query_shell_path(program: &str, args: &[&str]) -> Option<PathBuf> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let path = String::from_utf8(output.stdout).ok()?;
+    let path = path.trim();
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// Check both keybinding chords for `shell`, and that enabled keybindings don't share one.
+fn validate_keys(
+    shell: ShellType,
+    features: &HashSet<Feature>,
+    key: KeyChord,
+    explain_key: KeyChord,
+) -> Result<(), String> {
+    key.validate_for(shell)?;
+    explain_key.validate_for(shell)?;
+    if key == explain_key
+        && features.contains(&Feature::Keybinding)
+        && features.contains(&Feature::ExplainKeybinding)
+    {
+        return Err(format!(
+            "The transform and explain keybindings can't both use {}; pick another --explain-key",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// Modifier key of a keybinding chord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyModifier {
+    Ctrl,
+    Alt,
+}
+
+/// Key chord for a keybinding, written as `ctrl-<key>` or `alt-<key>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    modifier: KeyModifier,
+    key: char,
+}
+
+impl Default for KeyChord {
+    fn default() -> Self {
+        KeyChord {
+            modifier: KeyModifier::Ctrl,
+            key: 'g',
+        }
+    }
+}
+
+impl KeyChord {
+    /// Default chord for the explain keybinding: Alt+E.
+    pub const EXPLAIN_DEFAULT: KeyChord = KeyChord {
+        modifier: KeyModifier::Alt,
+        key: 'e',
+    };
+
+    /// Check that the chord can be bound in the given shell.
+    pub fn validate_for(&self, shell: ShellType) -> Result<(), String> {
+        if self.modifier == KeyModifier::Ctrl {
+            // Terminals send these as control characters the shell already relies on
+            let reserved = match self.key {
+                'c' => Some("interrupt"),
+                'd' => Some("end of input"),
+                'i' => Some("Tab"),
+                'j' | 'm' => Some("Enter"),
+                _ => None,
+            };
+            if let Some(meaning) = reserved {
+                return Err(format!("{} is indistinguishable from {} in a terminal", self, meaning));
+            }
+            if self.key.is_ascii_digit() && shell != ShellType::PowerShell {
+                return Err(format!(
+                    "{} can't be bound in {}: terminals don't send Ctrl with digits",
+                    self, shell
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Human-readable label, e.g. `Ctrl+G`.
+    pub fn label(&self) -> String {
+        let modifier = match self.modifier {
+            KeyModifier::Ctrl => "Ctrl",
+            KeyModifier::Alt => "Alt",
+        };
+        format!("{}+{}", modifier, self.key.to_ascii_uppercase())
+    }
+
+    /// The chord in the shell's own key notation.
+    fn notation(&self, shell: ShellType) -> String {
+        let key = self.key;
+        match (shell, self.modifier) {
+            (ShellType::Bash, KeyModifier::Ctrl) => format!("\\C-{}", key),
+            (ShellType::Bash, KeyModifier::Alt) => format!("\\e{}", key),
+            (ShellType::Zsh, KeyModifier::Ctrl) => format!("^{}", key.to_ascii_uppercase()),
+            (ShellType::Zsh, KeyModifier::Alt) => format!("^[{}", key),
+            (ShellType::Fish, KeyModifier::Ctrl) => format!("\\c{}", key),
+            (ShellType::Fish, KeyModifier::Alt) => format!("\\e{}", key),
+            (ShellType::PowerShell, KeyModifier::Ctrl) => format!("Ctrl+{}", key),
+            (ShellType::PowerShell, KeyModifier::Alt) => format!("Alt+{}", key),
+            // Nushell takes the modifier and key code as separate fields
+            (ShellType::Nu, _) => format!("char_{}", key),
+            (ShellType::Elvish, KeyModifier::Ctrl) => format!("Ctrl-{}", key.to_ascii_uppercase()),
+            (ShellType::Elvish, KeyModifier::Alt) => format!("Alt-{}", key),
+            // prompt_toolkit key sequence arguments
+            (ShellType::Xonsh, KeyModifier::Ctrl) => format!("'c-{}'", key),
+            (ShellType::Xonsh, KeyModifier::Alt) => format!("'escape', '{}'", key),
         }
     }
+
+    /// Substitute the chord's placeholders in a keybinding template.
+    fn apply(&self, template: &str, shell: ShellType) -> String {
+        let nu_modifier = match self.modifier {
+            KeyModifier::Ctrl => "control",
+            KeyModifier::Alt => "alt",
+        };
+        template
+            .replace("{{KEY_LABEL}}", &self.label())
+            .replace("{{KEY_MODIFIER}}", nu_modifier)
+            .replace("{{KEY_CODE}}", &self.notation(shell))
+            .replace("{{KEY}}", &self.notation(shell))
+    }
 }
 
-impl FromStr for ShellType {
+impl std::fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let modifier = match self.modifier {
+            KeyModifier::Ctrl => "ctrl",
+            KeyModifier::Alt => "alt",
+        };
+        write!(f, "{}-{}", modifier, self.key)
+    }
+}
+
+impl FromStr for KeyChord {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "bash" => Ok(ShellType::Bash),
-            "zsh" => Ok(ShellType::Zsh),
-            "fish" => Ok(ShellType::Fish),
-            "powershell" => Ok(ShellType::PowerShell),
-            _ => Err(format!("Unknown shell: {}", s)),
+        let lower = s.trim().to_lowercase();
+        let (modifier, key) = lower
+            .split_once(['-', '+'])
+            .ok_or_else(|| format!("Invalid key chord '{}': expected e.g. ctrl-g or alt-g", s))?;
+        let modifier = match modifier {
+            "ctrl" | "control" | "c" => KeyModifier::Ctrl,
+            "alt" | "meta" | "m" => KeyModifier::Alt,
+            _ => {
+                return Err(format!(
+                    "Invalid key chord '{}': modifier must be ctrl or alt",
+                    s
+                ))
+            }
+        };
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(key), None) if key.is_ascii_lowercase() || key.is_ascii_digit() => {
+                Ok(KeyChord { modifier, key })
+            }
+            _ => Err(format!(
+                "Invalid key chord '{}': key must be a single letter or digit",
+                s
+            )),
         }
     }
 }
@@ -140,6 +379,7 @@ impl Preset {
             Preset::Standard => [Feature::Completions, Feature::Aliases]
                 .into_iter()
                 .collect(),
+            // The explain keybinding is opt-in (`--add explain-keybinding`)
             Preset::Full => [Feature::Completions, Feature::Aliases, Feature::Keybinding]
                 .into_iter()
                 .collect(),
@@ -170,6 +410,10 @@ pub enum Feature {
     Aliases,
     /// Ctrl+G keybinding for inline transform
     Keybinding,
+    /// Alt+E keybinding to explain the current line
+    #[strum(serialize = "explain-keybinding")]
+    #[value(name = "explain-keybinding")]
+    ExplainKeybinding,
 }
 
 impl FromStr for Feature {
@@ -180,6 +424,7 @@ impl FromStr for Feature {
             "completions" => Ok(Feature::Completions),
             "aliases" => Ok(Feature::Aliases),
             "keybinding" => Ok(Feature::Keybinding),
+            "explain-keybinding" => Ok(Feature::ExplainKeybinding),
             _ => Err(format!("Unknown feature: {}", s)),
         }
     }
@@ -191,6 +436,12 @@ struct IntegrationPreferences {
     preset: Preset,
     add: Vec<Feature>,
     remove: Vec<Feature>,
+    key: KeyChord,
+    explain_key: KeyChord,
+    /// Whether the transform keybinding animates while it waits
+    animation: bool,
+    /// shell-ai version that generated the file, if recorded
+    version: Option<String>,
 }
 
 // =============================================================================
@@ -225,6 +476,28 @@ struct InstalledIntegration {
     path: String,
 }
 
+/// How an installed integration file compares to a freshly generated one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum FileStatus {
+    UpToDate,
+    Stale,
+    /// The file's header couldn't be read or parsed
+    Unrecognized,
+}
+
+#[derive(Serialize)]
+struct IntegrationStatus {
+    shell: String,
+    path: String,
+    status: FileStatus,
+    /// Version from the file's "Generated by" line
+    generated_by: Option<String>,
+    /// Why the file couldn't be checked, when unrecognized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 /// Resolve final feature set from preset + modifiers.
 fn resolve_features(preset: Preset, add: &[Feature], remove: &[Feature]) -> HashSet<Feature> {
     let mut features = preset.features();
@@ -239,8 +512,7 @@ fn resolve_features(preset: Preset, add: &[Feature], remove: &[Feature]) -> Hash
 
 /// Get the integration file path for a shell.
 fn integration_file_path(shell: ShellType) -> Option<PathBuf> {
-    let mut base = dirs::config_dir()?;
-    base.push("shell-ai");
+    let mut base = config::config_dir()?;
     base.push(format!("integration.{}", shell.extension()));
     Some(base)
 }
@@ -268,6 +540,9 @@ fn generate_header(
     preset: Preset,
     add: &[Feature],
     remove: &[Feature],
+    key: KeyChord,
+    explain_key: KeyChord,
+    animation: bool,
 ) -> String {
     let version = env!("CARGO_PKG_VERSION");
     let modifiers = format_modifiers(add, remove);
@@ -279,15 +554,22 @@ fn generate_header(
 # DO NOT EDIT THIS FILE MANUALLY
 # Regenerate with: shell-ai integration update {shell}
 #
+# @version: {version}
 # @shell: {shell}
 # @preset: {preset}
 # @modifiers: {modifiers}
+# @key: {key}
+# @explain-key: {explain_key}
+# @animation: {animation}
 #
 "#,
         version = version,
-        shell = shell.to_string(),
-        preset = preset.to_string(),
+        shell = shell,
+        preset = preset,
         modifiers = modifiers,
+        key = key,
+        explain_key = explain_key,
+        animation = if animation { "on" } else { "off" },
     )
 }
 
@@ -328,6 +610,10 @@ fn parse_header(content: &str) -> Result<IntegrationPreferences, String> {
     let mut preset = None;
     let mut add = Vec::new();
     let mut remove = Vec::new();
+    // Files generated before @key existed always used Ctrl+G
+    let mut key = KeyChord::default();
+    let mut explain_key = KeyChord::EXPLAIN_DEFAULT;
+    let mut animation = true;
 
     for line in content.lines().take(15) {
         if let Some(value) = line.strip_prefix("# @shell: ") {
@@ -344,6 +630,16 @@ fn parse_header(content: &str) -> Result<IntegrationPreferences, String> {
             let (a, r) = parse_modifiers(value)?;
             add = a;
             remove = r;
+        } else if let Some(value) = line.strip_prefix("# @key: ") {
+            key = <KeyChord as FromStr>::from_str(value)?;
+        } else if let Some(value) = line.strip_prefix("# @explain-key: ") {
+            explain_key = <KeyChord as FromStr>::from_str(value)?;
+        } else if let Some(value) = line.strip_prefix("# @animation: ") {
+            animation = match value.trim() {
+                "on" => true,
+                "off" => false,
+                other => return Err(format!("Invalid animation: {} (expected on or off)", other)),
+            };
         }
     }
 
@@ -352,33 +648,110 @@ fn parse_header(content: &str) -> Result<IntegrationPreferences, String> {
         preset: preset.ok_or("Missing @preset in header")?,
         add,
         remove,
+        key,
+        explain_key,
+        animation,
+        version: generated_by_version(content),
     })
 }
 
 /// Generate shell completions using clap_complete.
 fn generate_completions(shell: ShellType) -> String {
     let mut cmd = Cli::command();
-    let clap_shell = match shell {
-        ShellType::Bash => ClapShell::Bash,
-        ShellType::Zsh => ClapShell::Zsh,
-        ShellType::Fish => ClapShell::Fish,
-        ShellType::PowerShell => ClapShell::PowerShell,
-    };
-
     let mut buf = Vec::new();
-    generate(clap_shell, &mut cmd, "shell-ai", &mut buf);
+    match shell {
+        ShellType::Bash => generate(ClapShell::Bash, &mut cmd, "shell-ai", &mut buf),
+        ShellType::Zsh => generate(ClapShell::Zsh, &mut cmd, "shell-ai", &mut buf),
+        ShellType::Fish => generate(ClapShell::Fish, &mut cmd, "shell-ai", &mut buf),
+        ShellType::PowerShell => generate(ClapShell::PowerShell, &mut cmd, "shell-ai", &mut buf),
+        ShellType::Nu => generate(Nushell, &mut cmd, "shell-ai", &mut buf),
+        ShellType::Elvish => generate(ClapShell::Elvish, &mut cmd, "shell-ai", &mut buf),
+        ShellType::Xonsh => return generate_xonsh_completions(&cmd),
+    }
     String::from_utf8_lossy(&buf).into_owned()
 }
 
+/// Generate a xonsh completer for shell-ai, which clap_complete doesn't support.
+///
+/// Completes subcommand names, then the global options plus those of the
+/// subcommand on the line. Option values aren't completed.
+fn generate_xonsh_completions(cmd: &clap::Command) -> String {
+    let long_flags = |c: &clap::Command| -> Vec<String> {
+        c.get_arguments()
+            .filter(|a| !a.is_hide_set())
+            .filter_map(|a| a.get_long().map(|long| format!("--{}", long)))
+            .chain(["--help".to_string()])
+            .collect()
+    };
+
+    let subcommands: Vec<String> = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| format!("    {}: {},", python_str(sub.get_name()), python_list(&long_flags(sub))))
+        .collect();
+
+    format!(
+        r#"from xonsh.completers.completer import add_one_completer
+from xonsh.completers.tools import contextual_command_completer_for
+
+_SHAI_GLOBAL_OPTIONS = {global}
+_SHAI_SUBCOMMANDS = {{
+{subcommands}
+}}
+
+@contextual_command_completer_for("shell-ai")
+def _shai_complete(command):
+    words = [arg.value for arg in command.args[1:command.arg_index]]
+    subcommand = next((w for w in words if w in _SHAI_SUBCOMMANDS), None)
+    if subcommand:
+        options = _SHAI_GLOBAL_OPTIONS + _SHAI_SUBCOMMANDS[subcommand]
+    else:
+        options = _SHAI_GLOBAL_OPTIONS + list(_SHAI_SUBCOMMANDS)
+    return {{o for o in options if o.startswith(command.prefix)}}
+
+add_one_completer("shell_ai", _shai_complete, "start")
+"#,
+        global = python_list(&long_flags(cmd)),
+        subcommands = subcommands.join("\n"),
+    )
+}
+
+/// Quote `s` as a Python (and xonsh) string literal.
+fn python_str(s: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Quote `items` as a Python list of strings.
+fn python_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| python_str(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
 /// Generate the full integration file content.
 fn generate_integration_file(
     shell: ShellType,
     preset: Preset,
     add: &[Feature],
     remove: &[Feature],
+    key: KeyChord,
+    explain_key: KeyChord,
+    animation: bool,
 ) -> String {
     let features = resolve_features(preset, add, remove);
-    let mut output = generate_header(shell, preset, add, remove);
+    let mut output = generate_header(shell, preset, add, remove, key, explain_key, animation);
 
     match shell {
         ShellType::Bash => {
@@ -390,7 +763,11 @@ fn generate_integration_file(
                 output.push_str(BASH_ALIASES);
             }
             if features.contains(&Feature::Keybinding) {
-                output.push_str(BASH_KEYBINDING);
+                let template = if animation { BASH_KEYBINDING } else { BASH_STATIC_KEYBINDING };
+                output.push_str(&key.apply(template, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(BASH_EXPLAIN_KEYBINDING, shell));
             }
         }
         ShellType::Zsh => {
@@ -402,7 +779,11 @@ fn generate_integration_file(
                 output.push_str(ZSH_ALIASES);
             }
             if features.contains(&Feature::Keybinding) {
-                output.push_str(ZSH_KEYBINDING);
+                let template = if animation { ZSH_KEYBINDING } else { ZSH_STATIC_KEYBINDING };
+                output.push_str(&key.apply(template, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(ZSH_EXPLAIN_KEYBINDING, shell));
             }
         }
         ShellType::Fish => {
@@ -414,7 +795,11 @@ fn generate_integration_file(
                 output.push_str(FISH_ALIASES);
             }
             if features.contains(&Feature::Keybinding) {
-                output.push_str(FISH_KEYBINDING);
+                let template = if animation { FISH_KEYBINDING } else { FISH_STATIC_KEYBINDING };
+                output.push_str(&key.apply(template, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(FISH_EXPLAIN_KEYBINDING, shell));
             }
         }
         ShellType::PowerShell => {
@@ -426,7 +811,57 @@ fn generate_integration_file(
                 output.push_str(POWERSHELL_ALIASES);
             }
             if features.contains(&Feature::Keybinding) {
-                output.push_str(POWERSHELL_KEYBINDING);
+                let template = if animation { POWERSHELL_KEYBINDING } else { POWERSHELL_STATIC_KEYBINDING };
+                output.push_str(&key.apply(template, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(POWERSHELL_EXPLAIN_KEYBINDING, shell));
+            }
+        }
+        ShellType::Nu => {
+            if features.contains(&Feature::Completions) {
+                output.push_str("\n# === Completions ===\n");
+                output.push_str(&generate_completions(shell));
+            }
+            if features.contains(&Feature::Aliases) {
+                output.push_str(NU_ALIASES);
+            }
+            if features.contains(&Feature::Keybinding) {
+                output.push_str(&key.apply(NU_KEYBINDING, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(NU_EXPLAIN_KEYBINDING, shell));
+            }
+        }
+        // Neither can redraw while a keybinding runs, so there is no animation to turn off
+        ShellType::Elvish => {
+            if features.contains(&Feature::Completions) {
+                output.push_str("\n# === Completions ===\n");
+                output.push_str(&generate_completions(shell));
+            }
+            if features.contains(&Feature::Aliases) {
+                output.push_str(ELVISH_ALIASES);
+            }
+            if features.contains(&Feature::Keybinding) {
+                output.push_str(&key.apply(ELVISH_KEYBINDING, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(ELVISH_EXPLAIN_KEYBINDING, shell));
+            }
+        }
+        ShellType::Xonsh => {
+            if features.contains(&Feature::Completions) {
+                output.push_str("\n# === Completions ===\n");
+                output.push_str(&generate_completions(shell));
+            }
+            if features.contains(&Feature::Aliases) {
+                output.push_str(XONSH_ALIASES);
+            }
+            if features.contains(&Feature::Keybinding) {
+                output.push_str(&key.apply(XONSH_KEYBINDING, shell));
+            }
+            if features.contains(&Feature::ExplainKeybinding) {
+                output.push_str(&explain_key.apply(XONSH_EXPLAIN_KEYBINDING, shell));
             }
         }
     }
@@ -435,7 +870,7 @@ fn generate_integration_file(
 }
 
 /// Replace home directory with $HOME for portable paths.
-fn path_with_home_var(path: &PathBuf) -> String {
+fn path_with_home_var(path: &Path) -> String {
     if let Some(home) = dirs::home_dir() {
         if let Ok(relative) = path.strip_prefix(&home) {
             return format!("$HOME/{}", relative.display());
@@ -444,27 +879,248 @@ fn path_with_home_var(path: &PathBuf) -> String {
     path.display().to_string()
 }
 
-/// Print sourcing instructions for the user.
-fn print_sourcing_instructions(shell: ShellType, path: &PathBuf) {
+/// The line that sources the integration file from the shell's rc file.
+fn source_line(shell: ShellType, path: &Path) -> String {
     let path_str = path_with_home_var(path);
 
+    match shell {
+        ShellType::Bash | ShellType::Zsh | ShellType::Fish => {
+            format!("[ -f \"{}\" ] && source \"{}\"", path_str, path_str)
+        }
+        ShellType::PowerShell => {
+            format!("if (Test-Path \"{}\") {{ . \"{}\" }}", path_str, path_str)
+        }
+        ShellType::Nu => {
+            // Nushell resolves `source` at parse time, so it can't be guarded by a
+            // file check, and `~` is expanded where `$HOME` is not
+            format!("source \"{}\"", path_str.replacen("$HOME", "~", 1))
+        }
+        ShellType::Elvish => {
+            // Elvish has no `source`, and only expands `~` at the start of a bareword
+            let path = match path_str.strip_prefix("$HOME/") {
+                Some(relative) if !relative.contains(char::is_whitespace) => format!("~/{}", relative),
+                _ => format!("'{}'", path.display()),
+            };
+            format!("use os; if (os:exists {0}) {{ eval (slurp < {0}) }}", path)
+        }
+        ShellType::Xonsh => {
+            let path = match path_str.strip_prefix("$HOME/") {
+                Some(relative) => format!("$HOME + {}", python_str(&format!("/{}", relative))),
+                None => python_str(&path_str),
+            };
+            format!(
+                "if __import__(\"os\").path.isfile(_shai_integration := {}): source @(_shai_integration)",
+                path
+            )
+        }
+    }
+}
+
+/// Print sourcing instructions for the user.
+fn print_sourcing_instructions(shell: ShellType, path: &Path) {
     println!(
         "\nAdd this to your shell configuration ({}):\n",
         shell.rc_file().cyan()
     );
+    println!("  {}", source_line(shell, path));
+    println!(
+        "\nOr run '{}' to add it automatically.",
+        format!("shell-ai integration install {}", shell).cyan()
+    );
+    println!();
+}
 
-    match shell {
-        ShellType::Bash | ShellType::Zsh | ShellType::Fish => {
-            println!("  [ -f \"{}\" ] && source \"{}\"", path_str, path_str);
-        }
-        ShellType::PowerShell => {
-            println!(
-                "  if (Test-Path \"{}\") {{ . \"{}\" }}",
-                path_str, path_str
+const RC_BLOCK_START: &str = "# >>> shell-ai >>>";
+const RC_BLOCK_END: &str = "# <<< shell-ai <<<";
+
+/// Split rc file content around the shell-ai block, if present.
+/// Returns the text before the block and the text after it, or an error if
+/// a marker is missing its partner, since the block's extent is then unknown.
+fn split_rc_block(content: &str) -> Result<Option<(&str, &str)>> {
+    let Some(start) = content.find(RC_BLOCK_START) else {
+        if content.contains(RC_BLOCK_END) {
+            anyhow::bail!(
+                "Found \"{}\" without \"{}\" before it\n\
+                 Hint: Fix or remove the shell-ai lines by hand, then try again.",
+                RC_BLOCK_END,
+                RC_BLOCK_START
             );
         }
+        return Ok(None);
+    };
+    let Some(end) = content[start..].find(RC_BLOCK_END) else {
+        anyhow::bail!(
+            "Found \"{}\" without \"{}\" after it\n\
+             Hint: Fix or remove the shell-ai lines by hand, then try again.",
+            RC_BLOCK_START,
+            RC_BLOCK_END
+        );
+    };
+    let end = start + end + RC_BLOCK_END.len();
+    let after = content[end..].strip_prefix('\n').unwrap_or(&content[end..]);
+    Ok(Some((&content[..start], after)))
+}
+
+/// Whether rc file content sources `integration_path` outside the shell-ai
+/// block, as with a line added by hand before `install` existed.
+fn sources_integration(content: &str, integration_path: &Path) -> bool {
+    let outside = match split_rc_block(content) {
+        Ok(Some((before, after))) => format!("{}{}", before, after),
+        Ok(None) => content.to_string(),
+        // Left for `add_rc_block` to report
+        Err(_) => return false,
+    };
+    let full = integration_path.display().to_string();
+    let home_var = path_with_home_var(integration_path);
+    let from_home = home_var.strip_prefix("$HOME/");
+
+    outside
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| line.contains(&full) || from_home.is_some_and(|relative| line.contains(relative)))
+}
+
+/// Rc file content with the shell-ai `block` added, or replacing an older
+/// block. Returns `None` if the same block is already there.
+fn add_rc_block(content: &str, block: &str) -> Result<Option<String>> {
+    Ok(match split_rc_block(content)? {
+        Some((before, _)) if content[before.len()..].starts_with(block) => None,
+        Some((before, after)) => Some(format!("{}{}{}", before, block, after)),
+        None if content.is_empty() => Some(block.to_string()),
+        None if content.ends_with('\n') => Some(format!("{}\n{}", content, block)),
+        None => Some(format!("{}\n\n{}", content, block)),
+    })
+}
+
+/// Rc file content without the shell-ai block, or `None` if it has none.
+fn remove_rc_block(content: &str) -> Result<Option<String>> {
+    let Some((before, after)) = split_rc_block(content)? else {
+        return Ok(None);
+    };
+
+    // Drop the blank line that `install` put before the block
+    let before = match before.strip_suffix("\n\n") {
+        Some(trimmed) => format!("{}\n", trimmed),
+        None => before.to_string(),
+    };
+    Ok(Some(format!("{}{}", before, after)))
+}
+
+/// Copy the rc file aside before modifying it.
+fn backup_rc_file(rc_path: &Path) -> Result<PathBuf> {
+    let mut backup = rc_path.as_os_str().to_owned();
+    backup.push(".shell-ai.bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(rc_path, &backup)
+        .with_context(|| format!("Failed to back up {}", rc_path.display()))?;
+    Ok(backup)
+}
+
+/// Add (or refresh) the sentinel-guarded source line in the shell's rc file.
+fn install_source_line(shell: ShellType, integration_path: &Path) -> Result<()> {
+    let rc_path = shell
+        .rc_file_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the rc file for {}", shell))?;
+    let block = format!(
+        "{}\n{}\n{}\n",
+        RC_BLOCK_START,
+        source_line(shell, integration_path),
+        RC_BLOCK_END
+    );
+
+    let existing = match fs::read_to_string(&rc_path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", rc_path.display())),
+    };
+
+    let content = existing.as_deref().unwrap_or_default();
+    if sources_integration(content, integration_path) {
+        println!(
+            "{} {} (it sources {} outside the shell-ai block)",
+            "Already installed:".green(),
+            rc_path.display(),
+            integration_path.display()
+        );
+        return Ok(());
     }
-    println!();
+    let Some(new_content) = add_rc_block(content, &block)
+        .with_context(|| format!("Failed to update {}", rc_path.display()))?
+    else {
+        println!("{} {}", "Already installed:".green(), rc_path.display());
+        return Ok(());
+    };
+
+    if existing.is_some() {
+        let backup = backup_rc_file(&rc_path)?;
+        println!("{} {}", "Backed up:".dimmed(), backup.display());
+    } else if let Some(parent) = rc_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    fs::write(&rc_path, new_content)
+        .with_context(|| format!("Failed to write {}", rc_path.display()))?;
+    println!("{} {}", "Installed:".green(), rc_path.display());
+    println!("Restart your shell or source {} to load it.", shell.rc_file().cyan());
+
+    Ok(())
+}
+
+/// Resolve the shell for install/uninstall, falling back to detection.
+fn install_target_shell(shell: Option<ShellType>) -> Result<ShellType> {
+    shell.or_else(ShellType::detect).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not detect your shell. Specify it, e.g. 'shell-ai integration install bash'."
+        )
+    })
+}
+
+/// Run the install action.
+pub fn run_install(args: IntegrationInstallArgs) -> Result<()> {
+    let shell = install_target_shell(args.shell)?;
+    let path = integration_file_path(shell)
+        .ok_or_else(|| anyhow::anyhow!("Could not determine integration file path"))?;
+
+    if !path.exists() {
+        anyhow::bail!(
+            "No integration file found at {}\nRun 'shell-ai integration generate {}' first, or pass --install to it.",
+            path.display(),
+            shell
+        );
+    }
+
+    install_source_line(shell, &path)
+}
+
+/// Run the uninstall action.
+pub fn run_uninstall(args: IntegrationInstallArgs) -> Result<()> {
+    let shell = install_target_shell(args.shell)?;
+    let rc_path = shell
+        .rc_file_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the rc file for {}", shell))?;
+
+    let content = match fs::read_to_string(&rc_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", rc_path.display())),
+    };
+
+    let Some(new_content) = remove_rc_block(&content)
+        .with_context(|| format!("Failed to update {}", rc_path.display()))?
+    else {
+        println!("{} {}", "Not installed:".yellow(), rc_path.display());
+        return Ok(());
+    };
+
+    let backup = backup_rc_file(&rc_path)?;
+    println!("{} {}", "Backed up:".dimmed(), backup.display());
+    fs::write(&rc_path, new_content)
+        .with_context(|| format!("Failed to write {}", rc_path.display()))?;
+    println!("{} {}", "Uninstalled:".green(), rc_path.display());
+
+    Ok(())
 }
 
 /// Run the generate action.
@@ -484,12 +1140,17 @@ pub fn run_generate(args: IntegrationGenerateArgs) -> Result<()> {
         );
     }
 
+    validate_keys(args.shell, &features, args.key, args.explain_key).map_err(|e| anyhow::anyhow!(e))?;
+
     // Generate content
     let content = generate_integration_file(
         args.shell,
         args.preset,
         &args.add_features,
         &args.remove_features,
+        args.key,
+        args.explain_key,
+        !args.no_animation,
     );
 
     // Handle output
@@ -547,7 +1208,11 @@ pub fn run_generate(args: IntegrationGenerateArgs) -> Result<()> {
     fs::write(&path, &content).context("Failed to write integration file")?;
 
     println!("{} {}", "Created:".green(), path.display());
-    print_sourcing_instructions(args.shell, &path);
+    if args.install {
+        install_source_line(args.shell, &path)?;
+    } else {
+        print_sourcing_instructions(args.shell, &path);
+    }
 
     Ok(())
 }
@@ -591,14 +1256,180 @@ pub fn run_update(args: IntegrationUpdateArgs) -> Result<()> {
             )
         })?;
 
+        let features = resolve_features(prefs.preset, &prefs.add, &prefs.remove);
+        validate_keys(prefs.shell, &features, prefs.key, prefs.explain_key).map_err(|e| {
+            anyhow::anyhow!("Invalid @key or @explain-key in {}: {}", path.display(), e)
+        })?;
+
         // Regenerate with same preferences
-        let new_content =
-            generate_integration_file(prefs.shell, prefs.preset, &prefs.add, &prefs.remove);
+        let new_content = generate_integration_file(
+            prefs.shell,
+            prefs.preset,
+            &prefs.add,
+            &prefs.remove,
+            prefs.key,
+            prefs.explain_key,
+            prefs.animation,
+        );
 
         fs::write(&path, &new_content)
             .with_context(|| format!("Failed to write {}", path.display()))?;
 
-        println!("{} {}", "Updated:".green(), path.display());
+        match prefs.version.as_deref() {
+            Some(env!("CARGO_PKG_VERSION")) => {
+                println!("{} {}", "Updated:".green(), path.display())
+            }
+            version => println!(
+                "{} {} {}",
+                "Updated:".green(),
+                path.display(),
+                format!("(from v{})", version.unwrap_or("unknown")).dimmed()
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// The shell-ai version that generated a file, from its `@version` header field.
+/// Files generated before `@version` existed only name it in the "Generated by" comment.
+fn generated_by_version(content: &str) -> Option<String> {
+    let header = || content.lines().take(15);
+    header()
+        .find_map(|line| line.strip_prefix("# @version: "))
+        .or_else(|| header().find_map(|line| line.strip_prefix("# Generated by shell-ai v")))
+        .map(|version| version.trim().to_string())
+}
+
+/// Content without the version lines, so files differing only in version compare equal.
+fn without_version_line(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.starts_with("# Generated by shell-ai v") && !line.starts_with("# @version: "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A hint when `version` (from an integration file) isn't the running version.
+fn version_mismatch_hint(version: Option<&str>) -> Option<String> {
+    let current = env!("CARGO_PKG_VERSION");
+    match version {
+        Some(v) if v == current => None,
+        Some(v) => Some(format!(
+            "generated by shell-ai v{}, but this is v{}; the integration may be outdated",
+            v, current
+        )),
+        None => Some(format!(
+            "generated by an unknown shell-ai version, but this is v{}; the integration may be outdated",
+            current
+        )),
+    }
+}
+
+/// Compare an installed integration file against a fresh one from its stored preferences.
+fn integration_status(shell: ShellType, path: &Path) -> IntegrationStatus {
+    let unrecognized = |error: String, generated_by: Option<String>| IntegrationStatus {
+        shell: shell.to_string(),
+        path: path.display().to_string(),
+        status: FileStatus::Unrecognized,
+        generated_by,
+        error: Some(error),
+    };
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return unrecognized(format!("Failed to read: {}", e), None),
+    };
+    let generated_by = generated_by_version(&content);
+
+    let prefs = match parse_header(&content) {
+        Ok(prefs) => prefs,
+        Err(e) => return unrecognized(e, generated_by),
+    };
+    let features = resolve_features(prefs.preset, &prefs.add, &prefs.remove);
+    if let Err(e) = validate_keys(prefs.shell, &features, prefs.key, prefs.explain_key) {
+        return unrecognized(format!("Invalid @key or @explain-key: {}", e), generated_by);
+    }
+
+    let expected = generate_integration_file(
+        prefs.shell,
+        prefs.preset,
+        &prefs.add,
+        &prefs.remove,
+        prefs.key,
+        prefs.explain_key,
+        prefs.animation,
+    );
+    let status = if without_version_line(&content) == without_version_line(&expected) {
+        FileStatus::UpToDate
+    } else {
+        FileStatus::Stale
+    };
+
+    IntegrationStatus {
+        shell: shell.to_string(),
+        path: path.display().to_string(),
+        status,
+        generated_by,
+        error: None,
+    }
+}
+
+/// Run the status action.
+pub fn run_status(args: IntegrationStatusArgs, output_format: OutputFormat) -> Result<()> {
+    let statuses: Vec<IntegrationStatus> = ShellType::iter()
+        .filter(|s| args.shell.is_none() || args.shell == Some(*s))
+        .filter_map(|s| integration_file_path(s).filter(|p| p.exists()).map(|p| (s, p)))
+        .map(|(s, p)| integration_status(s, &p))
+        .collect();
+
+    if output_format != OutputFormat::Human {
+        output_format.print_serialized(&statuses)?;
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        outln!("No integration files found.");
+        outln!(
+            "Run '{}' first.",
+            "shell-ai integration generate <shell>".cyan()
+        );
+        return Ok(());
+    }
+
+    for status in &statuses {
+        let version = status
+            .generated_by
+            .as_deref()
+            .map(|v| format!(" (generated by v{})", v))
+            .unwrap_or_default();
+        let label = match status.status {
+            FileStatus::UpToDate => "up to date".green(),
+            FileStatus::Stale => "stale".yellow(),
+            FileStatus::Unrecognized => "unrecognized".red(),
+        };
+        outln!("  {:11} {}{} {}", status.shell.white(), label, version.dimmed(), status.path.dimmed());
+        if let Some(ref error) = status.error {
+            outln!("              {}", error.dimmed());
+        }
+        if let Some(hint) = version_mismatch_hint(status.generated_by.as_deref()) {
+            outln!("              {}", hint.yellow());
+        }
+    }
+
+    let current = Some(env!("CARGO_PKG_VERSION"));
+    let outdated = |s: &IntegrationStatus| {
+        s.status == FileStatus::Stale || (s.status == FileStatus::UpToDate && s.generated_by.as_deref() != current)
+    };
+    let outdated: Vec<&str> = statuses.iter().filter(|s| outdated(s)).map(|s| s.path.as_str()).collect();
+    if !outdated.is_empty() {
+        outln!();
+        outln!(
+            "Run '{}' to regenerate {} for shell-ai v{}.",
+            "shell-ai integration update".cyan(),
+            outdated.join(", "),
+            env!("CARGO_PKG_VERSION")
+        );
     }
 
     Ok(())
@@ -608,8 +1439,9 @@ pub fn run_update(args: IntegrationUpdateArgs) -> Result<()> {
 fn feature_description(feature: Feature) -> &'static str {
     match feature {
         Feature::Completions => "Tab completion for shell-ai commands",
-        Feature::Aliases => "?? for suggest, explain for explain (Fish: abbreviations)",
-        Feature::Keybinding => "Ctrl+G transform with animated progress indicator",
+        Feature::Aliases => "?? for suggest, explain for explain (Fish: abbreviations, Nushell: custom commands)",
+        Feature::Keybinding => "Ctrl+G (or --key) transform with animated progress indicator",
+        Feature::ExplainKeybinding => "Alt+E (or --explain-key) explanation of the current line, printed below it",
     }
 }
 
@@ -642,12 +1474,12 @@ fn collect_installed_integrations() -> Vec<InstalledIntegration> {
 /// Run the list action.
 pub fn run_list(output_format: OutputFormat) -> Result<()> {
     match output_format {
-        OutputFormat::Json => run_list_json(),
+        OutputFormat::Json | OutputFormat::Yaml => run_list_structured(output_format),
         OutputFormat::Human => run_list_human(),
     }
 }
 
-fn run_list_json() -> Result<()> {
+fn run_list_structured(output_format: OutputFormat) -> Result<()> {
     let features: Vec<FeatureInfo> = Feature::iter()
         .map(|f| FeatureInfo {
             name: f.to_string(),
@@ -677,55 +1509,55 @@ fn run_list_json() -> Result<()> {
         installed,
     };
 
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    output_format.print_serialized(&output)?;
     Ok(())
 }
 
 fn run_list_human() -> Result<()> {
     const HEADING: &str = "Shell-AI Shell Integration";
-    println!("{}", HEADING.bold());
-    println!("{}", "=".repeat(HEADING.len()));
-    println!();
+    outln!("{}", HEADING.bold());
+    outln!("{}", "=".repeat(HEADING.len()));
+    outln!();
 
     // List features
-    println!("{}:", "Available Features".cyan());
+    outln!("{}:", "Available Features".cyan());
     for feature in Feature::iter() {
-        println!(
+        outln!(
             "  {:15} {}",
             feature.to_string().white(),
             feature_description(feature).dimmed()
         );
     }
-    println!();
+    outln!();
 
     // List presets
-    println!("{}:", "Presets".cyan());
+    outln!("{}:", "Presets".cyan());
     for preset in Preset::iter() {
         let mut features: Vec<_> = preset.features().iter().map(|f| f.to_string()).collect();
         features.sort();
-        println!(
+        outln!(
             "  {:15} [{}]",
             preset.to_string().white(),
             features.join(", ").dimmed()
         );
     }
-    println!();
+    outln!();
 
     // List supported shells
-    println!("{}:", "Supported Shells".cyan());
+    outln!("{}:", "Supported Shells".cyan());
     for shell in ShellType::iter() {
-        println!("  {}", shell.to_string().white());
+        outln!("  {}", shell.to_string().white());
     }
-    println!();
+    outln!();
 
     // List existing integration files
-    println!("{}:", "Installed Integrations".cyan());
+    outln!("{}:", "Installed Integrations".cyan());
     let installed = collect_installed_integrations();
     if installed.is_empty() {
-        println!("  {}", "(none)".dimmed());
+        outln!("  {}", "(none)".dimmed());
     } else {
         for inst in installed {
-            println!("  {} ({})", inst.shell.green(), inst.features.join(", "));
+            outln!("  {} ({})", inst.shell.green(), inst.features.join(", "));
         }
     }
 
@@ -738,6 +1570,9 @@ pub fn run(args: IntegrationArgs, output_format: OutputFormat) -> Result<()> {
         IntegrationAction::Generate(gen_args) => run_generate(gen_args),
         IntegrationAction::Update(update_args) => run_update(update_args),
         IntegrationAction::List => run_list(output_format),
+        IntegrationAction::Status(status_args) => run_status(status_args, output_format),
+        IntegrationAction::Install(install_args) => run_install(install_args),
+        IntegrationAction::Uninstall(install_args) => run_uninstall(install_args),
     }
 }
 
@@ -747,13 +1582,13 @@ pub fn run(args: IntegrationArgs, output_format: OutputFormat) -> Result<()> {
 
 const BASH_ALIASES: &str = r##"
 # === Aliases ===
-alias '??'='shell-ai suggest --'
+alias '??'='shell-ai suggest --shell=bash --'
 alias 'explain'='shell-ai explain --'
 "##;
 
 const BASH_KEYBINDING: &str = r##"
 # === Keybinding ===
-# Ctrl+G: Transform current line into a shell command
+# {{KEY_LABEL}}: Transform current line into a shell command
 _shai_transform() {
     if [[ -n "$READLINE_LINE" ]]; then
         local original="$READLINE_LINE"
@@ -767,7 +1602,7 @@ _shai_transform() {
         set +m
         trap 'kill $pid 2>/dev/null; (( had_monitor )) && set -m; rm -f "$tmpfile"; printf "\r\033[K"; trap - INT TERM; return' INT TERM
 
-        { shell-ai --frontend=noninteractive suggest -- "$original" 2>/dev/null > "$tmpfile" & } 2>/dev/null
+        { shell-ai --frontend=noninteractive --shell=bash suggest -- "$original" 2>/dev/null > "$tmpfile" & } 2>/dev/null
         pid=$!
 
         local pos=0
@@ -799,18 +1634,48 @@ _shai_transform() {
         printf '\r\033[K'
     fi
 }
-bind -x '"\C-g": _shai_transform'
+bind -x '"{{KEY}}": _shai_transform'
+"##;
+
+const BASH_STATIC_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+_shai_transform() {
+    if [[ -n "$READLINE_LINE" ]]; then
+        local result
+        printf '\r\033[K\033[1;36m⠋\033[0m \033[2;36mthinking...\033[0m'
+        result=$(shell-ai --frontend=noninteractive --shell=bash suggest -- "$READLINE_LINE" 2>/dev/null)
+        printf '\r\033[K'
+        if [[ -n "$result" ]]; then
+            READLINE_LINE=$result
+            READLINE_POINT=${#READLINE_LINE}
+        fi
+    fi
+}
+bind -x '"{{KEY}}": _shai_transform'
+"##;
+
+const BASH_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+_shai_explain() {
+    if [[ -n "$READLINE_LINE" ]]; then
+        printf '\n'
+        shell-ai --shell=bash explain -- "$READLINE_LINE"
+    fi
+}
+bind -x '"{{KEY}}": _shai_explain'
 "##;
 
 const ZSH_ALIASES: &str = r##"
 # === Aliases ===
-alias '??'='shell-ai suggest --'
+alias '??'='shell-ai suggest --shell=zsh --'
 alias 'explain'='shell-ai explain --'
 "##;
 
 const ZSH_KEYBINDING: &str = r##"
 # === Keybinding ===
-# Ctrl+G: Transform current line into a shell command
+# {{KEY_LABEL}}: Transform current line into a shell command
 _shai_transform() {
     if [[ -n "$BUFFER" ]]; then
         local original="$BUFFER"
@@ -822,7 +1687,7 @@ _shai_transform() {
         setopt LOCAL_OPTIONS NO_NOTIFY NO_MONITOR LOCAL_TRAPS
         trap 'kill $pid 2>/dev/null; rm -f "$tmpfile"; printf "\r\033[K"; zle reset-prompt; return' INT TERM
 
-        (shell-ai --frontend=noninteractive suggest -- "$original" 2>/dev/null > "$tmpfile") &!
+        (shell-ai --frontend=noninteractive --shell=zsh suggest -- "$original" 2>/dev/null > "$tmpfile") &!
         pid=$!
 
         local pos=0
@@ -854,19 +1719,50 @@ _shai_transform() {
     fi
 }
 zle -N _shai_transform
-bindkey '^G' _shai_transform
+bindkey '{{KEY}}' _shai_transform
+"##;
+
+const ZSH_STATIC_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+_shai_transform() {
+    if [[ -n "$BUFFER" ]]; then
+        local result
+        printf '\r\033[K\033[1;36m⠋\033[0m \033[2;36mthinking...\033[0m'
+        result=$(shell-ai --frontend=noninteractive --shell=zsh suggest -- "$BUFFER" 2>/dev/null)
+        printf '\r\033[K'
+        [[ -n "$result" ]] && BUFFER=$result
+        zle reset-prompt
+        zle end-of-line
+    fi
+}
+zle -N _shai_transform
+bindkey '{{KEY}}' _shai_transform
+"##;
+
+const ZSH_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+_shai_explain() {
+    if [[ -n "$BUFFER" ]]; then
+        zle -I
+        shell-ai --shell=zsh explain -- "$BUFFER"
+    fi
+}
+zle -N _shai_explain
+bindkey '{{KEY}}' _shai_explain
 "##;
 
 const FISH_ALIASES: &str = r##"
 # === Abbreviations ===
 # Fish uses abbreviations instead of aliases for better integration
-abbr -a '??' 'shell-ai suggest --'
+abbr -a '??' 'shell-ai suggest --shell=fish --'
 abbr -a 'explain' 'shell-ai explain --'
 "##;
 
 const FISH_KEYBINDING: &str = r##"
 # === Keybinding ===
-# Ctrl+G: Transform current line into a shell command
+# {{KEY_LABEL}}: Transform current line into a shell command
 function _shai_transform
     set -l cmd (commandline)
     test -z "$cmd"; and return
@@ -883,7 +1779,7 @@ function _shai_transform
         kill $__shai_pid 2>/dev/null
     end
 
-    sh -c 'shell-ai --frontend=noninteractive suggest -- "$1" 2>/dev/null > "$2"' _ "$cmd" "$__shai_tmp" &
+    sh -c 'shell-ai --frontend=noninteractive --shell=fish suggest -- "$1" 2>/dev/null > "$2"' _ "$cmd" "$__shai_tmp" &
     set __shai_pid $last_pid
 
     set -l pos 0
@@ -918,19 +1814,50 @@ function _shai_transform
     commandline -f repaint
     commandline -f end-of-line
 end
-bind \cg _shai_transform
+bind {{KEY}} _shai_transform
+"##;
+
+const FISH_STATIC_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+function _shai_transform
+    set -l cmd (commandline)
+    test -z "$cmd"; and return
+
+    printf '\r\033[K\033[1;36m⠋\033[0m \033[2;36mthinking...\033[0m'
+    set -l result (shell-ai --frontend=noninteractive --shell=fish suggest -- "$cmd" 2>/dev/null | string collect)
+    printf '\r\033[K'
+    test -n "$result"; and commandline -r -- $result
+    commandline -f repaint
+    commandline -f end-of-line
+end
+bind {{KEY}} _shai_transform
+"##;
+
+const FISH_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+function _shai_explain
+    set -l cmd (commandline)
+    test -z "$cmd"; and return
+
+    echo
+    shell-ai --shell=fish explain -- "$cmd"
+    commandline -f repaint
+end
+bind {{KEY}} _shai_explain
 "##;
 
 const POWERSHELL_ALIASES: &str = r##"
 # === Functions (PowerShell equivalent of aliases) ===
-function ?? { shell-ai suggest -- @args }
+function ?? { shell-ai suggest --shell=powershell -- @args }
 function explain { shell-ai explain -- @args }
 "##;
 
 const POWERSHELL_KEYBINDING: &str = r##"
 # === Keybinding ===
-# Ctrl+G: Transform current line into a shell command
-Set-PSReadLineKeyHandler -Chord 'Ctrl+g' -ScriptBlock {
+# {{KEY_LABEL}}: Transform current line into a shell command
+Set-PSReadLineKeyHandler -Chord '{{KEY}}' -ScriptBlock {
     $line = $null
     [Microsoft.PowerShell.PSConsoleReadLine]::GetBufferState([ref]$line, [ref]$null)
     if ($line) {
@@ -940,7 +1867,7 @@ Set-PSReadLineKeyHandler -Chord 'Ctrl+g' -ScriptBlock {
 
         $job = Start-Job -ScriptBlock {
             param($l)
-            shell-ai --frontend=noninteractive suggest -- $l 2>$null
+            shell-ai --frontend=noninteractive --shell=powershell suggest -- $l 2>$null
         } -ArgumentList $line
 
         $pos = 0
@@ -986,3 +1913,285 @@ Set-PSReadLineKeyHandler -Chord 'Ctrl+g' -ScriptBlock {
     }
 }
 "##;
+
+const POWERSHELL_STATIC_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+Set-PSReadLineKeyHandler -Chord '{{KEY}}' -ScriptBlock {
+    $line = $null
+    [Microsoft.PowerShell.PSConsoleReadLine]::GetBufferState([ref]$line, [ref]$null)
+    if ($line) {
+        [Console]::Write("`r`e[K`e[1;36m⠋`e[0m `e[2;36mthinking...`e[0m")
+        $result = (shell-ai --frontend=noninteractive --shell=powershell suggest -- $line 2>$null) -join "`n"
+        [Console]::Write("`r`e[K")
+        if ($result) {
+            [Microsoft.PowerShell.PSConsoleReadLine]::Replace(0, $line.Length, $result)
+        }
+        [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+    }
+}
+"##;
+
+const POWERSHELL_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+Set-PSReadLineKeyHandler -Chord '{{KEY}}' -ScriptBlock {
+    $line = $null
+    [Microsoft.PowerShell.PSConsoleReadLine]::GetBufferState([ref]$line, [ref]$null)
+    if ($line) {
+        [Console]::WriteLine()
+        shell-ai --shell=powershell explain -- $line | Out-Host
+        [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+    }
+}
+"##;
+
+const NU_ALIASES: &str = r##"
+# === Custom Commands (Nushell equivalent of aliases) ===
+def --wrapped "??" [...prompt: string] { ^shell-ai suggest --shell=nu -- ...$prompt }
+def --wrapped explain [...command: string] { ^shell-ai explain -- ...$command }
+"##;
+
+const NU_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+# Nushell can't redraw from a background job, so the indicator is static
+def --env _shai_transform [] {
+    let original = (commandline)
+    if ($original | is-empty) { return }
+
+    print -n $"\r\e[K\e[1;36m⠋\e[0m \e[0;36m($original)\e[0m"
+    let result = (do { ^shell-ai --frontend=noninteractive --shell=nu suggest -- $original } | complete)
+    print -n "\r\e[K"
+
+    if $result.exit_code == 0 and ($result.stdout | str trim | is-not-empty) {
+        commandline edit --replace ($result.stdout | str trim --right)
+    }
+}
+
+$env.config.keybindings = ($env.config.keybindings | append {
+    name: shai_transform
+    modifier: {{KEY_MODIFIER}}
+    keycode: {{KEY_CODE}}
+    mode: [emacs vi_insert vi_normal]
+    event: { send: executehostcommand cmd: "_shai_transform" }
+})
+"##;
+
+const NU_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+def _shai_explain [] {
+    let line = (commandline)
+    if ($line | is-empty) { return }
+
+    print ""
+    ^shell-ai --shell=nu explain -- $line
+}
+
+$env.config.keybindings = ($env.config.keybindings | append {
+    name: shai_explain
+    modifier: {{KEY_MODIFIER}}
+    keycode: {{KEY_CODE}}
+    mode: [emacs vi_insert vi_normal]
+    event: { send: executehostcommand cmd: "_shai_explain" }
+})
+"##;
+
+const ELVISH_ALIASES: &str = r##"
+# === Abbreviations ===
+# Command abbreviations expand as you type, like fish abbreviations
+set edit:command-abbr['??'] = 'shell-ai suggest --shell=elvish --'
+set edit:command-abbr['explain'] = 'shell-ai explain --'
+"##;
+
+const ELVISH_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+# Elvish can't redraw while a binding runs, so there is no progress indicator
+use os
+use str
+set edit:insert:binding[{{KEY}}] = {
+    var line = $edit:current-command
+    if (eq $line '') { return }
+
+    var result = ''
+    try {
+        set result = (shell-ai --frontend=noninteractive --shell=elvish suggest -- $line 2>$os:dev-null | slurp)
+    } catch { }
+    if (not-eq $result '') {
+        set edit:current-command = (str:trim-right $result "\n")
+    }
+}
+"##;
+
+const ELVISH_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+use os
+set edit:insert:binding[{{KEY}}] = {
+    var line = $edit:current-command
+    if (eq $line '') { return }
+
+    try {
+        edit:notify (shell-ai --shell=elvish explain -- $line 2>$os:dev-null | slurp)
+    } catch {
+        edit:notify 'shell-ai explain failed'
+    }
+}
+"##;
+
+const XONSH_ALIASES: &str = r##"
+# === Aliases ===
+aliases['??'] = 'shell-ai suggest --shell=xonsh --'
+aliases['explain'] = 'shell-ai explain --'
+"##;
+
+const XONSH_KEYBINDING: &str = r##"
+# === Keybinding ===
+# {{KEY_LABEL}}: Transform current line into a shell command
+# prompt_toolkit can't redraw while a key handler runs, so there is no progress indicator
+@events.on_ptk_create
+def _shai_transform_binding(prompter, history, completer, bindings, **kw):
+    @bindings.add({{KEY}})
+    def _shai_transform(event):
+        import subprocess
+
+        buffer = event.current_buffer
+        if not buffer.text.strip():
+            return
+        try:
+            result = subprocess.run(
+                ["shell-ai", "--frontend=noninteractive", "--shell=xonsh", "suggest", "--", buffer.text],
+                capture_output=True,
+                text=True,
+            ).stdout.rstrip("\n")
+        except OSError:
+            return
+        if result:
+            buffer.text = result
+            buffer.cursor_position = len(result)
+"##;
+
+const XONSH_EXPLAIN_KEYBINDING: &str = r##"
+# === Explain keybinding ===
+# {{KEY_LABEL}}: Explain the current line without replacing it
+@events.on_ptk_create
+def _shai_explain_binding(prompter, history, completer, bindings, **kw):
+    @bindings.add({{KEY}})
+    def _shai_explain(event):
+        import subprocess
+        from prompt_toolkit.application import run_in_terminal
+
+        line = event.current_buffer.text
+        if not line.strip():
+            return
+        run_in_terminal(lambda: subprocess.run(["shell-ai", "--shell=xonsh", "explain", "--", line]))
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_of(content: &str) -> FileStatus {
+        let dir = std::env::temp_dir().join(format!("shell-ai-integration-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bash.bash");
+        fs::write(&path, content).unwrap();
+        let status = integration_status(ShellType::Bash, &path).status;
+        fs::remove_dir_all(dir).unwrap();
+        status
+    }
+
+    #[test]
+    fn reports_whether_integration_files_are_current() {
+        let fresh = generate_integration_file(
+            ShellType::Bash,
+            Preset::Standard,
+            &[],
+            &[],
+            KeyChord::default(),
+            KeyChord::EXPLAIN_DEFAULT,
+            true,
+        );
+
+        assert_eq!(status_of(&fresh), FileStatus::UpToDate);
+        assert_eq!(status_of(&format!("{}\n# edited\n", fresh)), FileStatus::Stale);
+        assert_eq!(status_of("echo hand-written\n"), FileStatus::Unrecognized);
+    }
+
+    #[test]
+    fn statuses_serialize_in_kebab_case() {
+        let statuses = [FileStatus::UpToDate, FileStatus::Stale, FileStatus::Unrecognized];
+        assert_eq!(
+            serde_json::to_value(statuses).unwrap(),
+            serde_json::json!(["up-to-date", "stale", "unrecognized"])
+        );
+    }
+
+    #[test]
+    fn quotes_python_strings() {
+        assert_eq!(python_str("--help"), "'--help'");
+        assert_eq!(python_str(r"C:\Users\o'neil"), r"'C:\\Users\\o\'neil'");
+        assert_eq!(python_str("a\nb\u{7}"), r"'a\nb\u0007'");
+        assert_eq!(python_list(&["-n".to_string(), "--count".to_string()]), "['-n', '--count']");
+        assert_eq!(python_list(&[]), "[]");
+    }
+
+    fn bash_block(path: &Path) -> String {
+        format!("{}\n{}\n{}\n", RC_BLOCK_START, source_line(ShellType::Bash, path), RC_BLOCK_END)
+    }
+
+    #[test]
+    fn installing_twice_adds_one_block_and_uninstall_restores_the_file() {
+        let block = bash_block(Path::new("/opt/shell-ai/integration.bash"));
+        let original = "export EDITOR=vim\n";
+
+        let installed = add_rc_block(original, &block).unwrap().unwrap();
+        assert_eq!(installed, format!("export EDITOR=vim\n\n{}", block));
+        assert_eq!(add_rc_block(&installed, &block).unwrap(), None);
+
+        assert_eq!(remove_rc_block(&installed).unwrap().as_deref(), Some(original));
+        assert_eq!(remove_rc_block(original).unwrap(), None);
+        assert_eq!(add_rc_block("", &block).unwrap(), Some(block.clone()));
+    }
+
+    #[test]
+    fn install_refreshes_an_outdated_block_in_place() {
+        let old = bash_block(Path::new("/opt/old/integration.bash"));
+        let new = bash_block(Path::new("/opt/shell-ai/integration.bash"));
+        let content = format!("alias ll='ls -l'\n\n{}export PATH=\"$PATH:/opt/bin\"\n", old);
+
+        assert_eq!(
+            add_rc_block(&content, &new).unwrap().unwrap(),
+            format!("alias ll='ls -l'\n\n{}export PATH=\"$PATH:/opt/bin\"\n", new)
+        );
+    }
+
+    #[test]
+    fn unbalanced_markers_are_reported() {
+        let block = bash_block(Path::new("/opt/shell-ai/integration.bash"));
+        let missing_end = format!("{}\nsource /opt/shell-ai/integration.bash\nexport A=1\n", RC_BLOCK_START);
+        let missing_start = format!("source /opt/shell-ai/integration.bash\n{}\n", RC_BLOCK_END);
+
+        for content in [missing_end, missing_start] {
+            assert!(add_rc_block(&content, &block).is_err(), "{}", content);
+            assert!(remove_rc_block(&content).is_err(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn finds_integration_files_sourced_by_hand() {
+        let path = Path::new("/opt/shell-ai/integration.bash");
+
+        assert!(sources_integration("export A=1\n. /opt/shell-ai/integration.bash\n", path));
+        assert!(!sources_integration("# source /opt/shell-ai/integration.bash\n", path));
+        assert!(!sources_integration(&bash_block(path), path));
+        assert!(!sources_integration("source /opt/other/integration.bash\n", path));
+
+        if let Some(home) = dirs::home_dir() {
+            let path = home.join(".config/shell-ai/integration.bash");
+            assert!(sources_integration("source ~/.config/shell-ai/integration.bash\n", &path));
+        }
+    }
+}
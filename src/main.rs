@@ -1,23 +1,39 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+mod bedrock;
+mod builtins;
+mod clipboard;
 mod config;
+mod datetime;
+mod doctor;
 mod explain;
+mod history;
 mod http;
 mod integration;
 mod logger;
+mod man_cache;
+mod mock;
+mod models;
+mod ollama;
+mod output;
 mod progress;
 mod provider;
+mod risk;
+mod setup;
+mod shell;
+mod shell_history;
 mod suggest;
 mod ui;
+mod usage;
 
-use crate::config::{AppConfig, CliOverrides, DebugLevel, OutputFormat};
+use crate::config::{AppConfig, CliOverrides, DebugLevel, EditMode, OutputFormat};
 
 /// Global options available on all commands.
 #[derive(Parser, Debug, Clone, Default)]
 pub struct GlobalOptions {
-    /// Provider override (openai, azure, groq, mistral, ollama)
+    /// Provider override (openai, azure, groq, mistral, ollama, bedrock, perplexity, deepseek, local, mock)
     #[arg(long = "provider", global = true)]
     pub provider: Option<String>,
 
@@ -33,11 +49,19 @@ pub struct GlobalOptions {
     #[arg(long = "temperature", global = true)]
     pub temperature: Option<f32>,
 
+    /// Number of suggestions to generate (overrides suggestion_count, max 10)
+    #[arg(long = "count", short = 'n', value_name = "N", global = true)]
+    pub count: Option<u32>,
+
+    /// API request timeout in seconds for this invocation (0 = no timeout)
+    #[arg(long = "timeout", value_name = "SECONDS", global = true)]
+    pub timeout: Option<u64>,
+
     /// Frontend mode: automatic (default), dialog, readline, or noninteractive
     #[arg(long = "frontend", global = true)]
     pub frontend: Option<String>,
 
-    /// Output format: human, json
+    /// Output format: human, json, yaml
     #[arg(long = "output-format", global = true)]
     pub output_format: Option<String>,
 
@@ -46,9 +70,61 @@ pub struct GlobalOptions {
     #[arg(long = "debug", short = 'd', global = true, value_enum, value_name = "LEVEL", num_args = 0..=1, default_missing_value = "debug", require_equals = true)]
     pub debug: Option<DebugLevel>,
 
+    /// Increase log verbosity: -v for info, -vv for debug, -vvv for trace (the more verbose of this and --debug wins)
+    #[arg(long = "verbose", short = 'v', global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only print errors on stderr: no warnings, info messages, or progress spinner (--debug takes precedence)
+    #[arg(long = "quiet", short = 'q', global = true)]
+    pub quiet: bool,
+
     /// Language/locale for AI responses (auto-detected by default, empty string to disable)
     #[arg(long = "locale", global = true)]
     pub locale: Option<String>,
+
+    /// Shell whose syntax suggestions should use: bash, zsh, fish, powershell, nu (auto-detected by default)
+    #[arg(long = "shell", id = "target_shell", global = true)]
+    pub shell: Option<String>,
+
+    /// Shell that executed commands run in: bash, zsh, fish, powershell, nu (defaults to --shell)
+    #[arg(long = "exec-shell", global = true)]
+    pub exec_shell: Option<String>,
+
+    /// Never run commands from shell-ai: no Execute action, plan execution, or context mode (or set SHAI_ALLOW_EXECUTE=false)
+    #[arg(long = "no-exec", global = true)]
+    pub no_exec: bool,
+
+    /// Send an extra HTTP header with provider requests, as NAME:VALUE (repeatable; adds to [<provider>.extra_headers])
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header, global = true)]
+    pub header: Vec<(String, String)>,
+
+    /// Pull the Ollama model first if it isn't installed (or set SHAI_OLLAMA_AUTOPULL=true)
+    #[arg(long = "pull", global = true)]
+    pub pull: bool,
+
+    /// Print provider-reported token usage to stderr after each request (or set SHAI_SHOW_USAGE=true)
+    #[arg(long = "show-usage", global = true)]
+    pub show_usage: bool,
+
+    /// Print each API request's wall-clock time and retry count to stderr (or set SHAI_SHOW_TIMING=true)
+    #[arg(long = "show-timing", global = true)]
+    pub show_timing: bool,
+
+    /// Save each API request and raw response (secrets redacted) to DIR, for bug reports (or set SHAI_RECORD_DIR)
+    #[arg(long = "record", value_name = "DIR", global = true, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer API requests with the responses recorded in DIR instead of contacting the provider (or set SHAI_REPLAY_DIR)
+    #[arg(long = "replay", value_name = "DIR", global = true)]
+    pub replay: Option<PathBuf>,
+
+    /// Write results to PATH instead of stdout, in the selected output format (prompts, progress, and logs stay on the terminal)
+    #[arg(long = "output-file", value_name = "PATH", global = true)]
+    pub output_file: Option<PathBuf>,
+
+    /// With --output-file, refuse to overwrite an existing file
+    #[arg(long = "no-clobber", global = true, requires = "output_file")]
+    pub no_clobber: bool,
 }
 
 /// Shell-AI CLI (full interface with subcommands)
@@ -85,8 +161,32 @@ struct ShaiCli {
     #[arg(long = "ctx")]
     ctx: bool,
 
+    /// In noninteractive mode, print the suggested command and a one-line synopsis to stderr before emitting the command on stdout.
+    #[arg(long = "explain-first")]
+    explain_first: bool,
+
+    /// Generate a multi-step plan of commands instead of a single command.
+    #[arg(long = "plan")]
+    plan: bool,
+
+    /// Send your last N shell history entries to the AI as context. Note: history can contain secrets and is sent to your AI provider.
+    #[arg(long = "history-context", value_name = "N", conflicts_with = "repeat")]
+    history_context: Option<usize>,
+
+    /// In noninteractive mode, also copy the emitted command to the clipboard (or set SHAI_AUTO_COPY=true).
+    #[arg(long = "copy")]
+    copy: bool,
+
+    /// Reuse the most recent prompt and generate fresh suggestions.
+    #[arg(long = "last", conflicts_with_all = ["prompt", "repeat"])]
+    last: bool,
+
+    /// Print the most recently accepted command again without contacting the model.
+    #[arg(long = "repeat", conflicts_with_all = ["prompt", "plan"])]
+    repeat: bool,
+
     /// Prompt describing what you want to do.
-    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    #[arg(required_unless_present_any = ["last", "repeat"], trailing_var_arg = true, allow_hyphen_values = true)]
     prompt: Vec<String>,
 }
 
@@ -104,6 +204,18 @@ enum Command {
 
     /// Generate shell integration scripts (completions, aliases, keybindings).
     Integration(integration::IntegrationArgs),
+
+    /// Show, search, or clear the history of executed and copied suggestions.
+    History(history::HistoryArgs),
+
+    /// Show estimated spend for models with configured prices, or clear the usage record.
+    Usage(usage::UsageArgs),
+
+    /// Check configuration, credentials, connectivity, and helper tools.
+    Doctor,
+
+    /// Interactively choose a provider, enter its API key, and pick a model, then write config.toml.
+    Setup,
 }
 
 #[derive(Parser, Debug)]
@@ -119,6 +231,18 @@ enum ConfigAction {
 
     /// Show configuration schema (descriptions of all settings).
     Schema,
+
+    /// Open config.toml in $EDITOR (creating it if missing) and check it for errors.
+    Edit,
+
+    /// List all providers with their model and whether they are ready to use.
+    Providers,
+
+    /// List the models available from the active provider.
+    Models,
+
+    /// Show the detected and effective locale, with common values to set.
+    Locales,
 }
 
 #[derive(Parser, Debug)]
@@ -134,13 +258,69 @@ struct SuggestArgs {
     #[arg(long = "ctx")]
     ctx: bool,
 
+    /// In noninteractive mode, print the suggested command and a one-line synopsis to stderr before emitting the command on stdout.
+    #[arg(long = "explain-first")]
+    explain_first: bool,
+
+    /// Generate a multi-step plan of commands instead of a single command.
+    #[arg(long = "plan")]
+    plan: bool,
+
+    /// Send your last N shell history entries to the AI as context. Note: history can contain secrets and is sent to your AI provider.
+    #[arg(long = "history-context", value_name = "N", conflicts_with = "repeat")]
+    history_context: Option<usize>,
+
+    /// In noninteractive mode, also copy the emitted command to the clipboard (or set SHAI_AUTO_COPY=true).
+    #[arg(long = "copy")]
+    copy: bool,
+
+    /// Reuse the most recent prompt and generate fresh suggestions.
+    #[arg(long = "last", conflicts_with_all = ["prompt", "repeat"])]
+    last: bool,
+
+    /// Print the most recently accepted command again without contacting the model.
+    #[arg(long = "repeat", conflicts_with_all = ["prompt", "plan"])]
+    repeat: bool,
+
     /// Prompt describing what you want to do.
-    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    #[arg(required_unless_present_any = ["last", "repeat"], trailing_var_arg = true, allow_hyphen_values = true)]
     prompt: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
 struct ExplainArgs {
+    /// Bypass the on-disk man page cache.
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Browse the explanation as a collapsible tree (expand segments, show citations on demand).
+    #[arg(long = "interactive", short = 'i')]
+    interactive: bool,
+
+    /// Show the man page citation beneath each segment, with a confidence dot (green high, yellow medium, red low).
+    #[arg(long = "citations")]
+    citations: bool,
+
+    /// Drop citations whose confidence is below this threshold (0.0-1.0).
+    #[arg(long = "min-confidence", value_name = "CONFIDENCE")]
+    min_confidence: Option<f32>,
+
+    /// Explain each line of piped stdin as a separate command, skipping blank and comment lines (a JSON/YAML array in structured output).
+    #[arg(long = "batch", conflicts_with_all = ["command", "interactive"])]
+    batch: bool,
+
+    /// Read the command to explain from the system clipboard.
+    #[arg(long = "clipboard", conflicts_with_all = ["command", "batch"])]
+    clipboard: bool,
+
+    /// Run the command and show the AI a sample of its output. Only simple read-only commands (e.g., `ls`, `df`, `git status`) run unless --i-know-this-runs-it is given.
+    #[arg(long = "with-output", conflicts_with = "batch")]
+    with_output: bool,
+
+    /// Let --with-output run any command, side effects and all. Never use this on a command you don't trust.
+    #[arg(long = "i-know-this-runs-it", requires = "with_output")]
+    i_know_this_runs_it: bool,
+
     /// Command to explain. If omitted and stdin is piped, read from stdin.
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     command: Vec<String>,
@@ -165,15 +345,89 @@ fn global_to_cli_overrides(global: &GlobalOptions) -> CliOverrides {
         model: global.model.clone(),
         max_tokens: global.max_tokens,
         temperature: global.temperature,
+        request_timeout: global.timeout,
+        suggestion_count: global.count,
+        citation_min_confidence: None,
+        show_usage: global.show_usage.then_some(true),
+        show_timing: global.show_timing.then_some(true),
         frontend: global.frontend.clone(),
         output_format: global.output_format.clone(),
-        debug: global.debug,
+        debug: global.debug.max(DebugLevel::from_verbosity(global.verbose)),
         locale: global.locale.clone(),
+        shell: global.shell.clone(),
+        exec_shell: global.exec_shell.clone(),
+        allow_execute: global.no_exec.then_some(false),
+        headers: global.header.clone(),
+    }
+}
+
+/// Build the config overrides for an invocation, including its subcommand's options.
+fn cli_overrides(cli: &Cli) -> CliOverrides {
+    let mut overrides = global_to_cli_overrides(&cli.global);
+    if let Command::Explain(args) = &cli.command {
+        overrides.citation_min_confidence = args.min_confidence;
+    }
+    overrides
+}
+
+/// Parse a `--header` value of the form `NAME:VALUE`.
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s.split_once(':').ok_or_else(|| format!("expected NAME:VALUE, got '{}'", s))?;
+    let name = name.trim();
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(format!("invalid header name '{}'", name));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Exit status when every suggestion attempt came back without a command.
+const EXIT_NO_SUGGESTION: i32 = 3;
+/// Exit status for invalid configuration or an error response from the provider.
+const EXIT_PROVIDER_ERROR: i32 = 4;
+/// Exit status when the provider could not be reached at all.
+const EXIT_NETWORK_ERROR: i32 = 5;
+
+/// The configuration failed validation, before any request was made.
+#[derive(Debug)]
+struct ConfigError(anyhow::Error);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Map an error to the documented exit status, so scripts can tell apart
+/// "no command", "fix your setup", and "try again later".
+fn exit_code(err: &anyhow::Error) -> i32 {
+    let cause = match err.downcast_ref::<suggest::NoSuggestionsError>() {
+        Some(suggest::NoSuggestionsError { reason: Some(reason) }) => reason,
+        Some(suggest::NoSuggestionsError { reason: None }) => return EXIT_NO_SUGGESTION,
+        None => err,
+    };
+
+    if cause.chain().any(|e| e.is::<http::NetworkError>()) {
+        EXIT_NETWORK_ERROR
+    } else if cause.chain().any(|e| e.is::<http::HttpStatusError>() || e.is::<ConfigError>()) {
+        EXIT_PROVIDER_ERROR
+    } else if err.is::<suggest::NoSuggestionsError>() {
+        EXIT_NO_SUGGESTION
+    } else {
+        1
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit_code(&e));
+    }
+}
+
+async fn run() -> Result<()> {
     logger::init();
 
     // Parse CLI, converting `shai` shorthand to full Cli with Command::Suggest
@@ -183,6 +437,12 @@ async fn main() -> Result<()> {
             global: args.global,
             command: Command::Suggest(SuggestArgs {
                 ctx: args.ctx,
+                explain_first: args.explain_first,
+                plan: args.plan,
+                history_context: args.history_context,
+                copy: args.copy,
+                last: args.last,
+                repeat: args.repeat,
                 prompt: args.prompt,
             }),
         }
@@ -190,24 +450,94 @@ async fn main() -> Result<()> {
         Cli::parse()
     };
 
-    let cli_overrides = global_to_cli_overrides(&cli.global);
-    let config = AppConfig::load_with_cli(cli_overrides);
+    // Editing must work even when the current config file fails to load
+    if let Command::Config(ConfigArgs { action: Some(ConfigAction::Edit) }) = cli.command {
+        return AppConfig::edit_config();
+    }
+
+    // The setup wizard writes the config rather than reading it
+    if let Command::Setup = cli.command {
+        setup::run()?;
+        return Ok(());
+    }
+
+    let mut config = AppConfig::load_with_cli(cli_overrides(&cli));
     logger::set_debug(config.debug.value);
+    if cli.global.quiet {
+        if config.debug.value.is_some() {
+            log::warn!("Ignoring --quiet because a log level was set with --debug, -v, or {}", config::env::SHAI_DEBUG);
+        } else {
+            logger::set_quiet();
+        }
+    }
+    if let Err(e) = logger::set_log_file(config.log_file.value.as_deref()) {
+        log::warn!("Could not open log file: {}", e);
+    }
+    if let Some(locale) = config.locale.value.as_deref() {
+        if !locale.is_empty() && !config::is_well_formed_locale(locale) {
+            log::warn!(
+                "Locale {:?} doesn't look like a language tag (e.g., fr_FR or pt-BR); it's sent to the model as is. See `shell-ai config locales`.",
+                locale
+            );
+        }
+    }
+    progress::set_enabled(config.progress.value && !logger::is_quiet());
+    progress::set_style(&config.spinner_frames.value, &config.spinner_template.value);
+    ui::set_vim_mode(config.edit_mode.value == EditMode::Vi);
+    ui::install_interrupt_handler();
+    suggest::set_exec_shell(config.execution_shell());
+    suggest::set_allow_execute(config.allow_execute.value);
+    http::set_show_timing(config.show_timing.value);
+    http::init_recording(cli.global.record.clone(), cli.global.replay.clone())?;
+    if let Some(ref path) = cli.global.output_file {
+        output::init(path, cli.global.no_clobber)?;
+    }
+
+    // First run on a terminal: offer the setup wizard instead of failing on a missing provider
+    let needs_provider = match &cli.command {
+        Command::Suggest(args) => !args.repeat,
+        Command::Explain(_) => true,
+        _ => false,
+    };
+    if needs_provider && setup::should_offer(&config) {
+        eprintln!("No provider is configured yet, so let's set one up.");
+        if !setup::run()? {
+            return Ok(());
+        }
+        config = AppConfig::load_with_cli(cli_overrides(&cli));
+    }
 
     match cli.command {
+        Command::Suggest(args) if args.repeat => {
+            suggest::repeat_last_command(config.output_format.value)?;
+        }
         Command::Suggest(args) => {
-            let validated_config = config.validate()?;
+            let validated_config = config.validate().map_err(ConfigError)?;
+            ollama::ensure_model(&validated_config, cli.global.pull)?;
 
             let opts = suggest::SuggestOptions {
                 ctx: args.ctx,
+                explain_first: args.explain_first,
+                plan: args.plan,
+                history_context: args.history_context,
+                copy: args.copy,
+                last: args.last,
                 prompt: args.prompt,
             };
             suggest::run_suggest(&validated_config, opts).await?;
         }
         Command::Explain(args) => {
-            let validated_config = config.validate()?;
+            let validated_config = config.validate().map_err(ConfigError)?;
+            ollama::ensure_model(&validated_config, cli.global.pull)?;
             let opts = explain::ExplainOptions {
                 command: args.command,
+                no_cache: args.no_cache,
+                interactive: args.interactive,
+                citations: args.citations,
+                batch: args.batch,
+                clipboard: args.clipboard,
+                with_output: args.with_output,
+                allow_unsafe_run: args.i_know_this_runs_it,
             };
             explain::run_explain(&validated_config, opts).await?;
         }
@@ -220,18 +550,39 @@ async fn main() -> Result<()> {
                     ConfigAction::Schema => {
                         AppConfig::print_schema(config.output_format.value);
                     }
+                    ConfigAction::Providers => {
+                        config.print_providers(config.output_format.value);
+                    }
+                    ConfigAction::Models => {
+                        let validated_config = config.validate().map_err(ConfigError)?;
+                        models::run(&validated_config, config.output_format.value)?;
+                    }
+                    ConfigAction::Locales => {
+                        config.print_locales(config.output_format.value);
+                    }
+                    ConfigAction::Edit => unreachable!("handled before config loading"),
                 }
             } else {
                 // Default: print current config
                 match config.output_format.value {
                     OutputFormat::Human => config.print_human(),
-                    OutputFormat::Json => config.print_json(),
+                    format => config.print_structured(format),
                 }
             }
         }
         Command::Integration(args) => {
             integration::run(args, config.output_format.value)?;
         }
+        Command::History(args) => {
+            history::run(args, config.output_format.value)?;
+        }
+        Command::Usage(args) => {
+            usage::run(args, config.output_format.value)?;
+        }
+        Command::Doctor => {
+            doctor::run(&config, config.output_format.value)?;
+        }
+        Command::Setup => unreachable!("handled before config loading"),
     }
 
     Ok(())
@@ -0,0 +1,117 @@
+//! On-disk cache for extracted man page references.
+//!
+//! Entries live under `man/` in the cache directory and are keyed by the
+//! command name, the man page file's mtime, and the per-page character limit,
+//! so an updated man page or a changed limit is a cache miss. The cache is
+//! capped in size; least recently used entries are evicted first.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+
+/// Maximum total size of the man page cache in bytes.
+const MAX_CACHE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Get the man page cache directory.
+fn cache_dir() -> Option<PathBuf> {
+    let mut base = config::cache_dir()?;
+    base.push("man");
+    Some(base)
+}
+
+/// Build the cache file path for a man page.
+///
+/// Returns `None` if the cache directory or the man page mtime is unavailable.
+fn entry_path(cmd: &str, man_path: &Path, max_chars: usize) -> Option<PathBuf> {
+    let mtime = fs::metadata(man_path)
+        .and_then(|m| m.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+
+    // Command names can contain path separators (e.g. `/usr/bin/ls`)
+    let safe_cmd: String = cmd
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_+.".contains(c) { c } else { '_' })
+        .collect();
+
+    let mut path = cache_dir()?;
+    path.push(format!("{}-{}-{}.txt", safe_cmd, mtime, max_chars));
+    Some(path)
+}
+
+/// Look up a cached man page reference.
+///
+/// Refreshes the entry's mtime on a hit so eviction is least-recently-used.
+pub fn get(cmd: &str, man_path: &Path, max_chars: usize) -> Option<String> {
+    let path = entry_path(cmd, man_path, max_chars)?;
+    let content = fs::read_to_string(&path).ok()?;
+
+    if let Ok(file) = File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+
+    log::debug!("Man page cache hit for '{}'", cmd);
+    Some(content)
+}
+
+/// Store a man page reference in the cache, then evict old entries if over the size cap.
+///
+/// Failures are logged at debug level and otherwise ignored.
+pub fn put(cmd: &str, man_path: &Path, max_chars: usize, content: &str) {
+    let Some(path) = entry_path(cmd, man_path, max_chars) else {
+        return;
+    };
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            log::debug!("Failed to create man page cache directory: {}", e);
+            return;
+        }
+    }
+
+    if let Err(e) = fs::write(&path, content) {
+        log::debug!("Failed to write man page cache for '{}': {}", cmd, e);
+        return;
+    }
+
+    evict();
+}
+
+/// Remove least recently used entries until the cache fits under the size cap.
+fn evict() {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            meta.is_file()
+                .then(|| (e.path(), meta.len(), meta.modified().unwrap_or(UNIX_EPOCH)))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= MAX_CACHE_BYTES {
+        return;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            log::debug!("Evicted man page cache entry: {}", path.display());
+            total = total.saturating_sub(len);
+        }
+    }
+}
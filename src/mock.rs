@@ -0,0 +1,156 @@
+//! The `mock` provider: canned answers from simple keyword rules.
+//!
+//! Requests never leave the process, so shell-ai can be demoed without an API
+//! key and exercised without a network. Responses come back in the OpenAI chat
+//! completion shape, so callers can't tell them from a real provider's.
+
+use serde_json::{json, Value};
+
+/// Keyword rules for suggestions, checked in order: the first rule with any
+/// keyword in the request supplies the command and its plan step description.
+/// Keywords match whole words, singular or plural.
+const RULES: &[(&[&str], &str, &str)] = &[
+    (&["disk space", "disk usage", "free space"], "df -h", "Show free space on each mounted filesystem"),
+    (&["large file", "big file", "biggest file", "largest file"], "find . -type f -size +100M", "Find files larger than 100 MB"),
+    (&["directory size", "folder size"], "du -sh ./*", "Show the size of each item in the current directory"),
+    (&["port", "listening"], "lsof -i -P -n | grep LISTEN", "List processes listening on network ports"),
+    (&["process", "running"], "ps aux", "List running processes"),
+    (&["memory", "ram"], "free -h", "Show memory usage"),
+    (&["extract", "untar", "unpack"], "tar -xzf archive.tar.gz", "Extract a gzipped tar archive"),
+    (&["compress", "archive", "tar"], "tar -czf archive.tar.gz .", "Create a gzipped tar archive of the current directory"),
+    (&["git log", "commit history", "recent commit"], "git log --oneline -n 10", "Show the last 10 commits"),
+    (&["git status", "changed files", "uncommitted"], "git status --short", "Show changed files in the working tree"),
+    (&["search", "grep", "containing"], "grep -rn 'TODO' .", "Search files recursively for a pattern"),
+    (&["find", "locate"], "find . -name '*.txt'", "Find files by name"),
+    (&["count line", "line count", "how many line"], "wc -l *", "Count lines in each file"),
+    (&["date", "time"], "date", "Print the current date and time"),
+    (&["ip address", "network interface"], "ip addr", "Show network interfaces and addresses"),
+    (&["list", "file", "directory", "folder"], "ls -la", "List files in the current directory"),
+];
+
+/// Used when no rule matches.
+const FALLBACK: (&str, &str) = ("echo 'The mock provider has no rule for this request'", "Explain that no rule matched");
+
+/// Connectors that split a plan request into steps.
+const STEP_SEPARATORS: &[&str] = &[", and then ", " and then ", ", then ", " then "];
+
+/// Answer an OpenAI-style chat completion request without any network access.
+pub fn chat_completion(payload: &Value) -> Value {
+    let request = payload["messages"]
+        .as_array()
+        .and_then(|messages| messages.iter().rev().find(|m| m["role"] == "user"))
+        .and_then(|m| m["content"].as_str())
+        .unwrap_or_default();
+
+    let content = match payload["response_format"]["json_schema"]["name"].as_str() {
+        Some("shell_command_suggestion") => json!({ "command": suggest(request).0 }),
+        Some("shell_command_plan") => plan(request),
+        Some("command_explanation") => explain(request),
+        _ => json!({ "command": suggest(request).0 }),
+    };
+
+    json!({
+        "choices": [{
+            "message": { "role": "assistant", "content": content.to_string() },
+            "finish_reason": "stop"
+        }],
+        "usage": { "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 }
+    })
+}
+
+/// The command and description of the first rule matching `request`.
+fn suggest(request: &str) -> (&'static str, &'static str) {
+    let words: Vec<String> = request
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect();
+    RULES
+        .iter()
+        .find(|(keywords, _, _)| keywords.iter().any(|k| mentions(&words, k)))
+        .map_or(FALLBACK, |(_, command, description)| (*command, *description))
+}
+
+/// Whether `words` contains the words of `phrase` in sequence, each either
+/// exactly or with a plural "s".
+fn mentions(words: &[String], phrase: &str) -> bool {
+    let phrase: Vec<&str> = phrase.split(' ').collect();
+    words.windows(phrase.len()).any(|window| {
+        window.iter().zip(&phrase).all(|(word, k)| word == k || word.strip_suffix('s') == Some(k))
+    })
+}
+
+/// One step per "then"-separated part of the request.
+fn plan(request: &str) -> Value {
+    let task = request.split_once(": ").map_or(request, |(_, task)| task);
+    let mut parts = vec![task.to_string()];
+    for separator in STEP_SEPARATORS {
+        parts = parts.iter().flat_map(|p| p.split(separator).map(str::to_string).collect::<Vec<_>>()).collect();
+    }
+
+    let steps: Vec<Value> = parts
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+        .map(|part| {
+            let (command, description) = suggest(part);
+            json!({ "command": command, "description": description })
+        })
+        .collect();
+    json!({ "steps": steps })
+}
+
+/// A token-by-token breakdown: each command in a pipeline or list, with its
+/// options and arguments as children.
+fn explain(command: &str) -> Value {
+    let mut explanations: Vec<Value> = Vec::new();
+    let mut program: Option<String> = None;
+
+    for token in command.split_whitespace() {
+        let operator = match token {
+            "|" => Some("pipes the output of the previous command into the next one"),
+            "&&" => Some("runs the next command only if the previous one succeeded"),
+            "||" => Some("runs the next command only if the previous one failed"),
+            ";" => Some("runs the next command after the previous one finishes"),
+            _ => None,
+        };
+        if let Some(suffix) = operator {
+            explanations.push(node(token, None, suffix));
+            program = None;
+            continue;
+        }
+
+        match program {
+            None => {
+                explanations.push(node(token, None, &format!("runs the {} program", token)));
+                program = Some(token.to_string());
+            }
+            Some(ref name) => {
+                let child = if token.starts_with('-') {
+                    node(token, Some("The option"), &format!("is passed to {}", name))
+                } else {
+                    node(token, Some("The argument"), &format!("is passed to {}", name))
+                };
+                if let Some(children) = explanations.last_mut().and_then(|n| n["children"].as_array_mut()) {
+                    children.push(child);
+                }
+            }
+        }
+    }
+
+    json!({
+        "synopsis": "Mock explanation: a token-by-token breakdown from the mock provider, not AI output",
+        "explanations": explanations
+    })
+}
+
+fn node(segment: &str, prefix: Option<&str>, suffix: &str) -> Value {
+    json!({
+        "segment": segment,
+        "citation": null,
+        "citation_confidence": 0.0,
+        "prefix": prefix,
+        "suffix": suffix,
+        "children": []
+    })
+}
@@ -0,0 +1,108 @@
+//! Model discovery for `shell-ai config models`.
+//!
+//! Lists the model IDs offered by the active provider, from the OpenAI-style
+//! `/v1/models` endpoint or Ollama's `/api/tags`. Azure OpenAI routes requests
+//! by deployment name, Bedrock needs signed requests to a different service,
+//! and Perplexity has no listing endpoint, so none of them are queried.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde_json::json;
+
+use crate::config::{OutputFormat, Provider, ValidatedConfig};
+use crate::http;
+use crate::ollama;
+use crate::output::outln;
+use crate::provider::ProviderConfig;
+
+/// Why a provider's models can't be listed, if they can't.
+fn unsupported_note(provider: Provider) -> Option<&'static str> {
+    match provider {
+        Provider::Azure => Some(
+            "Azure OpenAI uses the deployment name instead of a model; set [azure].deployment_name (or AZURE_DEPLOYMENT_NAME) to a deployment in your resource.",
+        ),
+        Provider::Bedrock => Some(
+            "Model listing isn't supported for Amazon Bedrock; see the model IDs enabled in your AWS console under Bedrock > Model access.",
+        ),
+        Provider::Perplexity => Some(
+            "Perplexity has no model listing endpoint; see https://docs.perplexity.ai/getting-started/models for model names.",
+        ),
+        _ => None,
+    }
+}
+
+/// Query an OpenAI-compatible `/v1/models` endpoint for model IDs.
+fn list_openai_models(provider: &ProviderConfig) -> Result<Vec<String>> {
+    let url = provider.models_url();
+    let extra_headers = provider.extra_headers_ref();
+    let (status, body) = http::get_json(&url, provider.bearer_token()?, &extra_headers, &provider.request)?;
+
+    if !(200..300).contains(&status) {
+        let detail = http::extract_api_error(&body).unwrap_or_else(|| "Unknown error".to_string());
+        return Err(anyhow!("HTTP {} error from {}: {}", status, url, detail));
+    }
+
+    let data = body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| anyhow!("{} response has no \"data\" array", url))?;
+    Ok(data
+        .iter()
+        .filter_map(|m| m.get("id").and_then(|id| id.as_str()))
+        .map(|id| id.to_string())
+        .collect())
+}
+
+/// Main entry point for the config models subcommand.
+pub fn run(validated: &ValidatedConfig<'_>, output_format: OutputFormat) -> Result<()> {
+    let provider_kind = *validated.provider;
+    let name = provider_kind.metadata().name;
+    let provider = ProviderConfig::from_validated(validated);
+
+    if let Some(note) = unsupported_note(provider_kind) {
+        match output_format {
+            OutputFormat::Human => outln!("{}", note),
+            format => format.print_serialized(&json!({
+                "provider": name,
+                "models": [],
+                "note": note,
+            }))?,
+        }
+        return Ok(());
+    }
+
+    let mut models = match provider_kind {
+        Provider::Ollama => ollama::list_models(&provider)?,
+        // Any model name works; the mock ignores it
+        Provider::Mock => vec![provider.model.clone()],
+        _ => list_openai_models(&provider)?,
+    };
+    models.sort();
+
+    match output_format {
+        OutputFormat::Human => {
+            let heading = format!("{} Models", provider_kind.metadata().display_name);
+            outln!("{}", heading.bold());
+            outln!("{}", "=".repeat(heading.len()));
+            for model in &models {
+                if *model == provider.model {
+                    outln!("{} {}", "*".cyan().bold(), model.white().bold());
+                } else {
+                    outln!("  {}", model);
+                }
+            }
+            if models.is_empty() {
+                outln!("  {}", "(none)".dimmed());
+            }
+            outln!();
+            outln!("{}", format!("* = configured model ({})", provider.model).dimmed());
+        }
+        format => format.print_serialized(&json!({
+            "provider": name,
+            "configured_model": provider.model,
+            "models": models,
+        }))?,
+    }
+
+    Ok(())
+}
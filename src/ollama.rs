@@ -0,0 +1,132 @@
+//! Ollama model availability check.
+//!
+//! Ollama answers requests for a model that hasn't been pulled with a bare
+//! 404. Before the first request, the local model list (`/api/tags`) is
+//! checked so a missing model produces actionable guidance, or is pulled
+//! automatically with `--pull` / `SHAI_OLLAMA_AUTOPULL=true`.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde_json::{json, Value};
+
+use crate::config::{env, Provider, ValidatedConfig};
+use crate::http;
+use crate::logger;
+use crate::progress::Progress;
+use crate::provider::ProviderConfig;
+
+/// Whether automatic pulls are enabled via `SHAI_OLLAMA_AUTOPULL`.
+fn autopull_from_env() -> bool {
+    matches!(std::env::var(env::SHAI_OLLAMA_AUTOPULL), Ok(v) if v.to_lowercase() == "true" || v == "1")
+}
+
+/// Ollama's API root, without any `/v1/chat/completions` suffix.
+fn api_root(provider: &ProviderConfig) -> String {
+    let base = provider.base_url.trim_end_matches('/');
+    base.split("/v1/").next().unwrap_or(base).trim_end_matches('/').to_string()
+}
+
+/// Whether `wanted` names one of the installed models.
+///
+/// A model requested without a tag matches its `:latest` variant.
+fn is_installed(installed: &[String], wanted: &str) -> bool {
+    installed
+        .iter()
+        .any(|name| name == wanted || (!wanted.contains(':') && *name == format!("{}:latest", wanted)))
+}
+
+/// List locally available models from `/api/tags`.
+pub fn list_models(provider: &ProviderConfig) -> Result<Vec<String>> {
+    let url = format!("{}/api/tags", api_root(provider));
+    let (status, body) = http::get_json(&url, None, &[], &provider.request)?;
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("Ollama model list returned HTTP {}", status));
+    }
+
+    let models = body
+        .get("models")
+        .and_then(|m| m.as_array())
+        .ok_or_else(|| anyhow!("Ollama model list response has no \"models\" array"))?;
+    Ok(models
+        .iter()
+        .filter_map(|m| m.get("name").and_then(|n| n.as_str()))
+        .map(|n| n.to_string())
+        .collect())
+}
+
+/// Make sure the configured Ollama model is available before sending requests.
+///
+/// Does nothing for other providers, or if Ollama's model list can't be read
+/// (the request itself will then report the connection problem). If the model
+/// is missing, pulls it when `pull` or `SHAI_OLLAMA_AUTOPULL` is set, and
+/// otherwise fails with a hint to run `ollama pull`.
+pub fn ensure_model(validated: &ValidatedConfig<'_>, pull: bool) -> Result<()> {
+    if *validated.provider != Provider::Ollama {
+        return Ok(());
+    }
+
+    let provider = ProviderConfig::from_validated(validated);
+    let root = api_root(&provider);
+    let installed = match list_models(&provider) {
+        Ok(installed) => installed,
+        Err(e) => {
+            log::debug!("Could not query Ollama model list: {}", e);
+            return Ok(());
+        }
+    };
+
+    let model = provider.model.as_str();
+    if is_installed(&installed, model) {
+        return Ok(());
+    }
+
+    if !(pull || autopull_from_env()) {
+        return Err(anyhow!(
+            "Ollama model '{}' is not installed.\n\
+             Hint: Run `ollama pull {}`, or pass --pull (or set {}=true) to pull it automatically.",
+            model,
+            model,
+            env::SHAI_OLLAMA_AUTOPULL
+        ));
+    }
+
+    pull_model(&root, model)?;
+    if !logger::is_quiet() {
+        eprintln!("{} {}", "Pulled Ollama model:".green(), model);
+    }
+    Ok(())
+}
+
+/// Pull a model via `/api/pull`, showing download progress from the streamed status lines.
+fn pull_model(root: &str, model: &str) -> Result<()> {
+    let url = format!("{}/api/pull", root);
+    let progress = Progress::new(&format!("Pulling {}...", model));
+
+    let result = http::post_json_lines(&url, &json!({ "model": model, "stream": true }), |line| {
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            return Ok(());
+        };
+
+        if let Some(err) = event.get("error").and_then(|e| e.as_str()) {
+            return Err(anyhow!("{}", err));
+        }
+
+        if let Some(ref p) = progress {
+            let step = event.get("status").and_then(|s| s.as_str()).unwrap_or("pulling");
+            let completed = event.get("completed").and_then(|c| c.as_u64());
+            let total = event.get("total").and_then(|t| t.as_u64()).filter(|t| *t > 0);
+            match (completed, total) {
+                (Some(done), Some(total)) => {
+                    p.set_message(&format!("Pulling {}: {} ({}%)", model, step, done * 100 / total))
+                }
+                _ => p.set_message(&format!("Pulling {}: {}", model, step)),
+            }
+        }
+        Ok(())
+    });
+
+    if let Some(ref p) = progress {
+        p.finish_and_clear();
+    }
+    result.map_err(|e| anyhow!("Failed to pull Ollama model '{}': {}", model, e))
+}
@@ -0,0 +1,147 @@
+//! Where command results are printed: stdout, or the file from `--output-file`.
+//!
+//! Only final results go through here ([`out!`] and [`outln!`]); prompts,
+//! menus, progress, and logs keep using the terminal, so the command stays
+//! interactive while its output lands in the file.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Context, Result};
+
+/// The `--output-file`, opened on the first write.
+static OUTPUT_FILE: OnceLock<Mutex<OutputFile>> = OnceLock::new();
+
+/// An output file that isn't created or truncated until there is something
+/// to write, so a command that fails first leaves an existing file intact.
+struct OutputFile {
+    path: PathBuf,
+    no_clobber: bool,
+    file: Option<File>,
+}
+
+impl OutputFile {
+    fn new(path: &Path, no_clobber: bool) -> Result<Self> {
+        if no_clobber && path.exists() {
+            return Err(anyhow!("{} already exists (not overwriting because of --no-clobber)", path.display()));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        Ok(Self { path: path.to_path_buf(), no_clobber, file: None })
+    }
+
+    fn write_all(&mut self, text: &str) -> io::Result<()> {
+        let file = match self.file {
+            Some(ref mut file) => file,
+            None => {
+                let mut options = OpenOptions::new();
+                if self.no_clobber {
+                    options.write(true).create_new(true);
+                } else {
+                    options.write(true).create(true).truncate(true);
+                }
+                self.file.insert(options.open(&self.path)?)
+            }
+        };
+        file.write_all(text.as_bytes())
+    }
+}
+
+/// Send results to `path` instead of stdout for the rest of the process.
+///
+/// Parent directories are created as needed. The file itself is opened on
+/// the first write: an existing file is overwritten then, unless `no_clobber`
+/// is set, in which case it's an error up front.
+pub fn init(path: &Path, no_clobber: bool) -> Result<()> {
+    let file = OutputFile::new(path, no_clobber)?;
+    OUTPUT_FILE
+        .set(Mutex::new(file))
+        .map_err(|_| anyhow!("Output file already set"))
+}
+
+/// Write formatted results to the output file, without terminal colors, or to stdout.
+pub fn write(args: fmt::Arguments) {
+    let Some(file) = OUTPUT_FILE.get() else {
+        print!("{}", args);
+        return;
+    };
+    let text = strip_ansi(&args.to_string());
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = file.write_all(&text) {
+        match e.kind() {
+            ErrorKind::AlreadyExists => log::error!(
+                "{} already exists (not overwriting because of --no-clobber)",
+                file.path.display()
+            ),
+            _ => log::error!("Failed to write to the output file {}: {}", file.path.display(), e),
+        }
+    }
+}
+
+/// Remove ANSI escape sequences (colors and styles) from `text`.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        // CSI sequences end with a byte in the range '@'..='~'
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Like `print!`, but to the `--output-file` when one is set.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::write(format_args!($($arg)*))
+    };
+}
+
+/// Like `println!`, but to the `--output-file` when one is set.
+macro_rules! outln {
+    () => {
+        $crate::output::write(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+pub(crate) use {out, outln};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_file_is_left_alone_until_the_first_write() {
+        let dir = std::env::temp_dir().join(format!("shell-ai-output-{}", std::process::id()));
+        let path = dir.join("nested").join("out.txt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "previous results\n").unwrap();
+
+        let mut file = OutputFile::new(&path, false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous results\n");
+
+        file.write_all("ls -la\n").unwrap();
+        file.write_all("df -h\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ls -la\ndf -h\n");
+
+        assert!(OutputFile::new(&path, true).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
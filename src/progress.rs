@@ -1,13 +1,28 @@
 //! Progress indicator for shell-ai using indicatif.
 //!
 //! Shows a spinner with elapsed time in deciseconds during slow operations.
-//! Only displays when stderr is a terminal.
+//! Only displays when stderr is a terminal, and can be turned off with
+//! `progress = false` or `SHAI_NO_PROGRESS=true`. The frames and layout come
+//! from `spinner_frames` and `spinner_template`.
 
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use is_terminal::IsTerminal;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 
+/// Default `spinner_frames`: a braille dot cycle.
+pub const DEFAULT_FRAMES: &str = "⠋,⠙,⠹,⠸,⠼,⠴,⠦,⠧,⠇,⠏";
+
+/// Default `spinner_template`: spinner + message + elapsed time.
+pub const DEFAULT_TEMPLATE: &str = "{spinner:.cyan} {msg} {elapsed:.dim}";
+
+/// Whether the spinner is enabled by configuration (set after config loading).
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// The spinner style from configuration (set after config loading).
+static STYLE: OnceLock<ProgressStyle> = OnceLock::new();
+
 /// Global active progress bar for coordination with the logger.
 /// When set, the logger will suspend this bar before printing.
 static ACTIVE_BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
@@ -26,6 +41,59 @@ where
     }
 }
 
+/// Clear the active progress bar from the terminal, if any (e.g., on Ctrl+C).
+pub fn clear_active() {
+    if let Some(bar) = ACTIVE_BAR.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        bar.finish_and_clear();
+    }
+}
+
+/// Enable or disable the spinner from the `progress` setting.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Set the spinner style from the `spinner_frames` and `spinner_template` settings.
+///
+/// An invalid template or an empty frame list logs a warning and keeps the default.
+pub fn set_style(frames: &str, template: &str) {
+    let style = ProgressStyle::default_spinner().template(template).unwrap_or_else(|e| {
+        log::warn!("Invalid spinner_template {:?} ({}); using the default", template, e);
+        default_template()
+    });
+
+    let frames = if frames.split(',').any(|f| !f.trim().is_empty()) {
+        frames
+    } else {
+        log::warn!("spinner_frames has no frames; using the default");
+        DEFAULT_FRAMES
+    };
+
+    let _ = STYLE.set(with_frames(style, frames));
+}
+
+/// The built-in style, used until configuration provides one.
+fn default_style() -> ProgressStyle {
+    with_frames(default_template(), DEFAULT_FRAMES)
+}
+
+fn default_template() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .template(DEFAULT_TEMPLATE)
+        .unwrap_or_else(|_| ProgressStyle::default_spinner())
+}
+
+/// Animate `style` with the comma-separated `frames`.
+fn with_frames(style: ProgressStyle, frames: &str) -> ProgressStyle {
+    let mut frames: Vec<&str> = frames.split(',').map(str::trim).filter(|f| !f.is_empty()).collect();
+    // indicatif holds back the last tick string for the finished state, so
+    // repeat the first frame there to keep every configured frame animating
+    if let Some(&first) = frames.first() {
+        frames.push(first);
+    }
+    style.tick_strings(&frames)
+}
+
 /// A progress indicator that shows a spinner with elapsed time.
 ///
 /// Example output: `⠹ Generating suggestions... 2.3s`
@@ -36,22 +104,17 @@ pub struct Progress {
 impl Progress {
     /// Create a new progress indicator with the given message.
     ///
-    /// Returns `None` if stderr is not a terminal (e.g., piped output).
+    /// Returns `None` if the spinner is disabled or stderr is not a terminal
+    /// (e.g., piped output).
     pub fn new(message: &str) -> Option<Self> {
-        if !std::io::stderr().is_terminal() {
+        if !ENABLED.load(Ordering::Relaxed) || !std::io::stderr().is_terminal() {
             return None;
         }
 
         let bar = ProgressBar::new_spinner();
         bar.set_draw_target(ProgressDrawTarget::stderr());
 
-        // Style: spinner + message + elapsed time
-        let style = ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg} {elapsed:.dim}")
-            .expect("Invalid progress template")
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]);
-
-        bar.set_style(style);
+        bar.set_style(STYLE.get().cloned().unwrap_or_else(default_style));
         bar.set_message(message.to_string());
 
         // Tick every 100ms for smooth animation and decisecond updates
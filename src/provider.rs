@@ -1,4 +1,14 @@
-use crate::config::{Provider, ValidatedConfig};
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::bedrock;
+use crate::config::{env, AzureAuth, ConfigSource, JsonSchemaMode, Provider, ReasoningEffort, ValidatedConfig};
+use crate::http::{self, AwsCredentials, HttpStatusError, RequestSettings};
+use crate::mock;
+use crate::usage::UsageTracker;
 
 /// Provider configuration for making API requests.
 #[derive(Clone)]
@@ -11,6 +21,74 @@ pub struct ProviderConfig {
     pub extra_headers: Vec<(String, String)>,
     /// Max tokens for AI response (optional, API auto-calculates when None).
     pub max_tokens: Option<u32>,
+    /// Timeout and retry settings for requests to this provider.
+    pub request: RequestSettings,
+    /// AWS credentials for SigV4-signed providers (Bedrock) instead of a bearer token.
+    pub aws: Option<AwsCredentials>,
+    /// Path appended to `base_url` for chat completions, replacing the
+    /// auto-detected `/v1/chat/completions` suffix (for custom gateways).
+    pub chat_path: Option<String>,
+    /// Models-list URL, when it isn't the chat completions URL with `/models`
+    /// in place of `/chat/completions` (Azure lists models outside deployments).
+    pub models_url: Option<String>,
+    /// Answer requests in-process from canned rules instead of over the
+    /// network (the mock provider).
+    pub mock: bool,
+    /// Authenticate with an Entra ID access token, fetched on first use
+    /// (Azure with `auth = "token"`), instead of `api_key`.
+    pub azure_ad_token: bool,
+    /// Structured-output mode, shared between clones so a downgrade after a
+    /// schema rejection applies to parallel and later requests too.
+    pub json_schema_mode: Arc<Mutex<JsonSchemaMode>>,
+    /// `reasoning_effort` to send (OpenAI and Azure only).
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Whether to send `temperature`, shared between clones so a rejection
+    /// by a reasoning model applies to parallel and later requests too.
+    pub send_temperature: Arc<AtomicBool>,
+    /// Token usage summed over requests, shared between clones so parallel
+    /// suggestion requests add up.
+    pub usage: Arc<UsageTracker>,
+}
+
+/// Resource the Entra ID access token is requested for.
+const AZURE_TOKEN_RESOURCE: &str = "https://cognitiveservices.azure.com";
+
+static AZURE_AD_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Entra ID access token for Azure OpenAI, fetched once per process from
+/// `AZURE_OPENAI_AD_TOKEN` or, failing that, the Azure CLI's signed-in account.
+pub fn azure_ad_token() -> Result<&'static str> {
+    if let Some(token) = AZURE_AD_TOKEN.get() {
+        return Ok(token);
+    }
+
+    let token = match std::env::var(env::AZURE_OPENAI_AD_TOKEN) {
+        Ok(token) if !token.trim().is_empty() => token.trim().to_string(),
+        _ => az_access_token()?,
+    };
+    Ok(AZURE_AD_TOKEN.get_or_init(|| token))
+}
+
+/// Ask the Azure CLI for an access token.
+fn az_access_token() -> Result<String> {
+    let az = crate::explain::find_in_path(if cfg!(windows) { "az.cmd" } else { "az" })
+        .ok_or_else(|| anyhow!("{} is not set and the Azure CLI (az) is not on PATH", env::AZURE_OPENAI_AD_TOKEN))?;
+    let output = Command::new(az)
+        .args(["account", "get-access-token", "--resource", AZURE_TOKEN_RESOURCE, "--query", "accessToken", "--output", "tsv"])
+        .output()
+        .context("Failed to run `az account get-access-token`")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "`az account get-access-token` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        return Err(anyhow!("`az account get-access-token` returned no token"));
+    }
+    Ok(token)
 }
 
 impl ProviderConfig {
@@ -20,12 +98,27 @@ impl ProviderConfig {
     /// the provider and credentials exist. No `Result` needed - the types
     /// enforce that validation has occurred.
     pub fn from_validated(validated: &ValidatedConfig) -> Self {
-        let temperature = validated.temperature();
+        let temperature = validated.effective_temperature();
         let max_tokens = validated.effective_max_tokens();
+        let config = validated.app_config();
+        let request = RequestSettings {
+            timeout_secs: config.request_timeout.value,
+            max_retries: config.max_retries.value,
+            initial_backoff_ms: config.initial_backoff_ms.value,
+        };
         let provider = validated.provider;
+        let json_schema_mode = default_json_schema_mode(*provider, config.json_schema_mode.source)
+            .unwrap_or(config.json_schema_mode.value);
+        let json_schema_mode = Arc::new(Mutex::new(json_schema_mode));
+        let usage = Arc::new(UsageTracker::new(validated));
+        // Only OpenAI's API takes reasoning_effort, and reasoning models only
+        // accept the default temperature
+        let reasoning_effort = config.reasoning_effort.value
+            .filter(|_| matches!(provider, Provider::OpenAI | Provider::Azure));
+        let send_temperature = Arc::new(AtomicBool::new(reasoning_effort.is_none()));
         let creds = validated.credentials;
 
-        match provider {
+        let mut provider_config = match provider {
             Provider::OpenAI => {
                 let base = creds.api_base.clone()
                     .unwrap_or_else(|| "https://api.openai.com".to_string());
@@ -40,6 +133,16 @@ impl ProviderConfig {
                     temperature,
                     extra_headers,
                     max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort,
+                    send_temperature: send_temperature.clone(),
                 }
             }
             Provider::Azure => {
@@ -50,25 +153,39 @@ impl ProviderConfig {
                     .unwrap_or_else(|| "2023-05-15".to_string());
                 let api_key = creds.api_key.clone()
                     .or_else(|| {
-                        validated.app_config()
+                        config
                             .get_credentials_for(&Provider::OpenAI)
                             .and_then(|c| c.api_key.clone())
                     });
 
-                let url = format!(
-                    "{}/openai/deployments/{}/chat/completions?api-version={}",
-                    base.trim_end_matches('/'), deployment, api_version
-                );
+                let url = azure_chat_url(&base, &deployment, &api_version);
 
-                let header_val = api_key.clone().unwrap_or_default();
+                // Entra ID tokens go out as the bearer Authorization header; keys use api-key
+                let token_auth = creds.auth.unwrap_or_default() == AzureAuth::Token;
+                let (api_key, extra_headers) = if token_auth {
+                    (None, Vec::new())
+                } else {
+                    let header_val = api_key.clone().unwrap_or_default();
+                    (api_key, vec![("api-key".to_string(), header_val)])
+                };
 
                 ProviderConfig {
                     base_url: url,
                     model: String::new(), // Azure uses deployment name, not model
                     api_key,
                     temperature,
-                    extra_headers: vec![("api-key".to_string(), header_val)],
+                    extra_headers,
                     max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: None,
+                    models_url: Some(azure_models_url(&base, &api_version)),
+                    mock: false,
+                    azure_ad_token: token_auth,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort,
+                    send_temperature: send_temperature.clone(),
                 }
             }
             Provider::Ollama => {
@@ -81,6 +198,39 @@ impl ProviderConfig {
                     temperature,
                     extra_headers: vec![],
                     max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
+                }
+            }
+            Provider::Local => {
+                // llama.cpp's server and LM Studio both serve /v1/chat/completions
+                let base = creds.api_base.clone()
+                    .unwrap_or_else(|| "http://localhost:8080".to_string());
+                ProviderConfig {
+                    base_url: base,
+                    model: validated.effective_model(),
+                    api_key: None,
+                    temperature,
+                    extra_headers: vec![],
+                    max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
                 }
             }
             Provider::Mistral => {
@@ -93,6 +243,66 @@ impl ProviderConfig {
                     temperature,
                     extra_headers: vec![],
                     max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
+                }
+            }
+            Provider::Perplexity => {
+                // Perplexity serves chat completions without a /v1 prefix
+                let base = creds.api_base.clone()
+                    .unwrap_or_else(|| "https://api.perplexity.ai".to_string());
+                let url = if base.contains("/chat/completions") {
+                    base
+                } else {
+                    format!("{}/chat/completions", base.trim_end_matches('/'))
+                };
+                ProviderConfig {
+                    base_url: url,
+                    model: validated.effective_model(),
+                    api_key: creds.api_key.clone(),
+                    temperature,
+                    extra_headers: vec![],
+                    max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
+                }
+            }
+            Provider::DeepSeek => {
+                let base = creds.api_base.clone()
+                    .unwrap_or_else(|| "https://api.deepseek.com".to_string());
+                ProviderConfig {
+                    base_url: base,
+                    model: validated.effective_model(),
+                    api_key: creds.api_key.clone(),
+                    temperature,
+                    extra_headers: vec![],
+                    max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
                 }
             }
             Provider::Groq => {
@@ -105,24 +315,506 @@ impl ProviderConfig {
                     temperature,
                     extra_headers: vec![],
                     max_tokens,
+                    request,
+                    aws: None,
+                    chat_path: creds.chat_path.clone(),
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
                 }
             }
+            Provider::Bedrock => {
+                let region = creds.region.clone().unwrap_or_default();
+                let base = creds.api_base.clone()
+                    .unwrap_or_else(|| bedrock::default_api_base(&region));
+                let model = validated.effective_model();
+                ProviderConfig {
+                    base_url: bedrock::invoke_url(&base, &model),
+                    model,
+                    api_key: None,
+                    temperature,
+                    extra_headers: vec![],
+                    max_tokens,
+                    request,
+                    aws: Some(AwsCredentials {
+                        access_key_id: creds.access_key_id.clone().unwrap_or_default(),
+                        secret_access_key: creds.secret_access_key.clone().unwrap_or_default(),
+                        session_token: creds.session_token.clone(),
+                        region,
+                        service: "bedrock".to_string(),
+                    }),
+                    chat_path: None,
+                    models_url: None,
+                    mock: false,
+                    azure_ad_token: false,
+                    json_schema_mode,
+                    usage: usage.clone(),
+                    reasoning_effort: None,
+                    send_temperature: send_temperature.clone(),
+                }
+            }
+            Provider::Mock => ProviderConfig {
+                base_url: String::new(),
+                model: validated.effective_model(),
+                api_key: None,
+                temperature,
+                extra_headers: vec![],
+                max_tokens,
+                request,
+                aws: None,
+                chat_path: None,
+                models_url: None,
+                mock: true,
+                azure_ad_token: false,
+                json_schema_mode,
+                usage: usage.clone(),
+                reasoning_effort: None,
+                send_temperature: send_temperature.clone(),
+            },
+        };
+
+        // Headers that carry credentials are never replaced by configured ones
+        let auth_headers: &[&str] = match provider {
+            Provider::Azure => &["authorization", "api-key"],
+            Provider::Bedrock => &["authorization", "x-amz-date", "x-amz-content-sha256", "x-amz-security-token"],
+            _ => &["authorization"],
+        };
+        for (name, value) in &creds.extra_headers {
+            if auth_headers.iter().any(|h| name.eq_ignore_ascii_case(h)) {
+                log::warn!("Ignoring extra header '{}', which would replace the {} credentials", name, provider.metadata().display_name);
+                continue;
+            }
+            let shown = if http::is_sensitive_header(name) { "****" } else { value.as_str() };
+            log::debug!("Sending extra header {}: {}", name, shown);
+            provider_config.extra_headers.retain(|(k, _)| !k.eq_ignore_ascii_case(name));
+            provider_config.extra_headers.push((name.clone(), value.clone()));
         }
+        provider_config
+    }
+
+    /// A copy of this provider that requests `model` instead, sharing usage
+    /// tracking and learned request adjustments with the original.
+    pub fn with_model(&self, model: &str) -> Self {
+        let mut other = self.clone();
+        if self.aws.is_some() {
+            // Bedrock puts the model in the URL rather than the payload
+            if let Some((base, _)) = self.base_url.rsplit_once("/model/") {
+                other.base_url = bedrock::invoke_url(base, model);
+            }
+        }
+        other.model = model.to_string();
+        other
     }
 
     /// Get the chat completions URL for this provider.
     pub fn chat_completions_url(&self) -> String {
-        if self.base_url.contains("/chat/completions") {
+        match &self.chat_path {
+            Some(path) if self.aws.is_none() => format!(
+                "{}/{}",
+                self.base_url.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            _ => self.default_chat_completions_url(),
+        }
+    }
+
+    /// The chat completions URL without `chat_path`: `base_url` as-is if it
+    /// already names the endpoint, otherwise with `/v1/chat/completions` appended.
+    fn default_chat_completions_url(&self) -> String {
+        if self.aws.is_some() || self.base_url.contains("/chat/completions") {
             self.base_url.clone()
         } else {
             format!("{}/v1/chat/completions", self.base_url.trim_end_matches('/'))
         }
     }
 
+    /// Get the models-list URL for this provider, usually derived from the chat
+    /// completions URL. A `chat_path` without `/chat/completions` says nothing
+    /// about where models are listed, so the auto-detected URL is used instead.
+    pub fn models_url(&self) -> String {
+        if let Some(ref url) = self.models_url {
+            return url.clone();
+        }
+        let chat_url = self.chat_completions_url();
+        let chat_url = if chat_url.contains("/chat/completions") {
+            chat_url
+        } else {
+            self.default_chat_completions_url()
+        };
+        chat_url.replacen("/chat/completions", "/models", 1)
+    }
+
+    /// The bearer token to send: `api_key`, or the Entra ID access token for
+    /// Azure token auth, which is fetched on the first request.
+    pub fn bearer_token(&self) -> Result<Option<&str>> {
+        if !self.azure_ad_token {
+            return Ok(self.api_key.as_deref());
+        }
+        azure_ad_token().map(Some).map_err(|e| {
+            anyhow!(
+                "Could not get an Entra ID access token for Azure OpenAI: {}\n\
+                 Hint: Set {} or sign in with `az login`.",
+                e,
+                env::AZURE_OPENAI_AD_TOKEN
+            )
+        })
+    }
+
     /// Get extra headers as borrowed string slices for use with http functions.
     pub fn extra_headers_ref(&self) -> Vec<(&str, &str)> {
         self.extra_headers.iter()
             .map(|(k, v)| (k.as_str(), v.as_str()))
             .collect()
     }
-}
\ No newline at end of file
+
+    /// Send an OpenAI-style chat completion request and return the response JSON.
+    ///
+    /// Retries transient failures; non-2xx responses become errors.
+    pub fn post_chat(&self, payload: &Value) -> Result<Value> {
+        if self.mock {
+            return Ok(mock::chat_completion(payload));
+        }
+        let Some(ref aws) = self.aws else {
+            let url = self.chat_completions_url();
+            let extra_headers = self.extra_headers_ref();
+            let bearer_token = self.bearer_token()?;
+            loop {
+                let mode = self.current_json_schema_mode();
+                let payload = self.prepare_payload(payload, mode);
+                match http::post_json(&url, bearer_token, &extra_headers, &payload, &self.request) {
+                    Err(e) => match e.downcast_ref::<HttpStatusError>() {
+                        Some(err) if self.adjust_after_rejection(mode, err.status, &err.body) => continue,
+                        _ => return Err(e),
+                    },
+                    Ok(mut json) => {
+                        self.record_usage(&json);
+                        if mode == JsonSchemaMode::None {
+                            strip_content_code_fence(&mut json);
+                        }
+                        return Ok(json);
+                    }
+                }
+            }
+        };
+
+        let (status, body) = http::post_json_signed(&self.base_url, &bedrock::to_invoke_body(payload), aws, &self.extra_headers_ref(), &self.request)?;
+        if !(200..300).contains(&status) {
+            let message = format!("HTTP {}: {}", status, bedrock::error_message(&body));
+            return Err(anyhow::Error::new(HttpStatusError { status, body }).context(message));
+        }
+        let json: Value = serde_json::from_str(&body)
+            .map_err(|e| anyhow!("Failed to parse JSON: {}", e))?;
+        let completion = bedrock::to_chat_completion(&json)?;
+        self.record_usage(&completion);
+        Ok(completion)
+    }
+
+    /// Send an OpenAI-style chat completion request and return the response
+    /// status and body, with successful bodies in the OpenAI response shape.
+    pub fn post_chat_raw(&self, payload: &Value) -> Result<(u16, String)> {
+        if self.mock {
+            return Ok((200, mock::chat_completion(payload).to_string()));
+        }
+        let Some(ref aws) = self.aws else {
+            let url = self.chat_completions_url();
+            let extra_headers = self.extra_headers_ref();
+            let bearer_token = self.bearer_token()?;
+            loop {
+                let mode = self.current_json_schema_mode();
+                let payload = self.prepare_payload(payload, mode);
+                let (status, body) = http::post_json_raw(&url, bearer_token, &extra_headers, &payload, &self.request)?;
+                if !self.adjust_after_rejection(mode, status, &body) {
+                    if (200..300).contains(&status) {
+                        if let Ok(mut json) = serde_json::from_str::<Value>(&body) {
+                            self.record_usage(&json);
+                            if mode == JsonSchemaMode::None {
+                                strip_content_code_fence(&mut json);
+                                return Ok((status, json.to_string()));
+                            }
+                        }
+                    }
+                    return Ok((status, body));
+                }
+            }
+        };
+
+        let (status, body) = http::post_json_signed(&self.base_url, &bedrock::to_invoke_body(payload), aws, &self.extra_headers_ref(), &self.request)?;
+        if !(200..300).contains(&status) {
+            return Ok((status, bedrock::error_message(&body)));
+        }
+        let json: Value = serde_json::from_str(&body)
+            .map_err(|e| anyhow!("Failed to parse JSON: {}", e))?;
+        let completion = bedrock::to_chat_completion(&json)?;
+        self.record_usage(&completion);
+        Ok((status, completion.to_string()))
+    }
+
+    /// Add a response's reported token usage to the running total.
+    fn record_usage(&self, resp_json: &Value) {
+        if let Some(usage) = http::extract_usage(resp_json) {
+            self.usage.add(usage);
+        }
+    }
+
+    /// Print (with `show_usage`) and record the token usage summed so far.
+    pub fn report_usage(&self) {
+        self.usage.report();
+    }
+
+    /// Adapt a payload to this provider's current settings: the JSON schema
+    /// mode, `reasoning_effort`, and whether `temperature` is accepted.
+    fn prepare_payload(&self, payload: &Value, mode: JsonSchemaMode) -> Value {
+        let mut payload = apply_json_schema_mode(payload, mode);
+        if let Some(effort) = self.reasoning_effort {
+            payload["reasoning_effort"] = json!(effort.to_string());
+        }
+        if !self.send_temperature.load(Ordering::Relaxed) {
+            if let Some(obj) = payload.as_object_mut() {
+                obj.remove("temperature");
+            }
+        }
+        payload
+    }
+
+    /// Adjust settings after a 400 response that rejected part of the request.
+    /// Returns whether to retry.
+    fn adjust_after_rejection(&self, mode: JsonSchemaMode, status: u16, body: &str) -> bool {
+        if status == 400 && is_temperature_rejection(body) && self.send_temperature.swap(false, Ordering::Relaxed) {
+            log::debug!("Model rejected the temperature setting; retrying without it");
+            return true;
+        }
+        self.downgrade_json_schema_mode(mode, status, body)
+    }
+
+    fn current_json_schema_mode(&self) -> JsonSchemaMode {
+        *self.json_schema_mode.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Step down to the next looser JSON schema mode if a response shows the
+    /// provider rejected the schema sent under `mode`. Returns whether to retry.
+    fn downgrade_json_schema_mode(&self, mode: JsonSchemaMode, status: u16, body: &str) -> bool {
+        if status != 400 || !is_schema_rejection(body) {
+            return false;
+        }
+        let next = match mode {
+            JsonSchemaMode::Strict => JsonSchemaMode::Lenient,
+            JsonSchemaMode::Lenient => JsonSchemaMode::None,
+            JsonSchemaMode::None => return false,
+        };
+
+        let mut current = self.json_schema_mode.lock().unwrap_or_else(|e| e.into_inner());
+        // A parallel request may have downgraded already
+        if *current == mode {
+            log::warn!(
+                "Provider rejected the {} JSON schema; retrying with json_schema_mode = {} (set it in your config to skip this)",
+                mode, next
+            );
+            *current = next;
+        }
+        true
+    }
+}
+
+/// Unwrap a code fence around the message content, which models not held to
+/// a JSON schema sometimes add despite instructions.
+fn strip_content_code_fence(resp_json: &mut Value) {
+    if let Some(content) = resp_json.pointer_mut("/choices/0/message/content") {
+        if let Some(text) = content.as_str() {
+            *content = json!(http::strip_code_fence(text));
+        }
+    }
+}
+
+/// Azure OpenAI's chat completions URL for a deployment.
+fn azure_chat_url(base: &str, deployment: &str, api_version: &str) -> String {
+    format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        base.trim_end_matches('/'), deployment, api_version
+    )
+}
+
+/// Azure OpenAI's models-list URL, which is per resource rather than per deployment.
+fn azure_models_url(base: &str, api_version: &str) -> String {
+    format!("{}/openai/models?api-version={}", base.trim_end_matches('/'), api_version)
+}
+
+/// Whether an HTTP 400 body says the model doesn't accept `temperature`, as
+/// reasoning models (gpt-5, o-series) do for anything but the default.
+fn is_temperature_rejection(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("temperature")
+        && ["unsupported", "not supported", "does not support"].iter().any(|needle| body.contains(needle))
+}
+
+/// Whether an HTTP 400 body says the provider doesn't accept the
+/// `response_format` JSON schema, rather than rejecting something else.
+fn is_schema_rejection(body: &str) -> bool {
+    let body = body.to_lowercase();
+    ["response_format", "json_schema"].iter().any(|keyword| body.contains(keyword))
+        && ["unsupported", "not supported", "does not support", "invalid", "unavailable", "not allowed", "unrecognized"]
+            .iter()
+            .any(|needle| body.contains(needle))
+}
+
+/// The JSON schema mode a provider needs when none is configured explicitly:
+/// Perplexity and DeepSeek get the schema in the prompt since they reject JSON
+/// schema response formats, and Mistral gets it without `strict`, which its
+/// schemas handle poorly.
+fn default_json_schema_mode(provider: Provider, source: ConfigSource) -> Option<JsonSchemaMode> {
+    match (provider, source) {
+        (Provider::Perplexity | Provider::DeepSeek, ConfigSource::Default) => Some(JsonSchemaMode::None),
+        (Provider::Mistral, ConfigSource::Default) => Some(JsonSchemaMode::Lenient),
+        _ => None,
+    }
+}
+
+/// Adapt a payload's `response_format` JSON schema to `mode`.
+fn apply_json_schema_mode(payload: &Value, mode: JsonSchemaMode) -> Value {
+    let mut payload = payload.clone();
+    let Some(schema) = payload.pointer("/response_format/json_schema/schema").cloned() else {
+        return payload;
+    };
+
+    match mode {
+        JsonSchemaMode::Strict => {}
+        JsonSchemaMode::Lenient => {
+            let json_schema = &mut payload["response_format"]["json_schema"];
+            if let Some(obj) = json_schema.as_object_mut() {
+                obj.remove("strict");
+            }
+            strip_additional_properties(&mut json_schema["schema"]);
+        }
+        JsonSchemaMode::None => {
+            if let Some(obj) = payload.as_object_mut() {
+                obj.remove("response_format");
+            }
+            let instruction = format!(
+                "Respond with only a JSON object (no code fences or other text) matching this JSON schema:\n{}",
+                schema
+            );
+            let messages = payload["messages"].as_array_mut();
+            match messages.and_then(|m| m.iter_mut().find(|m| m["role"] == "system")) {
+                Some(system) => {
+                    let content = system["content"].as_str().unwrap_or_default();
+                    system["content"] = json!(format!("{}\n\n{}", content, instruction));
+                }
+                None => {
+                    if let Some(messages) = payload["messages"].as_array_mut() {
+                        messages.insert(0, json!({ "role": "system", "content": instruction }));
+                    }
+                }
+            }
+        }
+    }
+    payload
+}
+
+fn strip_additional_properties(schema: &mut Value) {
+    match schema {
+        Value::Object(obj) => {
+            obj.remove("additionalProperties");
+            obj.values_mut().for_each(strip_additional_properties);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_additional_properties),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mistral_defaults_to_lenient_schemas() {
+        assert_eq!(
+            default_json_schema_mode(Provider::Mistral, ConfigSource::Default),
+            Some(JsonSchemaMode::Lenient)
+        );
+        assert_eq!(default_json_schema_mode(Provider::Mistral, ConfigSource::Environment), None);
+    }
+
+    #[test]
+    fn lenient_payload_drops_strict_and_additional_properties() {
+        let payload = json!({
+            "model": "codestral-latest",
+            "messages": [{ "role": "system", "content": "sys" }],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "suggestions",
+                    "strict": true,
+                    "schema": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "items": {
+                                "type": "array",
+                                "items": { "type": "object", "additionalProperties": false }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let lenient = apply_json_schema_mode(&payload, JsonSchemaMode::Lenient);
+
+        assert_eq!(
+            lenient["response_format"],
+            json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "suggestions",
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "items": { "type": "array", "items": { "type": "object" } }
+                        }
+                    }
+                }
+            })
+        );
+        assert_eq!(lenient["messages"], payload["messages"]);
+    }
+
+    #[test]
+    fn azure_lists_models_outside_the_deployment() {
+        let base = "https://example.openai.azure.com/";
+
+        assert_eq!(
+            azure_chat_url(base, "gpt-4o", "2024-10-21"),
+            "https://example.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(
+            azure_models_url(base, "2024-10-21"),
+            "https://example.openai.azure.com/openai/models?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn schema_rejections_name_the_response_format() {
+        for body in [
+            r#"{"error":{"message":"Invalid schema for response_format 'suggestions': 'additionalProperties' is required to be supplied and to be false","param":"response_format"}}"#,
+            r#"{"error":{"message":"'response_format' of type 'json_schema' is not supported with this model."}}"#,
+            r#"{"error":{"message":"This response_format type is unavailable now"}}"#,
+        ] {
+            assert!(is_schema_rejection(body), "{}", body);
+        }
+    }
+
+    #[test]
+    fn other_bad_requests_are_not_schema_rejections() {
+        for body in [
+            r#"{"error":{"message":"Invalid value for 'max_tokens': must be at most 4096"}}"#,
+            r#"{"error":{"message":"Request body does not match schema: messages[0].content is required"}}"#,
+            r#"{"error":{"message":"strict mode is enabled for this organization; model not allowed"}}"#,
+            r#"{"error":{"message":"The response_format was accepted, but the model gpt-x does not exist"}}"#,
+        ] {
+            assert!(!is_schema_rejection(body), "{}", body);
+        }
+    }
+}
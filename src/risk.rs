@@ -0,0 +1,180 @@
+//! Risk classification for shell commands before execution.
+//!
+//! Flags destructive patterns (recursive deletes, raw disk writes, fork bombs,
+//! piping downloads into a shell) so the frontends can warn or require an
+//! explicit confirmation before running a suggested command.
+
+/// How dangerous a command looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    /// Nothing suspicious detected.
+    Low,
+    /// Potentially harmful; print a warning before executing.
+    Medium,
+    /// Likely destructive; require a typed confirmation before executing.
+    High,
+}
+
+/// A pattern that marks a command as risky.
+///
+/// A rule matches when every needle appears in the normalized command
+/// (see [`normalize`]) once its harmless targets are removed. Add new
+/// patterns to [`RISK_RULES`].
+#[derive(Debug, Clone, Copy)]
+pub struct RiskRule {
+    pub level: RiskLevel,
+    pub reason: &'static str,
+    pub needles: &'static [&'static str],
+    /// Substrings that make an otherwise matching command safe, such as
+    /// `of=/dev/null` for a `dd` write.
+    pub harmless: &'static [&'static str],
+}
+
+impl RiskRule {
+    const fn high(reason: &'static str, needles: &'static [&'static str]) -> Self {
+        Self { level: RiskLevel::High, reason, needles, harmless: &[] }
+    }
+
+    const fn medium(reason: &'static str, needles: &'static [&'static str]) -> Self {
+        Self { level: RiskLevel::Medium, reason, needles, harmless: &[] }
+    }
+
+    const fn except(self, harmless: &'static [&'static str]) -> Self {
+        Self { harmless, ..self }
+    }
+
+    fn matches(&self, normalized: &str) -> bool {
+        let mut command = normalized.to_string();
+        for target in self.harmless {
+            command = command.replace(target, " ");
+        }
+        self.needles.iter().all(|n| command.contains(n))
+    }
+}
+
+/// Known risky patterns, matched against the normalized command.
+pub const RISK_RULES: &[RiskRule] = &[
+    RiskRule::high("recursive forced delete", &["rm -rf"]),
+    RiskRule::high("recursive forced delete", &["rm -fr"]),
+    RiskRule::high("recursive forced delete", &["rm -r -f"]),
+    RiskRule::high("recursive forced delete", &["rm --recursive --force"]),
+    RiskRule::high("filesystem creation (erases the target device)", &["mkfs"]),
+    RiskRule::high("raw write to a block device", &["dd ", "of=/dev/"])
+        .except(&["of=/dev/null", "of=/dev/zero", "of=/dev/stdout"]),
+    RiskRule::high("raw write to a block device", &[">/dev/sd"]),
+    RiskRule::high("raw write to a block device", &[">/dev/nvme"]),
+    RiskRule::high("fork bomb", &[":()", ":|:"]),
+    RiskRule::high("downloaded script piped into a shell", &["curl ", "|sh"]),
+    RiskRule::high("downloaded script piped into a shell", &["curl ", "|bash"]),
+    RiskRule::high("downloaded script piped into a shell", &["wget ", "|sh"]),
+    RiskRule::high("downloaded script piped into a shell", &["wget ", "|bash"]),
+    RiskRule::medium("runs with elevated privileges", &["sudo "]),
+    RiskRule::medium("recursive permission change", &["chmod -r"]),
+    RiskRule::medium("recursive ownership change", &["chown -r"]),
+    RiskRule::medium("force push rewrites remote history", &["git push", "--force"]),
+    RiskRule::medium("force push rewrites remote history", &["git push", " -f"]),
+    RiskRule::medium("discards uncommitted changes", &["git reset --hard"]),
+    RiskRule::medium("deletes untracked files", &["git clean -"]),
+    RiskRule::medium("shuts down or restarts the system", &["shutdown"]),
+    RiskRule::medium("shuts down or restarts the system", &["reboot"]),
+    RiskRule::medium("kills processes without cleanup", &["kill -9"]),
+    RiskRule::medium("kills processes without cleanup", &["killall"]),
+];
+
+/// Normalize a command for pattern matching.
+///
+/// Lowercases, collapses whitespace, and removes spaces around pipes and
+/// redirections so `curl x | sh` and `curl x|sh` match the same needle.
+pub fn normalize(command: &str) -> String {
+    let collapsed = command
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    collapsed
+        .replace(" |", "|")
+        .replace("| ", "|")
+        .replace(" >", ">")
+        .replace("> ", ">")
+}
+
+/// Return all rules that match the command.
+pub fn matching_rules(command: &str) -> Vec<&'static RiskRule> {
+    let normalized = normalize(command);
+    RISK_RULES.iter().filter(|r| r.matches(&normalized)).collect()
+}
+
+/// Classify how risky a command is to execute.
+pub fn classify_risk(command: &str) -> RiskLevel {
+    matching_rules(command)
+        .iter()
+        .map(|r| r.level)
+        .max()
+        .unwrap_or(RiskLevel::Low)
+}
+
+/// Deduplicated reasons for the rules matching at the given level.
+pub fn reasons_at(command: &str, level: RiskLevel) -> Vec<&'static str> {
+    let mut reasons: Vec<&'static str> = Vec::new();
+    for rule in matching_rules(command) {
+        if rule.level == level && !reasons.contains(&rule.reason) {
+            reasons.push(rule.reason);
+        }
+    }
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_risky_commands() {
+        let cases = [
+            ("rm -rf /tmp/build", RiskLevel::High),
+            ("sudo rm -fr ~/old", RiskLevel::High),
+            ("mkfs.ext4 /dev/sdb1", RiskLevel::High),
+            ("dd if=disk.img of=/dev/sdb bs=4M", RiskLevel::High),
+            ("dd if=/dev/zero of=/dev/nvme0n1", RiskLevel::High),
+            ("cat disk.img > /dev/sda", RiskLevel::High),
+            (":(){ :|:& };:", RiskLevel::High),
+            ("curl -fsSL https://example.com/install.sh | sh", RiskLevel::High),
+            ("wget -qO- https://example.com/install.sh|bash", RiskLevel::High),
+            ("sudo apt update", RiskLevel::Medium),
+            ("chmod -R 777 .", RiskLevel::Medium),
+            ("git push --force origin main", RiskLevel::Medium),
+            ("git reset --hard HEAD~1", RiskLevel::Medium),
+            ("kill -9 1234", RiskLevel::Medium),
+        ];
+        for (command, level) in cases {
+            assert_eq!(classify_risk(command), level, "{}", command);
+        }
+    }
+
+    #[test]
+    fn classifies_safe_commands() {
+        let cases = [
+            "ls -la",
+            "find . -name '*.log' -mtime +7",
+            "rm build.log",
+            "echo done > /dev/null",
+            "dd if=/dev/zero of=/dev/null bs=1M count=100",
+            "dd if=/dev/sda of=/dev/zero",
+            "dd if=/dev/urandom of=/dev/stdout bs=16 count=1",
+            "curl -o page.html https://example.com",
+            "git push origin main",
+        ];
+        for command in cases {
+            assert_eq!(classify_risk(command), RiskLevel::Low, "{}", command);
+        }
+    }
+
+    #[test]
+    fn reasons_are_deduplicated() {
+        assert_eq!(
+            reasons_at("rm -rf a && rm -fr b", RiskLevel::High),
+            vec!["recursive forced delete"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! First-run setup wizard.
+//!
+//! `shell-ai setup` walks through choosing a provider, entering its
+//! credentials, and picking a model, then writes a minimal config.toml.
+//! It also runs on its own the first time suggest or explain is used on a
+//! terminal with no configuration at all.
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use is_terminal::IsTerminal;
+
+use crate::config::{self, AppConfig, Frontend, ProviderMeta, PROVIDER_METADATA};
+use crate::output::outln;
+use crate::ui::{InteractiveSelect, TextInput};
+
+/// Keys for the provider menu, in `PROVIDER_METADATA` order.
+///
+/// `j` and `k` are left out because they move the highlight.
+const PROVIDER_KEYS: &str = "1234567890abcdefghilm";
+
+/// Whether setup can prompt: both stdin and stderr must be terminals.
+pub fn can_prompt() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Whether to offer setup instead of failing with "No provider configured".
+///
+/// Only when no provider is set anywhere, no config file was loaded, and
+/// the frontend may prompt.
+pub fn should_offer(config: &AppConfig) -> bool {
+    config.provider.value.is_none()
+        && config.toml_path.is_none()
+        && config.project_path.is_none()
+        && config.json_path.is_none()
+        && config.frontend.value != Frontend::Noninteractive
+        && can_prompt()
+}
+
+/// Run the setup wizard.
+///
+/// Returns `true` if a config file was written, or `false` if the user cancelled.
+pub fn run() -> Result<bool> {
+    if !can_prompt() {
+        anyhow::bail!(
+            "Setup needs an interactive terminal.\n\
+             Hint: Run `shell-ai config init` to write a documented example config instead."
+        );
+    }
+
+    let path = config::toml_config_path()
+        .ok_or_else(|| anyhow!("Could not determine config directory"))?;
+
+    if path.exists() {
+        eprintln!("{} {}", "Config file already exists:".yellow(), path.display());
+        let overwrite = InteractiveSelect::new("Replace it?")
+            .option('y', "Yes, replace it")
+            .option('n', "No, keep it")
+            .run()?;
+        if overwrite != Some('y') {
+            return cancelled();
+        }
+    }
+
+    let providers: Vec<&ProviderMeta> = PROVIDER_METADATA
+        .iter()
+        .filter(|p| p.name != "mock")
+        .collect();
+    let mut select = InteractiveSelect::new("Choose a provider:");
+    for (meta, key) in providers.iter().zip(PROVIDER_KEYS.chars()) {
+        select = select.option(key, format!("{} - {}", meta.display_name, meta.description));
+    }
+    let Some(key) = select.run()? else {
+        return cancelled();
+    };
+    let Some(meta) = PROVIDER_KEYS.find(key).and_then(|i| providers.get(i)) else {
+        return cancelled();
+    };
+    eprintln!("{} {}", "Provider:".cyan(), meta.display_name);
+
+    let mut section = toml::Table::new();
+    for field in meta.all_fields() {
+        // The API key is always asked for; other fields only when they must be set
+        let ask = field.name == "api_key" || (field.required && field.default.is_none());
+        if !ask {
+            continue;
+        }
+
+        let env_value = field.env_var.filter(|var| std::env::var(var).is_ok_and(|v| !v.is_empty()));
+        let optional = !field.required || env_value.is_some();
+        let hint = match env_value {
+            Some(var) => format!(" (leave empty to use ${})", var),
+            None if optional => " (optional)".to_string(),
+            None => String::new(),
+        };
+        eprintln!("{}", field.description.dimmed());
+
+        loop {
+            let Some(value) = TextInput::new(format!("{}{}:", field.name, hint))
+                .with_vim_mode(false)
+                .with_masked(field.sensitive)
+                .run()?
+            else {
+                return cancelled();
+            };
+            let value = value.trim();
+            if !value.is_empty() {
+                section.insert(field.name.to_string(), value.into());
+                break;
+            }
+            if optional {
+                break;
+            }
+            eprintln!("{}", format!("{} is required.", field.name).yellow());
+        }
+    }
+
+    if let Some(field) = meta.resolved_field("model") {
+        eprintln!("{}", "Model to use (edit or press Enter to accept the default)".dimmed());
+        let Some(model) = TextInput::new("model:")
+            .with_vim_mode(false)
+            .with_initial_value(field.default.unwrap_or_default())
+            .run()?
+        else {
+            return cancelled();
+        };
+        let model = model.trim();
+        if !model.is_empty() && Some(model) != field.default {
+            section.insert("model".to_string(), model.into());
+        }
+    }
+
+    let mut table = toml::Table::new();
+    table.insert("provider".to_string(), meta.name.into());
+    table.insert(meta.name.to_string(), section.into());
+    let content = format!(
+        "# Written by `shell-ai setup`. Run `shell-ai config schema` to see every setting.\n\n{}",
+        toml::to_string(&table)?
+    );
+
+    AppConfig::create_config_file(&path, &content)?;
+    outln!("Created config file at: {}", path.display());
+    Ok(true)
+}
+
+fn cancelled() -> Result<bool> {
+    eprintln!("Setup cancelled; no config file was written.");
+    Ok(false)
+}
@@ -0,0 +1,132 @@
+//! Shell detection and shell-specific prompt hints.
+//!
+//! [`ShellType`] is shared by config, suggest, shell history, and the shell
+//! integration generator, so it lives here rather than in any of them.
+
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+/// Supported shell types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Display, EnumIter, Deserialize, Serialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ShellType {
+    Bash,
+    Zsh,
+    Fish,
+    #[clap(name = "powershell")]
+    #[strum(serialize = "powershell")]
+    #[serde(alias = "pwsh")]
+    PowerShell,
+    #[serde(alias = "nushell")]
+    Nu,
+    Elvish,
+    Xonsh,
+}
+
+impl ShellType {
+    /// Detect the user's shell from the environment.
+    ///
+    /// Checks `$FISH_VERSION`, `$NU_VERSION`, and `$XONSH_VERSION` (set inside
+    /// fish, Nushell, and xonsh), then the name of `$SHELL`.
+    /// `$SHELL` is the login shell, which may differ from the shell actually
+    /// in use, so an explicit `--shell` takes precedence over this.
+    pub fn detect() -> Option<ShellType> {
+        Self::detect_from(|name| std::env::var_os(name))
+    }
+
+    /// Detect the shell like [`detect`](Self::detect), reading environment
+    /// variables through `var`.
+    pub fn detect_from(var: impl Fn(&str) -> Option<OsString>) -> Option<ShellType> {
+        if var("FISH_VERSION").is_some() {
+            return Some(ShellType::Fish);
+        }
+        if var("NU_VERSION").is_some() {
+            return Some(ShellType::Nu);
+        }
+        if var("XONSH_VERSION").is_some() {
+            return Some(ShellType::Xonsh);
+        }
+        let shell = var("SHELL")?;
+        let name = Path::new(&shell).file_stem()?.to_str()?;
+        <ShellType as FromStr>::from_str(name).ok()
+    }
+
+    /// System prompt sentence telling the model which shell's syntax to use.
+    pub fn prompt_hint(&self) -> &'static str {
+        match self {
+            ShellType::Bash => "The command will run in bash.",
+            ShellType::Zsh => "The command will run in zsh.",
+            ShellType::Fish => {
+                "The command will run in fish, so use fish syntax \
+                 (e.g., `set -gx VAR value` instead of `export VAR=value`, `(cmd)` instead of `$(cmd)`)."
+            }
+            ShellType::PowerShell => {
+                "The command will run in PowerShell, so use PowerShell syntax and cmdlets \
+                 (e.g., `$env:VAR = 'value'`, `Get-ChildItem`) rather than Unix tools."
+            }
+            ShellType::Nu => {
+                "The command will run in Nushell, so use Nushell syntax \
+                 (e.g., `$env.VAR = 'value'` instead of `export VAR=value`, `;` instead of `&&`, `(cmd)` instead of `$(cmd)`)."
+            }
+            ShellType::Elvish => {
+                "The command will run in Elvish, so use Elvish syntax \
+                 (e.g., `set E:VAR = value` instead of `export VAR=value`, `(cmd)` instead of `$(cmd)`, \
+                 `and`/`or` or separate commands instead of `&&`/`||`)."
+            }
+            ShellType::Xonsh => {
+                "The command will run in xonsh, so use xonsh syntax \
+                 (e.g., `$VAR = 'value'` instead of `export VAR=value`, `@(expr)` to pass Python values to commands)."
+            }
+        }
+    }
+}
+
+impl FromStr for ShellType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(ShellType::Bash),
+            "zsh" => Ok(ShellType::Zsh),
+            "fish" => Ok(ShellType::Fish),
+            "powershell" | "pwsh" => Ok(ShellType::PowerShell),
+            "nu" | "nushell" => Ok(ShellType::Nu),
+            "elvish" => Ok(ShellType::Elvish),
+            "xonsh" => Ok(ShellType::Xonsh),
+            _ => Err(format!("Unknown shell: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_with(vars: &[(&str, &str)]) -> Option<ShellType> {
+        ShellType::detect_from(|name| {
+            vars.iter().find(|(k, _)| *k == name).map(|(_, v)| OsString::from(v))
+        })
+    }
+
+    #[test]
+    fn detects_the_shell_from_the_environment() {
+        assert_eq!(detect_with(&[("SHELL", "/bin/bash")]), Some(ShellType::Bash));
+        assert_eq!(detect_with(&[("SHELL", "/usr/local/bin/zsh")]), Some(ShellType::Zsh));
+        assert_eq!(detect_with(&[("SHELL", "/usr/bin/pwsh")]), Some(ShellType::PowerShell));
+        assert_eq!(detect_with(&[("SHELL", "/bin/sh")]), None);
+        assert_eq!(detect_with(&[]), None);
+    }
+
+    #[test]
+    fn shell_version_variables_override_the_login_shell() {
+        assert_eq!(detect_with(&[("SHELL", "/bin/bash"), ("FISH_VERSION", "3.7.0")]), Some(ShellType::Fish));
+        assert_eq!(detect_with(&[("SHELL", "/bin/zsh"), ("NU_VERSION", "0.95.0")]), Some(ShellType::Nu));
+        assert_eq!(detect_with(&[("SHELL", "/bin/bash"), ("XONSH_VERSION", "0.18.0")]), Some(ShellType::Xonsh));
+    }
+}
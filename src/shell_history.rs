@@ -0,0 +1,88 @@
+//! Reading the user's own shell history, for `--history-context`.
+//!
+//! Each shell keeps history in its own file and format; this finds the file
+//! for a [`ShellType`] and returns the most recent commands as plain text.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::shell::ShellType;
+
+/// The history file a shell writes by default, honoring `$HISTFILE` where the shell does.
+fn history_file(shell: ShellType) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    let data_dir = || {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".local").join("share"))
+    };
+    let histfile = || std::env::var_os("HISTFILE").filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    match shell {
+        ShellType::Bash => Some(histfile().unwrap_or_else(|| home.join(".bash_history"))),
+        ShellType::Zsh => Some(histfile().unwrap_or_else(|| {
+            let dir = std::env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or_else(|| home.clone());
+            dir.join(".zsh_history")
+        })),
+        ShellType::Fish => Some(data_dir().join("fish").join("fish_history")),
+        ShellType::PowerShell if cfg!(windows) => Some(
+            dirs::data_dir()?
+                .join("Microsoft")
+                .join("Windows")
+                .join("PowerShell")
+                .join("PSReadLine")
+                .join("ConsoleHost_history.txt"),
+        ),
+        ShellType::PowerShell => Some(data_dir().join("powershell").join("PSReadLine").join("ConsoleHost_history.txt")),
+        ShellType::Nu => Some(dirs::config_dir()?.join("nushell").join("history.txt")),
+        // Both keep history in databases (BoltDB, and JSON files or SQLite) rather than a plain file
+        ShellType::Elvish | ShellType::Xonsh => None,
+    }
+}
+
+/// The commands in a history file's contents, oldest first.
+fn parse_history(shell: ShellType, content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| match shell {
+            // Extended history: ": <start>:<elapsed>;<command>"
+            ShellType::Zsh => Some(match line.strip_prefix(": ").and_then(|l| l.split_once(';')) {
+                Some((_, command)) => command,
+                None => line,
+            }),
+            // YAML-ish entries: "- cmd: <command>", followed by "  when: ..." lines
+            ShellType::Fish => line.strip_prefix("- cmd: "),
+            // Bash timestamps (HISTTIMEFORMAT) are stored as "#<seconds>" lines
+            ShellType::Bash if line.starts_with('#') && line[1..].chars().all(|c| c.is_ascii_digit()) => None,
+            _ => Some(line),
+        })
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The last `count` commands from the shell's history, oldest first, keeping
+/// only as many of the newest as fit in `max_chars`.
+pub fn recent_commands(shell: ShellType, count: usize, max_chars: usize) -> Result<Vec<String>> {
+    if matches!(shell, ShellType::Elvish | ShellType::Xonsh) {
+        return Err(anyhow!("Reading {} history isn't supported, since it's kept in a database", shell));
+    }
+    let path = history_file(shell).ok_or_else(|| anyhow!("Could not determine the {} history file", shell))?;
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read shell history from {}", path.display()))?;
+    let commands = parse_history(shell, &String::from_utf8_lossy(&bytes));
+
+    let mut recent = Vec::new();
+    let mut used = 0;
+    for command in commands.into_iter().rev().take(count) {
+        used += command.chars().count() + 1;
+        if used > max_chars {
+            break;
+        }
+        recent.push(command);
+    }
+    recent.reverse();
+    Ok(recent)
+}
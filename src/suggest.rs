@@ -1,35 +1,101 @@
 use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
 use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
+use crossterm::event::KeyCode;
 use futures::{stream, StreamExt};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::config::{resolve_locale, AppConfig, Frontend, OutputFormat, ValidatedConfig};
+use crate::clipboard;
+use crate::config::{resolve_locale, AppConfig, ConfigSource, CtxCapture, Frontend, MenuAction, OutputFormat, Provider, ValidatedConfig};
 use crate::explain;
+use crate::history::{self, Outcome};
 use crate::http;
+use crate::logger;
+use crate::output::outln;
 use crate::progress::Progress;
 use crate::provider::ProviderConfig;
-use crate::ui::{self, InteractiveSelect, TextInput};
+use crate::risk::{self, RiskLevel};
+use crate::shell::ShellType;
+use crate::shell_history;
+use crate::ui::{self, InteractiveSelect, Selection, TextInput};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 struct Suggestion {
     command: String,
 }
 
+/// A single step of a multi-command plan.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct PlanStep {
+    command: String,
+    description: String,
+}
+
+/// An ordered sequence of commands generated in `--plan` mode.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct Plan {
+    steps: Vec<PlanStep>,
+}
+
+/// Shell that accepted commands run in, set once at startup
+static EXEC_SHELL: OnceLock<Option<ShellType>> = OnceLock::new();
+
+/// Upper bound on shell history sent as context, in characters
+const HISTORY_CONTEXT_MAX_CHARS: usize = 4000;
+
+/// Whether commands may be run at all (`allow_execute`)
+static ALLOW_EXECUTE: AtomicBool = AtomicBool::new(true);
+
+/// Upper bound on suggestions per round, each of which is a separate request
+const MAX_SUGGESTION_COUNT: u32 = 10;
+
 // Command selection options (dialog mode)
 const SYSTEM_OPTION_GEN: &str = "Generate new suggestions";
 const SYSTEM_OPTION_NEW: &str = "Enter a new command";
 const SYSTEM_OPTION_DISMISS: &str = "Dismiss";
 
+/// Key that copies the highlighted suggestion and quits, skipping the action menu
+const COPY_AND_QUIT_KEY: KeyCode = KeyCode::Tab;
+
 // Action menu options (after selecting a command)
 const ACTION_COPY: &str = "Copy to clipboard";
 const ACTION_EXPLAIN: &str = "Explain command";
 const ACTION_EXECUTE: &str = "Execute command";
 const ACTION_REVISE: &str = "Revise command";
+const ACTION_REFINE: &str = "Refine with AI";
 const ACTION_EXIT: &str = "Exit";
 
+/// The actions to offer after picking a suggestion: `menu_actions`, without
+/// Execute when execution isn't allowed.
+fn menu_actions(config: &AppConfig) -> Result<Vec<MenuAction>> {
+    Ok(config
+        .menu_actions()?
+        .into_iter()
+        .filter(|a| *a != MenuAction::Execute || execution_allowed())
+        .collect())
+}
+
+/// Shortcut key and label of an action menu entry.
+fn action_entry(action: MenuAction) -> (char, &'static str) {
+    match action {
+        MenuAction::Copy => ('c', ACTION_COPY),
+        MenuAction::Explain => ('e', ACTION_EXPLAIN),
+        MenuAction::Execute => ('x', ACTION_EXECUTE),
+        MenuAction::Revise => ('r', ACTION_REVISE),
+        MenuAction::Refine => ('a', ACTION_REFINE),
+    }
+}
+
+// Plan menu options
+const PLAN_EXECUTE_ALL: &str = "Execute all";
+const PLAN_COPY_ALL: &str = "Copy all to clipboard";
+const PLAN_REGENERATE: &str = "Generate a new plan";
+
 /// JSON Schema for the `suggest` structured output.
 const SUGGEST_SCHEMA: &str = r#"{
   "type": "object",
@@ -43,35 +109,88 @@ const SUGGEST_SCHEMA: &str = r#"{
   "additionalProperties": false
 }"#;
 
+/// JSON Schema for the `suggest --plan` structured output.
+const PLAN_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "steps": {
+      "type": "array",
+      "description": "Shell commands to run in order to accomplish the task.",
+      "items": {
+        "type": "object",
+        "properties": {
+          "command": {
+            "type": "string",
+            "description": "A single-line shell command that can be executed directly."
+          },
+          "description": {
+            "type": "string",
+            "description": "A short description of what this step does."
+          }
+        },
+        "required": ["command", "description"],
+        "additionalProperties": false
+      }
+    }
+  },
+  "required": ["steps"],
+  "additionalProperties": false
+}"#;
+
 #[derive(Debug)]
 pub struct SuggestOptions {
     pub ctx: bool,
+    pub explain_first: bool,
+    pub plan: bool,
+    /// Number of recent shell history entries to send as context
+    pub history_context: Option<usize>,
+    pub copy: bool,
+    /// Reuse the most recent prompt instead of `prompt`
+    pub last: bool,
     pub prompt: Vec<String>,
 }
 
-/// Resolve `Frontend::Automatic` to a concrete frontend based on runtime context.
+/// Resolve the configured frontend to a concrete frontend based on runtime context.
 ///
 /// Resolution rules:
-/// - JSON output → Noninteractive (JSON requires structured output)
-/// - TTY + Human output → Dialog (interactive menu)
-/// - Non-TTY + Human output → Noninteractive (print first suggestion)
+/// - Automatic + JSON/YAML output → Noninteractive (structured output)
+/// - Automatic + Human output → Dialog (interactive menu)
+/// - Set with `--frontend` or `SHAI_FRONTEND` → as set, terminal or not
+/// - Otherwise, Dialog or Readline without a terminal on both stdin and
+///   stdout → Noninteractive, so pipelines get plain output
 fn resolve_frontend(config: &AppConfig) -> Frontend {
-    match config.frontend.value {
-        Frontend::Automatic => {
-            if config.output_format.value == OutputFormat::Json {
-                Frontend::Noninteractive
-            } else if std::io::stdout().is_terminal() {
-                Frontend::Dialog
-            } else {
-                Frontend::Noninteractive
-            }
+    let explicit = matches!(config.frontend.source, ConfigSource::Cli | ConfigSource::Environment);
+    let frontend = match config.frontend.value {
+        Frontend::Automatic if config.output_format.value.is_structured() => Frontend::Noninteractive,
+        Frontend::Automatic => Frontend::Dialog,
+        other => other,
+    };
+
+    let stdin_tty = std::io::stdin().is_terminal();
+    let stdout_tty = std::io::stdout().is_terminal();
+    match frontend {
+        Frontend::Dialog | Frontend::Readline if !explicit && (!stdin_tty || !stdout_tty) => {
+            log::debug!(
+                "Using the noninteractive frontend instead of {} because {} isn't a terminal",
+                frontend,
+                if stdout_tty { "stdin" } else { "stdout" }
+            );
+            Frontend::Noninteractive
         }
         other => other,
     }
 }
 
 pub async fn run_suggest(validated: &ValidatedConfig<'_>, opts: SuggestOptions) -> Result<()> {
-    let prompt = opts.prompt.join(" ");
+
+    let prompt = if opts.last {
+        history::load_last()
+            .and_then(|last| last.prompt)
+            .filter(|p| !p.trim().is_empty())
+            .ok_or_else(|| anyhow!("No previous prompt to reuse.\nHint: Run `shai <sentence>` first; --last replays it."))?
+    } else {
+        opts.prompt.join(" ")
+    };
     if prompt.trim().is_empty() {
         println!("Describe what you want to do as a single sentence. `shai <sentence>`");
         return Ok(());
@@ -80,14 +199,20 @@ pub async fn run_suggest(validated: &ValidatedConfig<'_>, opts: SuggestOptions)
     // Context mode flag (CLI or env var)
     let ctx_enabled = opts.ctx || matches!(std::env::var("CTX"), Ok(v) if v.to_lowercase() == "true");
 
+    // Skipped in context mode for the same reason as history recording
+    if !ctx_enabled {
+        history::save_last_prompt(&prompt);
+    }
+
     // Resolve automatic frontend to concrete frontend based on context
     let config = validated.app_config();
     let resolved_frontend = resolve_frontend(config);
 
     log::debug!(
-        "Frontend resolution: {:?} -> {:?} (stdout_tty={}, output_format={:?})",
+        "Frontend resolution: {:?} -> {:?} (stdin_tty={}, stdout_tty={}, output_format={:?})",
         config.frontend.value,
         resolved_frontend,
+        std::io::stdin().is_terminal(),
         std::io::stdout().is_terminal(),
         config.output_format.value
     );
@@ -96,24 +221,96 @@ pub async fn run_suggest(validated: &ValidatedConfig<'_>, opts: SuggestOptions)
     if resolved_frontend == Frontend::Noninteractive && ctx_enabled {
         return Err(anyhow!(
             "Context mode (--ctx) requires an interactive frontend.\n\
-             The frontend resolved to noninteractive because stdout is not a TTY or JSON output was requested.\n\
+             The frontend resolved to noninteractive because stdin or stdout is not a TTY or JSON output was requested.\n\
              Hint: Run in a terminal with human output format to use context mode."
         ));
     }
 
+    if ctx_enabled && !execution_allowed() {
+        return Err(anyhow!(
+            "Context mode (--ctx) runs commands, but executing commands is disabled (allow_execute = false)."
+        ));
+    }
+
+    let mut settings = PromptSettings::from_validated(validated)?;
+    if let Some(count) = opts.history_context.filter(|&n| n > 0) {
+        settings.shell_history = load_shell_history(validated, count)?;
+    }
+
+    if opts.plan {
+        if ctx_enabled {
+            return Err(anyhow!("Plan mode (--plan) cannot be combined with context mode (--ctx)."));
+        }
+        return plan_frontend(validated, &settings, &prompt, resolved_frontend).await;
+    }
+
     // Dispatch to appropriate frontend
     match resolved_frontend {
         Frontend::Automatic => unreachable!("Automatic should be resolved"),
-        Frontend::Dialog => dialog_frontend(validated, &prompt, ctx_enabled).await,
-        Frontend::Readline => readline_frontend(validated, &prompt, ctx_enabled).await,
-        Frontend::Noninteractive => noninteractive_frontend(validated, &prompt).await,
+        Frontend::Dialog => dialog_frontend(validated, &settings, &prompt, ctx_enabled).await,
+        Frontend::Readline => readline_frontend(validated, &settings, &prompt, ctx_enabled).await,
+        Frontend::Noninteractive => {
+            let copy = opts.copy || config.auto_copy.value;
+            noninteractive_frontend(validated, &settings, &prompt, opts.explain_first, copy).await
+        }
+    }
+}
+
+/// Read the last `count` shell history entries for the suggestion prompts.
+///
+/// The budget is about four characters per token of `max_tokens` (when set),
+/// capped at [`HISTORY_CONTEXT_MAX_CHARS`]. Returns `None` if the history is empty.
+fn load_shell_history(validated: &ValidatedConfig<'_>, count: usize) -> Result<Option<String>> {
+    let config = validated.app_config();
+    let shell = config.shell.value.or_else(ShellType::detect).ok_or_else(|| {
+        anyhow!(
+            "Could not detect your shell to read its history.\n\
+             Hint: Set it with --shell or SHAI_SHELL."
+        )
+    })?;
+    let max_chars = validated
+        .effective_max_tokens()
+        .map_or(HISTORY_CONTEXT_MAX_CHARS, |tokens| (tokens as usize * 4).min(HISTORY_CONTEXT_MAX_CHARS));
+
+    let commands = shell_history::recent_commands(shell, count, max_chars)?;
+    log::warn!(
+        "History context enabled: your last {} {} history entries will be sent to the AI provider. \
+         Shell history can contain secrets, such as tokens passed on the command line.",
+        commands.len(),
+        shell
+    );
+    Ok(Some(commands.join("\n")).filter(|h| !h.is_empty()))
+}
+
+/// Print the most recently accepted command, as the noninteractive frontend would.
+///
+/// This is `--repeat`, which doesn't call the model, so it needs no provider.
+pub fn repeat_last_command(output_format: OutputFormat) -> Result<()> {
+    let command = history::load_last().and_then(|last| last.command).ok_or_else(|| {
+        anyhow!("No previous command to repeat.\nHint: Accept a suggestion first (copy, execute, or noninteractive output).")
+    })?;
+
+    match output_format {
+        format @ (OutputFormat::Json | OutputFormat::Yaml) => {
+            format.print_serialized(&vec![Suggestion { command }])?;
+        }
+        OutputFormat::Human => outln!("{}", command),
     }
+    Ok(())
 }
 
 /// Dialog frontend using interactive menus with arrow keys and letter shortcuts.
-async fn dialog_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str, mut ctx_enabled: bool) -> Result<()> {
+async fn dialog_frontend(
+    validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
+    initial_prompt: &str,
+    mut ctx_enabled: bool,
+) -> Result<()> {
+    let actions = menu_actions(validated.app_config())?;
     let mut prompt = initial_prompt.to_string();
     let mut ctx_buffer = String::new();
+    // Prompts entered this session, recalled with Up in "New prompt"
+    let mut prompt_history = vec![prompt.clone()];
 
     if ctx_enabled {
         log::warn!(
@@ -126,7 +323,7 @@ async fn dialog_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str,
     'outer: loop {
         // Show progress while generating suggestions
         let progress = Progress::new("Generating suggestions...");
-        let suggestions = generate_suggestions(validated, &prompt, ctx_enabled, &ctx_buffer, None).await;
+        let suggestions = generate_suggestions(validated, settings, &prompt, ctx_enabled, &ctx_buffer, None).await;
         if let Some(ref p) = progress {
             p.finish_and_clear();
         }
@@ -138,22 +335,40 @@ async fn dialog_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str,
             let mut select = InteractiveSelect::new("Select a command:");
             for (i, s) in suggestions.iter().enumerate() {
                 let key = char::from_digit((i + 1) as u32, 10).unwrap_or('?');
-                select = select.option(key, &s.command);
+                select = select.command_option(key, &s.command);
             }
             select = select
                 .option('g', SYSTEM_OPTION_GEN)
                 .option('n', SYSTEM_OPTION_NEW)
                 .option('q', SYSTEM_OPTION_DISMISS);
+            if actions.contains(&MenuAction::Copy) {
+                select = select.action_key(COPY_AND_QUIT_KEY, "copy & quit");
+            }
+
+            let selection = select.run_selection().map_err(|e| anyhow!("Selection error: {}", e))?;
 
-            let selection = select.run().map_err(|e| anyhow!("Selection error: {}", e))?;
+            // The copy shortcut on a suggestion skips the action menu
+            if let Some(Selection { index, action: Some(COPY_AND_QUIT_KEY), .. }) = selection {
+                // Suggestions are the first options, so their positions line up
+                let Some(suggestion) = suggestions.get(index) else {
+                    // The highlight was on a system option, which can't be copied
+                    continue 'selection;
+                };
+                ui::copy_to_clipboard(&suggestion.command);
+                record_history(validated, &prompt, &suggestion.command, Outcome::Copied, ctx_enabled);
+                return Ok(());
+            }
 
-            match selection {
+            let index = selection.map(|s| s.index);
+            match selection.map(|s| s.key) {
                 Some('q') | None => return Ok(()),
                 Some('n') => {
                     if let Some(new_prompt) = TextInput::new("New prompt:")
+                        .with_history(prompt_history.clone())
                         .run()
                         .map_err(|e| anyhow!("Input error: {}", e))?
                     {
+                        remember_prompt(&mut prompt_history, &new_prompt);
                         prompt = new_prompt;
                         continue 'outer; // Regenerate with new prompt
                     }
@@ -161,66 +376,92 @@ async fn dialog_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str,
                     continue 'selection;
                 }
                 Some('g') => continue 'outer, // Regenerate
-                Some(c) => {
-                    // Numeric selection
-                    if let Some(idx) = c.to_digit(10) {
-                        let idx = idx as usize;
-                        if idx >= 1 && idx <= suggestions.len() {
-                            let mut selected_command = suggestions[idx - 1].command.clone();
-
-                            // Action menu loop
-                            loop {
-                                println!();
-                                println!("Selected: {}", selected_command.green());
-
-                                let mut action_select = InteractiveSelect::new("Action:")
-                                    .option('c', ACTION_COPY)
-                                    .option('e', ACTION_EXPLAIN)
-                                    .option('x', ACTION_EXECUTE)
-                                    .option('r', ACTION_REVISE)
-                                    .option('b', "Back to suggestions")
-                                    .option('q', ACTION_EXIT);
-
-                                let action = action_select.run().map_err(|e| anyhow!("Selection error: {}", e))?;
-
-                                match action {
-                                    Some('c') => {
-                                        ui::copy_to_clipboard(&selected_command);
-                                    }
-                                    Some('e') => {
-                                        if let Err(e) = explain::explain_command(&selected_command, validated).await {
-                                            log::error!("Failed to explain command: {}", e);
-                                        }
+                Some(_) => {
+                    // Suggestion selection, by position since the 10th key is '?'
+                    if let Some(suggestion) = index.and_then(|i| suggestions.get(i)) {
+                        let mut selected_command = suggestion.command.clone();
+
+                        // Action menu loop
+                        loop {
+                            println!();
+                            println!("Selected: {}", selected_command.green());
+
+                            let mut action_select = InteractiveSelect::new("Action:");
+                            for (key, label) in actions.iter().copied().map(action_entry) {
+                                action_select = action_select.option(key, label);
+                            }
+                            let mut action_select = action_select
+                                .option('b', "Back to suggestions")
+                                .option('q', ACTION_EXIT);
+
+                            let action = action_select.run().map_err(|e| anyhow!("Selection error: {}", e))?;
+
+                            match action {
+                                Some('c') => {
+                                    ui::copy_to_clipboard(&selected_command);
+                                    record_history(validated, &prompt, &selected_command, Outcome::Copied, ctx_enabled);
+                                }
+                                Some('e') => {
+                                    if let Err(e) = explain::explain_command(&selected_command, validated, true).await {
+                                        log::error!("Failed to explain command: {}", e);
                                     }
-                                    Some('x') => {
-                                        if !ctx_enabled {
-                                            run_command_default(&selected_command)?;
-                                            return Ok(());
-                                        } else {
-                                            handle_command_with_ctx(&selected_command, &mut ctx_buffer, &mut ctx_enabled)?;
-                                            println!(">>> {}", std::env::current_dir()?.display());
-                                            if let Some(new_prompt) = TextInput::new("New prompt:")
-                                                .run()
-                                                .map_err(|e| anyhow!("Input error: {}", e))?
-                                            {
-                                                prompt = new_prompt;
-                                            }
-                                            continue 'outer; // Regenerate after execute in ctx mode
-                                        }
+                                }
+                                Some('x') => {
+                                    let Some(command) = edit_before_execution(validated.app_config(), &selected_command)? else {
+                                        continue;
+                                    };
+                                    selected_command = command;
+                                    if !confirm_execution(&selected_command, Frontend::Dialog)? {
+                                        continue;
                                     }
-                                    Some('r') => {
-                                        if let Some(revised) = TextInput::new("Revise command:")
-                                            .with_initial_value(&selected_command)
+                                    if !ctx_enabled {
+                                        record_history(validated, &prompt, &selected_command, Outcome::Executed, ctx_enabled);
+                                        run_command_default(&selected_command)?;
+                                        return Ok(());
+                                    } else {
+                                        handle_command_with_ctx(validated.app_config(), &selected_command, &mut ctx_buffer, &mut ctx_enabled)?;
+                                        println!(">>> {}", std::env::current_dir()?.display());
+                                        if let Some(new_prompt) = TextInput::new("New prompt:")
+                                            .with_history(prompt_history.clone())
                                             .run()
                                             .map_err(|e| anyhow!("Input error: {}", e))?
                                         {
-                                            selected_command = revised;
+                                            remember_prompt(&mut prompt_history, &new_prompt);
+                                            prompt = new_prompt;
+                                        }
+                                        continue 'outer; // Regenerate after execute in ctx mode
+                                    }
+                                }
+                                Some('r') => {
+                                    if let Some(revised) = TextInput::new("Revise command:")
+                                        .with_initial_value(&selected_command)
+                                        .run()
+                                        .map_err(|e| anyhow!("Input error: {}", e))?
+                                    {
+                                        selected_command = revised;
+                                    }
+                                }
+                                Some('a') => {
+                                    let Some(instruction) = TextInput::new("Refine with AI (e.g. \"make it recursive\"):")
+                                        .run()
+                                        .map_err(|e| anyhow!("Input error: {}", e))?
+                                    else {
+                                        continue;
+                                    };
+                                    if instruction.trim().is_empty() {
+                                        continue;
+                                    }
+                                    match refine_command(validated, settings, &prompt, &selected_command, &instruction, ctx_enabled, &ctx_buffer).await {
+                                        Ok(refined) => {
+                                            print_refinement(&selected_command, &refined);
+                                            selected_command = refined;
                                         }
+                                        Err(e) => log::error!("Failed to refine command: {}", e),
                                     }
-                                    Some('b') => continue 'selection, // Back to selection menu
-                                    Some('q') | None => return Ok(()),
-                                    _ => {}
                                 }
+                                Some('b') => continue 'selection, // Back to selection menu
+                                Some('q') | None => return Ok(()),
+                                _ => {}
                             }
                         }
                     }
@@ -230,8 +471,21 @@ async fn dialog_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str,
     }
 }
 
+/// Add a prompt to the session history, skipping blanks and immediate repeats.
+fn remember_prompt(history: &mut Vec<String>, prompt: &str) {
+    if !prompt.trim().is_empty() && history.last().map(String::as_str) != Some(prompt) {
+        history.push(prompt.to_string());
+    }
+}
+
 /// Readline frontend using numbered selection and simple line input.
-async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str, mut ctx_enabled: bool) -> Result<()> {
+async fn readline_frontend(
+    validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
+    initial_prompt: &str,
+    mut ctx_enabled: bool,
+) -> Result<()> {
+    let actions = menu_actions(validated.app_config())?;
     let mut prompt = initial_prompt.to_string();
     let mut ctx_buffer = String::new();
 
@@ -248,7 +502,7 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
     'outer: loop {
         // Show progress while generating suggestions
         let progress = Progress::new("Generating suggestions...");
-        let suggestions = generate_suggestions(validated, &prompt, ctx_enabled, &ctx_buffer, None).await;
+        let suggestions = generate_suggestions(validated, settings, &prompt, ctx_enabled, &ctx_buffer, None).await;
         if let Some(ref p) = progress {
             p.finish_and_clear();
         }
@@ -259,12 +513,12 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
             // Print numbered list
             println!();
             for (i, s) in suggestions.iter().enumerate() {
-                println!("  {}. {}", (i + 1).to_string().cyan(), s.command);
+                println!("  {}. {}", (i + 1).to_string().cyan(), ui::highlight_command(&s.command));
             }
             println!();
-            println!("  {}. {}", "g".cyan(), "Generate new suggestions");
-            println!("  {}. {}", "n".cyan(), "Enter new prompt");
-            println!("  {}. {}", "q".cyan(), "Quit");
+            println!("  {}. Generate new suggestions", "g".cyan());
+            println!("  {}. Enter new prompt", "n".cyan());
+            println!("  {}. Quit", "q".cyan());
             println!();
 
             print!("Select [1-{}/g/n/q]: ", suggestions.len());
@@ -297,36 +551,46 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
                         println!();
                         println!("Selected: {}", selected_command.green());
                         println!();
-                        println!("  {}. {}", "c".cyan(), "Copy to clipboard");
-                        println!("  {}. {}", "e".cyan(), "Explain command");
-                        println!("  {}. {}", "x".cyan(), "Execute command");
-                        println!("  {}. {}", "r".cyan(), "Revise command");
-                        println!("  {}. {}", "b".cyan(), "Back to selection");
-                        println!("  {}. {}", "q".cyan(), "Quit");
+                        for (key, label) in actions.iter().copied().map(action_entry) {
+                            println!("  {}. {}", key.to_string().cyan(), label);
+                        }
+                        println!("  {}. Back to selection", "b".cyan());
+                        println!("  {}. Quit", "q".cyan());
                         println!();
 
-                        print!("Action [c/e/x/r/b/q]: ");
+                        let keys: String = actions.iter().map(|a| format!("{}/", action_entry(*a).0)).collect();
+                        print!("Action [{}b/q]: ", keys);
                         io::stdout().flush()?;
 
                         let mut action_input = String::new();
                         stdin.lock().read_line(&mut action_input)?;
                         let action = action_input.trim().to_lowercase();
+                        let offered = |action: MenuAction| actions.contains(&action);
 
                         match action.as_str() {
-                            "c" => {
+                            "c" if offered(MenuAction::Copy) => {
                                 ui::copy_to_clipboard(&selected_command);
+                                record_history(validated, &prompt, &selected_command, Outcome::Copied, ctx_enabled);
                             }
-                            "e" => {
-                                if let Err(e) = explain::explain_command(&selected_command, validated).await {
+                            "e" if offered(MenuAction::Explain) => {
+                                if let Err(e) = explain::explain_command(&selected_command, validated, true).await {
                                     log::error!("Failed to explain command: {}", e);
                                 }
                             }
-                            "x" => {
+                            "x" if offered(MenuAction::Execute) => {
+                                let Some(command) = edit_before_execution(validated.app_config(), &selected_command)? else {
+                                    continue;
+                                };
+                                selected_command = command;
+                                if !confirm_execution(&selected_command, Frontend::Readline)? {
+                                    continue;
+                                }
                                 if !ctx_enabled {
+                                    record_history(validated, &prompt, &selected_command, Outcome::Executed, ctx_enabled);
                                     run_command_default(&selected_command)?;
                                     return Ok(());
                                 } else {
-                                    handle_command_with_ctx(&selected_command, &mut ctx_buffer, &mut ctx_enabled)?;
+                                    handle_command_with_ctx(validated.app_config(), &selected_command, &mut ctx_buffer, &mut ctx_enabled)?;
                                     print!(">>> {}\nNew prompt: ", std::env::current_dir()?.display());
                                     io::stdout().flush()?;
                                     let mut new_prompt = String::new();
@@ -335,7 +599,7 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
                                     continue 'outer; // Regenerate after execute in ctx mode
                                 }
                             }
-                            "r" => {
+                            "r" if offered(MenuAction::Revise) => {
                                 print!("Revise command: ");
                                 io::stdout().flush()?;
                                 let mut revised = String::new();
@@ -345,10 +609,27 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
                                     selected_command = revised.to_string();
                                 }
                             }
+                            "a" if offered(MenuAction::Refine) => {
+                                print!("Refine with AI (e.g. \"make it recursive\"): ");
+                                io::stdout().flush()?;
+                                let mut instruction = String::new();
+                                stdin.lock().read_line(&mut instruction)?;
+                                let instruction = instruction.trim();
+                                if instruction.is_empty() {
+                                    continue;
+                                }
+                                match refine_command(validated, settings, &prompt, &selected_command, instruction, ctx_enabled, &ctx_buffer).await {
+                                    Ok(refined) => {
+                                        print_refinement(&selected_command, &refined);
+                                        selected_command = refined;
+                                    }
+                                    Err(e) => log::error!("Failed to refine command: {}", e),
+                                }
+                            }
                             "b" => {
                                 continue 'selection; // Back to selection menu
                             }
-                            "q" | _ => {
+                            _ => {
                                 return Ok(());
                             }
                         }
@@ -362,110 +643,488 @@ async fn readline_frontend(validated: &ValidatedConfig<'_>, initial_prompt: &str
 }
 
 /// Noninteractive frontend: auto-select first suggestion and output.
-async fn noninteractive_frontend(validated: &ValidatedConfig<'_>, prompt: &str) -> Result<()> {
+///
+/// With `explain_first`, human output also prints the command and a one-line
+/// synopsis to stderr before the command is emitted on stdout. With `copy`,
+/// the first command is also copied to the clipboard.
+async fn noninteractive_frontend(
+    validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
+    prompt: &str,
+    explain_first: bool,
+    copy: bool,
+) -> Result<()> {
     let config = validated.app_config();
     // Optimization: Only generate 1 suggestion for human output since we only use the first.
-    // JSON output may want all suggestions for programmatic selection.
+    // Structured output may want all suggestions for programmatic selection.
     let count_override = match config.output_format.value {
         OutputFormat::Human => Some(1),
-        OutputFormat::Json => None,
+        OutputFormat::Json | OutputFormat::Yaml => None,
     };
     let progress = Progress::new("Generating suggestions...");
-    let suggestions = generate_suggestions(validated, prompt, false, "", count_override).await;
+    let suggestions = generate_suggestions(validated, settings, prompt, false, "", count_override).await;
     if let Some(ref p) = progress {
         p.finish_and_clear();
     }
     let suggestions = suggestions?;
 
     match config.output_format.value {
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(&suggestions)?);
+        format @ (OutputFormat::Json | OutputFormat::Yaml) => {
+            format.print_serialized(&suggestions)?;
         }
         OutputFormat::Human => {
             if let Some(first) = suggestions.first() {
-                println!("{}", first.command);
+                if explain_first {
+                    print_synopsis(validated, &first.command).await;
+                }
+                outln!("{}", first.command);
             }
         }
     }
 
+    if let Some(first) = suggestions.first() {
+        history::save_last_command(&first.command);
+        if copy {
+            copy_quietly(&first.command);
+        }
+    }
+
     Ok(())
 }
 
+/// Copy a command to the clipboard as a side effect, keeping stdout clean for pipelines.
+///
+/// The confirmation goes to stderr, and only when stdout is a terminal so scripts stay quiet.
+fn copy_quietly(command: &str) {
+    match clipboard::copy(command) {
+        Ok(backend) => {
+            if io::stdout().is_terminal() && !logger::is_quiet() {
+                eprintln!("Command copied to clipboard ({}).", backend);
+            }
+        }
+        Err(e) => log::warn!("Failed to copy to clipboard: {}", e),
+    }
+}
+
+/// Print a command and its one-line synopsis to stderr, keeping stdout clean.
+async fn print_synopsis(validated: &ValidatedConfig<'_>, command: &str) {
+    match explain::synopsis(command, validated).await {
+        Ok(synopsis) => {
+            eprintln!("{}", command.green());
+            eprintln!("  {}", synopsis.dimmed());
+        }
+        Err(e) => log::warn!("Failed to explain command: {}", e),
+    }
+}
+
+/// Plan frontend: generate a multi-step plan and offer to run it step by step.
+async fn plan_frontend(
+    validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
+    prompt: &str,
+    frontend: Frontend,
+) -> Result<()> {
+    let config = validated.app_config();
+
+    loop {
+        let progress = Progress::new("Generating plan...");
+        let plan = generate_plan(validated, settings, prompt).await;
+        if let Some(ref p) = progress {
+            p.finish_and_clear();
+        }
+        let plan = plan?;
+
+        if frontend == Frontend::Noninteractive {
+            match config.output_format.value {
+                format @ (OutputFormat::Json | OutputFormat::Yaml) => format.print_serialized(&plan)?,
+                OutputFormat::Human => {
+                    for step in &plan.steps {
+                        outln!("{}", step.command);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        loop {
+            print_plan(&plan);
+
+            let action = match frontend {
+                Frontend::Dialog => {
+                    let mut select = InteractiveSelect::new("Action:");
+                    if execution_allowed() {
+                        select = select.option('x', PLAN_EXECUTE_ALL);
+                    }
+                    select
+                        .option('c', PLAN_COPY_ALL)
+                        .option('g', PLAN_REGENERATE)
+                        .option('q', ACTION_EXIT)
+                        .run()
+                        .map_err(|e| anyhow!("Selection error: {}", e))?
+                }
+                _ => {
+                    if execution_allowed() {
+                        println!("  {}. {}", "x".cyan(), PLAN_EXECUTE_ALL);
+                    }
+                    println!("  {}. {}", "c".cyan(), PLAN_COPY_ALL);
+                    println!("  {}. {}", "g".cyan(), PLAN_REGENERATE);
+                    println!("  {}. Quit", "q".cyan());
+                    println!();
+                    if execution_allowed() {
+                        print!("Action [x/c/g/q]: ");
+                    } else {
+                        print!("Action [c/g/q]: ");
+                    }
+                    io::stdout().flush()?;
+                    let mut input = String::new();
+                    io::stdin().lock().read_line(&mut input)?;
+                    input.trim().to_lowercase().chars().next()
+                }
+            };
+
+            match action {
+                Some('x') if execution_allowed() => return execute_plan(validated, prompt, &plan, frontend),
+                Some('c') => {
+                    let script = plan.steps.iter().map(|s| s.command.as_str()).collect::<Vec<_>>().join("\n");
+                    ui::copy_to_clipboard(&script);
+                    record_history(validated, prompt, &script, Outcome::Copied, false);
+                }
+                Some('g') => break,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Print a plan as a numbered list of steps.
+fn print_plan(plan: &Plan) {
+    println!();
+    println!("{}", "Plan:".white().bold());
+    for (i, step) in plan.steps.iter().enumerate() {
+        println!("  {}. {}", (i + 1).to_string().cyan(), step.command.green());
+        println!("     {}", step.description.dimmed());
+    }
+    println!();
+}
+
+/// Run each step of a plan in order, stopping at the first declined or failed step.
+///
+/// Every step goes through the same danger confirmation as a single command.
+fn execute_plan(validated: &ValidatedConfig<'_>, prompt: &str, plan: &Plan, frontend: Frontend) -> Result<()> {
+    let total = plan.steps.len();
+    for (i, step) in plan.steps.iter().enumerate() {
+        println!();
+        println!("{} {}", format!("[{}/{}]", i + 1, total).cyan().bold(), step.description);
+        println!("$ {}", step.command.green());
+
+        // Same final edit and risk check as running a single suggestion
+        let command = match edit_before_execution(validated.app_config(), &step.command)? {
+            Some(command) if confirm_execution(&command, frontend)? => command,
+            _ => {
+                println!("Plan stopped at step {} of {}.", i + 1, total);
+                return Ok(());
+            }
+        };
+
+        record_history(validated, prompt, &command, Outcome::Executed, false);
+        run_command_default(&command)
+            .map_err(|e| anyhow!("Plan stopped at step {} of {}: {}", i + 1, total, e))?;
+    }
+    Ok(())
+}
+
+async fn generate_plan(validated: &ValidatedConfig<'_>, settings: &PromptSettings, prompt: &str) -> Result<Plan> {
+    let prov = ProviderConfig::from_validated(validated);
+
+    let plan = plan_once(&prov, prompt, settings).await;
+    prov.report_usage();
+    let mut plan = plan?;
+    plan.steps.retain(|s| !s.command.trim().is_empty());
+    if plan.steps.is_empty() {
+        return Err(anyhow!("No plan could be generated.\nReason: the model returned no steps"));
+    }
+    Ok(plan)
+}
+
 async fn generate_suggestions(
     validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
     prompt: &str,
     ctx_enabled: bool,
     ctx_buffer: &str,
     count_override: Option<usize>,
 ) -> Result<Vec<Suggestion>> {
     let config = validated.app_config();
-    let count = count_override.unwrap_or_else(|| config.suggestion_count.value.max(1) as usize);
-    let max_workers = 4usize;
+    let count = count_override
+        .unwrap_or_else(|| config.suggestion_count.value.clamp(1, MAX_SUGGESTION_COUNT) as usize);
+    // Never more workers than requests, and always at least one
+    let max_workers = (config.suggest_concurrency.value as usize).clamp(1, count);
 
     let prompt_string = prompt.to_string();
     let ctx_string = if ctx_enabled { ctx_buffer.to_string() } else { String::new() };
     let prov = ProviderConfig::from_validated(validated);
-    let locale = resolve_locale(config.locale.value.as_deref());
 
     let tasks = stream::iter(0..count).map(|_| {
         let p = prompt_string.clone();
         let c = ctx_string.clone();
         let prov = prov.clone();
-        let loc = locale.clone();
-        async move { suggest_once(&prov, &p, &c, loc.as_deref()).await }
+        let settings = settings.clone();
+        async move { suggest_once(&prov, &p, &c, &settings).await }
     });
 
     let mut results: Vec<Suggestion> = Vec::new();
-    let mut last_error: Option<String> = None;
+    let mut last_error: Option<anyhow::Error> = None;
+    let mut unparsable = 0usize;
 
     tasks
         .buffer_unordered(max_workers)
         .for_each(|res| {
             match res {
-                Ok(Some(s)) if !s.command.trim().is_empty() => {
-                    if !results.iter().any(|existing| existing.command == s.command) {
-                        results.push(s);
-                    }
-                }
+                Ok(Some(s)) if !s.command.trim().is_empty() => push_unique(&mut results, s),
                 Ok(Some(_)) => {} // Empty command, skip
                 Ok(None) => {}    // No suggestion, skip
                 Err(e) => {
                     log::debug!("Suggestion attempt failed: {}", e);
-                    last_error = Some(e.to_string());
+                    if e.is::<UnparsableResponse>() {
+                        unparsable += 1;
+                    }
+                    last_error = Some(e);
                 }
             }
             futures::future::ready(())
         })
         .await;
 
+    if unparsable == count {
+        if let Some(ref fallback) = config.fallback_model.value {
+            log::warn!(
+                "Could not parse the model's output ({} attempts); retrying once with fallback model {}",
+                count, fallback
+            );
+            match suggest_once(&prov.with_model(fallback), &prompt_string, &ctx_string, settings).await {
+                Ok(Some(s)) if !s.command.trim().is_empty() => results.push(s),
+                Ok(_) => {}
+                Err(e) => {
+                    log::debug!("Fallback attempt failed: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+    }
+    prov.report_usage();
+
     if results.is_empty() {
-        let reason = last_error.unwrap_or_else(|| "unknown error".to_string());
-        Err(anyhow!(
-            "No suggestions could be generated.\nReason: {}",
-            reason
-        ))
+        Err(NoSuggestionsError { reason: last_error }.into())
     } else {
         Ok(results)
     }
 }
 
+/// Every suggestion attempt failed or came back empty.
+///
+/// Keeps the last attempt's error so callers can tell a provider or network
+/// failure apart from the model simply not producing a command.
+#[derive(Debug)]
+pub struct NoSuggestionsError {
+    pub reason: Option<anyhow::Error>,
+}
+
+impl std::fmt::Display for NoSuggestionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "No suggestions could be generated.\nReason: {}", reason),
+            None => write!(f, "No suggestions could be generated.\nReason: unknown error"),
+        }
+    }
+}
+
+impl std::error::Error for NoSuggestionsError {}
+
+/// Normalize a command for duplicate detection: case-insensitive, with runs of
+/// whitespace collapsed and trailing semicolons dropped.
+fn dedup_key(command: &str) -> String {
+    command
+        .trim()
+        .trim_end_matches(|c: char| c == ';' || c.is_whitespace())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Add a suggestion unless a near-duplicate is already in `results`, so the
+/// first-seen variant is kept.
+fn push_unique(results: &mut Vec<Suggestion>, suggestion: Suggestion) {
+    let key = dedup_key(&suggestion.command);
+    if !results.iter().any(|existing| dedup_key(&existing.command) == key) {
+        results.push(suggestion);
+    }
+}
+
 async fn suggest_once(
     provider: &ProviderConfig,
     prompt: &str,
     ctx_buffer: &str,
-    locale: Option<&str>,
+    settings: &PromptSettings,
 ) -> Result<Option<Suggestion>> {
-    let mut system_message = String::from(
+    let system_message = build_system_message(
         "You are an expert at using shell commands. Respond with a JSON object only, \
          matching the provided JSON schema. The command will be directly executed \
-         in a shell as a single executable line of code."
+         in a shell as a single executable line of code.",
+        ctx_buffer,
+        settings,
+    );
+    let user_message = format!("Generate a shell command that satisfies this user request: {}", prompt);
+
+    let mut suggestion: Suggestion = request_structured(
+        provider,
+        &system_message,
+        &user_message,
+        "shell_command_suggestion",
+        SUGGEST_SCHEMA,
+    )?;
+
+    if let Some(first) = first_command_line(&suggestion.command) {
+        log::debug!(
+            "Model returned a multi-line command; keeping only the first command: {:?}",
+            suggestion.command
+        );
+        suggestion.command = first;
+    }
+
+    Ok(Some(suggestion))
+}
+
+/// The first line of a command the model spread over several lines, or
+/// `None` if it is a single line.
+///
+/// Lines ending in a backslash continuation stay joined to the next line,
+/// since they belong to one command.
+fn first_command_line(command: &str) -> Option<String> {
+    let command = command.trim();
+    if !command.contains('\n') {
+        return None;
+    }
+
+    let mut first = String::new();
+    for line in command.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()) {
+        match line.strip_suffix('\\') {
+            Some(continued) => first.push_str(continued),
+            None => {
+                first.push_str(line);
+                break;
+            }
+        }
+    }
+    (first.trim() != command).then(|| first.trim().to_string())
+}
+
+/// Ask the model to revise `command` according to a natural-language instruction.
+///
+/// The original prompt and the current command are sent along as context so
+/// the model tweaks the existing command rather than starting over.
+async fn refine_command(
+    validated: &ValidatedConfig<'_>,
+    settings: &PromptSettings,
+    prompt: &str,
+    command: &str,
+    instruction: &str,
+    ctx_enabled: bool,
+    ctx_buffer: &str,
+) -> Result<String> {
+    let provider = ProviderConfig::from_validated(validated);
+    let ctx = if ctx_enabled { ctx_buffer } else { "" };
+    let refine_prompt = format!(
+        "{}\n\nStart from this existing command: {}\nChange it as follows: {}",
+        prompt, command, instruction
     );
 
+    let progress = Progress::new("Refining command...");
+    let result = suggest_once(&provider, &refine_prompt, ctx, settings).await;
+    if let Some(ref p) = progress {
+        p.finish_and_clear();
+    }
+    provider.report_usage();
+
+    match result? {
+        Some(s) if !s.command.trim().is_empty() => Ok(s.command),
+        _ => Err(anyhow!("the model returned no command")),
+    }
+}
+
+/// Show a command before and after refinement.
+fn print_refinement(before: &str, after: &str) {
+    println!();
+    println!("Before: {}", before.dimmed());
+    println!("After:  {}", after.green());
+}
+
+async fn plan_once(provider: &ProviderConfig, prompt: &str, settings: &PromptSettings) -> Result<Plan> {
+    let system_message = build_system_message(
+        "You are an expert at using shell commands. Respond with a JSON object only, \
+         matching the provided JSON schema. Break the task into an ordered list of steps. \
+         Each step's command will be directly executed in a shell as a single executable \
+         line of code, in order, stopping at the first failure. Each step runs in a \
+         separate shell, so directory changes and environment variables do not carry \
+         over between steps.",
+        "",
+        settings,
+    );
+    let user_message = format!("Generate a plan of shell commands that satisfies this user request: {}", prompt);
+
+    request_structured(provider, &system_message, &user_message, "shell_command_plan", PLAN_SCHEMA)
+}
+
+/// Settings that shape the suggest system prompt, resolved once per run.
+#[derive(Debug, Clone)]
+struct PromptSettings {
+    locale: Option<String>,
+    shell: Option<ShellType>,
+    customization: Option<String>,
+    /// Maximum length of the context mode buffer, for the prompt to report.
+    ctx_max_chars: u32,
+    /// Recent shell history, when `--history-context` is used.
+    shell_history: Option<String>,
+    /// Whether the model is a code model (Mistral's Codestral) that does
+    /// better with code-flavored instructions.
+    code_model: bool,
+}
+
+impl PromptSettings {
+    fn from_validated(validated: &ValidatedConfig<'_>) -> Result<Self> {
+        let config = validated.app_config();
+        Ok(Self {
+            locale: resolve_locale(config.locale.value.as_deref()),
+            shell: config.shell.value.or_else(ShellType::detect),
+            customization: config.suggest_prompt_customization()?,
+            ctx_max_chars: config.effective_ctx_max_chars(),
+            shell_history: None,
+            code_model: *validated.provider == Provider::Mistral
+                && validated.effective_model().contains("codestral"),
+        })
+    }
+}
+
+/// Build the system message shared by single-command and plan requests.
+///
+/// The user's customization goes last so it can override the built-in
+/// instructions, platform and shell hints, and locale.
+fn build_system_message(intro: &str, ctx_buffer: &str, settings: &PromptSettings) -> String {
+    let mut system_message = String::from(intro);
+
     if !ctx_buffer.is_empty() {
         system_message.push_str(&format!(
-            " Between [], these are the last 1500 characters from the previous \
+            " Between [], these are the last {} characters from the previous \
              command's output, you can use them as context: [{}]",
-            ctx_buffer
+            settings.ctx_max_chars, ctx_buffer
+        ));
+    }
+
+    if let Some(ref history) = settings.shell_history {
+        system_message.push_str(&format!(
+            " Between [], these are the user's most recent shell commands, oldest first, \
+             you can use them as context: [{}]",
+            history
         ));
     }
 
@@ -476,27 +1135,58 @@ async fn suggest_once(
     );
     system_message.push_str(&platform_string);
 
-    if let Some(loc) = locale {
+    if let Some(shell) = settings.shell {
+        system_message.push(' ');
+        system_message.push_str(shell.prompt_hint());
+    }
+
+    if settings.code_model {
+        system_message.push_str(
+            " Write the command as an experienced shell scripter would: quote variables and \
+             paths, prefer standard tools, and handle file names with spaces. Put no comments \
+             or commentary in the command.",
+        );
+    }
+
+    if let Some(ref loc) = settings.locale {
         system_message.push_str(&format!(
-            " Respond in the user's preferred locale/language: {}.",
+            " Write any natural-language text (such as comments and descriptions) in the \
+             user's preferred locale/language: {}. Keep command names, flags, and other \
+             shell syntax in their standard form; never translate them.",
             loc
         ));
     }
 
-    let schema_value: serde_json::Value = serde_json::from_str(SUGGEST_SCHEMA)
+    if let Some(ref custom) = settings.customization {
+        system_message.push_str("\n\nAdditional instructions from the user, which take precedence over the above:\n");
+        system_message.push_str(custom);
+    }
+
+    system_message
+}
+
+/// Send a chat completion request constrained to a JSON schema and parse the response.
+fn request_structured<T: DeserializeOwned>(
+    provider: &ProviderConfig,
+    system_message: &str,
+    user_message: &str,
+    schema_name: &str,
+    schema: &str,
+) -> Result<T> {
+    let schema_value: serde_json::Value = serde_json::from_str(schema)
         .context("invalid internal suggest JSON schema")?;
 
     let mut payload = json!({
         "model": provider.model,
         "messages": [
             { "role": "system", "content": system_message },
-            { "role": "user", "content": format!("Generate a shell command that satisfies this user request: {}", prompt) }
+            { "role": "user", "content": user_message }
         ],
         "temperature": provider.temperature,
         "response_format": {
             "type": "json_schema",
             "json_schema": {
-                "name": "shell_command_suggestion",
+                "name": schema_name,
                 "strict": true,
                 "schema": schema_value
             }
@@ -508,11 +1198,7 @@ async fn suggest_once(
         payload["max_tokens"] = json!(max_tokens);
     }
 
-    let url = provider.chat_completions_url();
-    let bearer_token = provider.api_key.as_deref();
-    let extra_headers = provider.extra_headers_ref();
-
-    let resp_json: serde_json::Value = http::post_json(&url, bearer_token, &extra_headers, &payload)?;
+    let resp_json = provider.post_chat(&payload)?;
 
     if let Some(msg) = http::extract_api_error(&resp_json) {
         return Err(anyhow!("API error: {}", msg));
@@ -520,43 +1206,196 @@ async fn suggest_once(
 
     let content = http::extract_content_from_response(&resp_json)?;
 
-    let suggestion: Suggestion = serde_json::from_str(content).map_err(|e| {
+    serde_json::from_str(content).map_err(|e| {
         // If parsing failed and response was truncated, give a helpful hint
         if http::is_truncated(&resp_json) {
             anyhow!(
                 "Response truncated (max_tokens too low). Increase --max-tokens or SHAI_MAX_TOKENS."
             )
         } else {
-            anyhow!("Failed to parse JSON from model: {}\nReceived: {}", e, content)
+            anyhow::Error::new(UnparsableResponse(format!(
+                "Failed to parse JSON from model: {}\nReceived: {}",
+                e, content
+            )))
         }
-    })?;
+    })
+}
 
-    Ok(Some(suggestion))
+/// The model answered, but not with JSON that could be parsed.
+#[derive(Debug)]
+struct UnparsableResponse(String);
+
+impl std::fmt::Display for UnparsableResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
-fn run_command_default(command: &str) -> Result<()> {
-    #[cfg(windows)]
-    let mut cmd = std::process::Command::new("cmd");
-    #[cfg(not(windows))]
-    let mut cmd = std::process::Command::new("sh");
+impl std::error::Error for UnparsableResponse {}
+
+/// Record an accepted command in history.
+///
+/// Skipped in context mode, since the prompt may have been built from
+/// captured command output that could contain sensitive data.
+fn record_history(validated: &ValidatedConfig<'_>, prompt: &str, command: &str, outcome: Outcome, ctx_enabled: bool) {
+    if ctx_enabled {
+        return;
+    }
+    history::record(validated, prompt, command, outcome);
+    history::save_last_command(command);
+}
+
+/// With `confirm_before_execute`, show the command pre-filled for a final
+/// edit. Returns the command to run, or `None` if the user cancelled.
+fn edit_before_execution(config: &AppConfig, command: &str) -> Result<Option<String>> {
+    if !config.confirm_before_execute.value {
+        return Ok(Some(command.to_string()));
+    }
+
+    println!();
+    let edited = TextInput::new("Run (Enter to execute, Esc to cancel):")
+        .with_initial_value(command)
+        .run()
+        .map_err(|e| anyhow!("Input error: {}", e))?;
+    match edited.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) {
+        Some(command) => Ok(Some(command)),
+        None => {
+            println!("Execution cancelled.");
+            Ok(None)
+        }
+    }
+}
+
+/// Check a command's risk level before executing it.
+///
+/// Medium-risk commands print a warning; high-risk commands require the user
+/// to type `yes`. Returns `false` if the user declined.
+fn confirm_execution(command: &str, frontend: Frontend) -> Result<bool> {
+    match risk::classify_risk(command) {
+        RiskLevel::Low => Ok(true),
+        RiskLevel::Medium => {
+            let reasons = risk::reasons_at(command, RiskLevel::Medium);
+            log::warn!("This command may be risky: {}", reasons.join(", "));
+            Ok(true)
+        }
+        RiskLevel::High => {
+            let reasons = risk::reasons_at(command, RiskLevel::High);
+            println!();
+            println!(
+                "{} {}",
+                "Dangerous command:".red().bold(),
+                reasons.join(", ").red()
+            );
+
+            let confirmed = match frontend {
+                Frontend::Dialog => ui::confirm_typed("Type 'yes' to execute:", "yes")
+                    .map_err(|e| anyhow!("Input error: {}", e))?,
+                _ => {
+                    print!("Type 'yes' to execute: ");
+                    io::stdout().flush()?;
+                    let mut answer = String::new();
+                    io::stdin().lock().read_line(&mut answer)?;
+                    answer.trim() == "yes"
+                }
+            };
 
-    #[cfg(windows)]
-    {
-        cmd.arg("/C").arg(command);
+            if !confirmed {
+                println!("Execution cancelled.");
+            }
+            Ok(confirmed)
+        }
     }
-    #[cfg(not(windows))]
-    {
-        cmd.arg("-c").arg(command);
+}
+
+/// Set the shell that accepted commands run in (`None` for `sh`, or `cmd`
+/// on Windows). Call once at startup, after config load.
+pub fn set_exec_shell(shell: Option<ShellType>) {
+    let _ = EXEC_SHELL.set(shell);
+}
+
+/// Allow or forbid running commands. Call once at startup, after config load.
+pub fn set_allow_execute(allowed: bool) {
+    ALLOW_EXECUTE.store(allowed, Ordering::Relaxed);
+}
+
+fn execution_allowed() -> bool {
+    ALLOW_EXECUTE.load(Ordering::Relaxed)
+}
+
+/// Build a process that runs `command` in the execution shell.
+///
+/// Every command shell-ai runs goes through here, so this is where
+/// `allow_execute = false` is enforced; the menus only hide the option.
+/// Falls back to `sh -c` (`cmd /C` on Windows) if that shell isn't installed.
+pub fn shell_command(command: &str) -> Result<std::process::Command> {
+    if !execution_allowed() {
+        return Err(anyhow!("Executing commands is disabled (allow_execute = false)."));
     }
+    let shell = EXEC_SHELL.get().copied().flatten();
+    Ok(shell_command_with(command, shell, |program| explain::find_in_path(program).is_some()))
+}
+
+/// Build the process for `command` in `shell`, using `on_path` to check
+/// which interpreters are installed.
+fn shell_command_with(
+    command: &str,
+    shell: Option<ShellType>,
+    on_path: impl Fn(&str) -> bool,
+) -> std::process::Command {
+    let (program, args): (&str, &[&str]) = match shell {
+        // Windows PowerShell 5 is `powershell`; PowerShell 7+ is `pwsh` everywhere
+        Some(ShellType::PowerShell) if !on_path("pwsh") && cfg!(windows) => {
+            ("powershell", &["-NoProfile", "-Command"])
+        }
+        Some(ShellType::PowerShell) => ("pwsh", &["-NoProfile", "-Command"]),
+        Some(ShellType::Bash) => ("bash", &["-c"]),
+        Some(ShellType::Zsh) => ("zsh", &["-c"]),
+        Some(ShellType::Fish) => ("fish", &["-c"]),
+        Some(ShellType::Nu) => ("nu", &["-c"]),
+        Some(ShellType::Elvish) => ("elvish", &["-c"]),
+        Some(ShellType::Xonsh) => ("xonsh", &["-c"]),
+        None => default_shell(),
+    };
+    let (program, args) = if on_path(program) {
+        (program, args)
+    } else {
+        log::debug!("Execution shell {} not found on PATH; using the default shell", program);
+        default_shell()
+    };
 
-    let status = cmd.status()?;
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args).arg(command);
+    cmd
+}
+
+/// The platform's default shell and the arguments that make it run a command.
+fn default_shell() -> (&'static str, &'static [&'static str]) {
+    if cfg!(windows) {
+        ("cmd", &["/C"])
+    } else {
+        ("sh", &["-c"])
+    }
+}
+
+fn run_command_default(command: &str) -> Result<()> {
+    let status = shell_command(command)?.status()?;
     if !status.success() {
         return Err(anyhow!("Command exited with status: {}", status));
     }
     Ok(())
 }
 
+/// The last `max_chars` characters of `text`.
+fn last_chars(text: &str, max_chars: usize) -> &str {
+    let skip = text.chars().count().saturating_sub(max_chars);
+    match text.char_indices().nth(skip) {
+        Some((start, _)) => &text[start..],
+        None => "",
+    }
+}
+
 fn handle_command_with_ctx(
+    config: &AppConfig,
     command: &str,
     ctx_buffer: &mut String,
     ctx_enabled: &mut bool,
@@ -571,46 +1410,134 @@ fn handle_command_with_ctx(
         return Ok(());
     }
 
-    if command.starts_with("cd ") {
-        let path = command[3..].trim();
+    if let Some(path) = command.strip_prefix("cd ") {
+        let path = path.trim();
         let expanded = shellexpand::tilde(path).into_owned();
         std::env::set_current_dir(expanded)?;
         return Ok(());
     }
 
-    // Run command and capture stdout.
-    #[cfg(windows)]
-    let mut cmd = std::process::Command::new("cmd");
-    #[cfg(not(windows))]
-    let mut cmd = std::process::Command::new("sh");
-
-    #[cfg(windows)]
-    {
-        cmd.arg("/C").arg(command);
-    }
-    #[cfg(not(windows))]
-    {
-        cmd.arg("-c").arg(command);
-    }
-
-    let output = cmd.output()?;
+    // Run command and capture its output.
+    let output = shell_command(command)?.output()?;
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
     if !stdout.is_empty() {
         println!("\n{}", stdout);
     }
+    if !stderr.is_empty() {
+        eprintln!("{}", stderr);
+    }
 
-    // Update context buffer with last 1500 characters.
-    let max_len = 1500usize;
-    let trimmed = if stdout.len() > max_len {
-        stdout[stdout.len() - max_len..].to_string()
-    } else {
-        stdout
+    let captured = match config.ctx_capture.value {
+        CtxCapture::Stdout => stdout,
+        CtxCapture::Stderr => stderr,
+        CtxCapture::Both if stderr.is_empty() => stdout,
+        CtxCapture::Both if stdout.is_empty() => format!("[stderr]\n{}", stderr),
+        CtxCapture::Both => format!("{}\n[stderr]\n{}", stdout.trim_end(), stderr),
     };
-    *ctx_buffer = trimmed;
 
-    if !output.status.success() {
+    // Update context buffer with the last ctx_max_chars characters.
+    *ctx_buffer = last_chars(&captured, config.effective_ctx_max_chars() as usize).to_string();
+
+    // A failed command's stdout alone rarely helps, so context mode stops
+    // there unless its diagnostics on stderr are being captured
+    if !output.status.success() && config.ctx_capture.value == CtxCapture::Stdout {
         *ctx_enabled = false;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt_settings() -> PromptSettings {
+        PromptSettings {
+            locale: None,
+            shell: None,
+            customization: None,
+            ctx_max_chars: 1000,
+            shell_history: None,
+            code_model: false,
+        }
+    }
+
+    #[test]
+    fn system_prompt_names_the_detected_shell() {
+        let shell = ShellType::detect_from(|name| (name == "SHELL").then(|| "/usr/bin/fish".into()));
+        let settings = PromptSettings { shell, ..prompt_settings() };
+
+        let message = build_system_message("Suggest a command.", "", &settings);
+        assert!(message.contains(ShellType::Fish.prompt_hint()), "{}", message);
+    }
+
+    #[test]
+    fn near_duplicate_suggestions_collapse() {
+        let mut results = Vec::new();
+        for command in ["ls -la", "LS  -la;", " ls -la ; ", "ls -lah", "ls\t-la"] {
+            push_unique(&mut results, Suggestion { command: command.to_string() });
+        }
+
+        let commands: Vec<&str> = results.iter().map(|s| s.command.as_str()).collect();
+        assert_eq!(commands, ["ls -la", "ls -lah"]);
+    }
+
+    #[test]
+    fn system_prompt_omits_the_shell_when_unknown() {
+        let message = build_system_message("Suggest a command.", "", &prompt_settings());
+        assert!(!message.contains("The command will run in"), "{}", message);
+    }
+
+    #[test]
+    fn multi_line_commands_keep_only_the_first_command() {
+        assert_eq!(first_command_line("ls -la"), None);
+        assert_eq!(first_command_line("ls -la\n"), None);
+        assert_eq!(
+            first_command_line("cd /tmp\n\nrm -rf build\n"),
+            Some("cd /tmp".to_string())
+        );
+        assert_eq!(
+            first_command_line("find . -name '*.rs' \\\n  -exec wc -l {} +\necho done"),
+            Some("find . -name '*.rs'   -exec wc -l {} +".to_string())
+        );
+    }
+
+    fn argv(cmd: &std::process::Command) -> Vec<String> {
+        std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn commands_run_in_the_selected_interpreter() {
+        let installed = |program: &str| ["fish", "pwsh", "sh", "cmd"].contains(&program);
+        let (default_program, default_flag) = default_shell();
+
+        let cmd = shell_command_with("ls", Some(ShellType::Fish), installed);
+        assert_eq!(argv(&cmd), ["fish", "-c", "ls"]);
+
+        let cmd = shell_command_with("ls", Some(ShellType::PowerShell), installed);
+        assert_eq!(argv(&cmd), ["pwsh", "-NoProfile", "-Command", "ls"]);
+
+        // Missing interpreters fall back to the platform default
+        let cmd = shell_command_with("ls", Some(ShellType::Zsh), installed);
+        assert_eq!(argv(&cmd), [default_program, default_flag[0], "ls"]);
+
+        let cmd = shell_command_with("ls", None, installed);
+        assert_eq!(argv(&cmd), [default_program, default_flag[0], "ls"]);
+    }
+
+    #[test]
+    fn system_prompt_names_the_resolved_locale() {
+        let settings = PromptSettings { locale: resolve_locale(Some("de_DE")), ..prompt_settings() };
+        let message = build_system_message("Suggest a command.", "", &settings);
+        assert!(message.contains("preferred locale/language: de_DE."), "{}", message);
+        assert!(message.contains("never translate them"), "{}", message);
+
+        // An empty locale setting turns the hint off
+        let settings = PromptSettings { locale: resolve_locale(Some("")), ..prompt_settings() };
+        let message = build_system_message("Suggest a command.", "", &settings);
+        assert!(!message.contains("locale"), "{}", message);
+    }
+}
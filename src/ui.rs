@@ -3,14 +3,102 @@
 //! Provides interactive prompts with both arrow key navigation and
 //! number/letter shortcuts (similar to Claude Code's interface).
 
-use colored::Colorize;
+use colored::{Color, Colorize};
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{self, ClearType},
 };
+use std::collections::HashSet;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::clipboard;
+use crate::progress;
+
+/// Whether text inputs default to vim mode (set from `edit_mode` after config loading).
+static VIM_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Make vim-style editing the default for text inputs.
+pub fn set_vim_mode(enabled: bool) {
+    VIM_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Exit cleanly on Ctrl+C instead of dying with the terminal in a bad state.
+///
+/// Provider requests block the async runtime, so cleanup happens on the
+/// signal handler's own thread: clear the spinner, leave raw mode, show the
+/// cursor, and exit with status 130. In-flight requests end with the process.
+pub fn install_interrupt_handler() {
+    let result = ctrlc::set_handler(|| {
+        progress::clear_active();
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(io::stderr(), cursor::Show);
+        eprintln!("\nInterrupted.");
+        std::process::exit(130);
+    });
+    if let Err(e) = result {
+        log::debug!("Could not install the Ctrl+C handler: {}", e);
+    }
+}
+
+/// Width of the terminal in columns, or 80 if it can't be determined.
+pub fn terminal_width() -> usize {
+    terminal::size().map(|(w, _)| w as usize).unwrap_or(80)
+}
+
+/// Height of the terminal in rows, or 24 if it can't be determined.
+fn terminal_height() -> usize {
+    match terminal::size() {
+        Ok((_, h)) if h > 0 => h as usize,
+        _ => 24,
+    }
+}
+
+/// Switches a terminal in and out of raw mode.
+trait RawMode {
+    fn enable(&self) -> io::Result<()>;
+    fn disable(&self);
+}
+
+/// The real terminal, through crossterm.
+struct Terminal;
+
+impl RawMode for Terminal {
+    fn enable(&self) -> io::Result<()> {
+        terminal::enable_raw_mode()
+    }
+
+    fn disable(&self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Keeps the terminal in raw mode while alive.
+///
+/// Raw mode is left on drop, which also happens on an early `?` return or a
+/// panic, so a failed `event::read()` can't leave the terminal unusable.
+struct RawModeGuard<'a, M: RawMode>(&'a M);
+
+impl<'a, M: RawMode> RawModeGuard<'a, M> {
+    fn enable(mode: &'a M) -> io::Result<Self> {
+        mode.enable()?;
+        Ok(Self(mode))
+    }
+}
+
+impl<M: RawMode> Drop for RawModeGuard<'_, M> {
+    fn drop(&mut self) {
+        self.0.disable();
+    }
+}
+
+/// Run `f` with the terminal in raw mode, leaving it again however `f` ends.
+fn with_raw_mode<T>(mode: &impl RawMode, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    let _raw_mode = RawModeGuard::enable(mode)?;
+    f()
+}
 
 /// An option in an interactive select menu.
 #[derive(Clone)]
@@ -19,6 +107,8 @@ pub struct SelectOption {
     pub key: char,
     /// The display label for this option
     pub label: String,
+    /// Whether the label is a shell command to show syntax-highlighted
+    pub command: bool,
 }
 
 impl SelectOption {
@@ -26,21 +116,47 @@ impl SelectOption {
         Self {
             key,
             label: label.into(),
+            command: false,
         }
     }
 }
 
+/// Which option was chosen in an [`InteractiveSelect`], and how.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    /// Key of the chosen option
+    pub key: char,
+    /// Position of the chosen option in the order it was added
+    pub index: usize,
+    /// The action key that chose the highlighted option, or `None` for a
+    /// plain selection with Enter or the option's own key
+    pub action: Option<KeyCode>,
+}
+
 /// Interactive select menu with arrow navigation and keyboard shortcuts.
 ///
 /// Supports:
 /// - Arrow up/down: Move highlight between options
 /// - Number/letter keys: Jump directly to and select that option
+/// - Action keys: Choose the highlighted option for another purpose (see [`Self::action_key`])
+/// - Other characters: Type to filter options (subsequence match on the label)
+/// - Backspace: Edit the filter
 /// - Enter: Confirm currently highlighted option
-/// - Escape/Ctrl+C: Cancel
+/// - Escape: Clear the filter, or cancel if there is none
+/// - Ctrl+C: Cancel
+///
+/// When the options don't fit in the terminal, a window of them is shown
+/// around the highlight, with `▲`/`▼` lines counting the ones scrolled out.
 pub struct InteractiveSelect {
     prompt: String,
     options: Vec<SelectOption>,
+    /// Index into the filtered options
     selected: usize,
+    filter: String,
+    /// Index of the first filtered option in the window
+    offset: usize,
+    /// Extra keys that choose the highlighted option, with their help text
+    action_keys: Vec<(KeyCode, String)>,
 }
 
 impl InteractiveSelect {
@@ -49,22 +165,48 @@ impl InteractiveSelect {
             prompt: prompt.into(),
             options: Vec::new(),
             selected: 0,
+            filter: String::new(),
+            offset: 0,
+            action_keys: Vec::new(),
         }
     }
 
+    /// Add a key that chooses the highlighted option for another purpose,
+    /// described in the help line (e.g. Tab to copy and quit).
+    ///
+    /// Use a key that isn't typed into the filter, so it works while filtering
+    /// and never shadows a filter's first letter. Use [`Self::run_selection`]
+    /// to learn whether it was pressed.
+    pub fn action_key(mut self, key: KeyCode, description: impl Into<String>) -> Self {
+        self.action_keys.push((key, description.into()));
+        self
+    }
+
     /// Add an option with a key and label.
     pub fn option(mut self, key: char, label: impl Into<String>) -> Self {
         self.options.push(SelectOption::new(key, label));
         self
     }
 
+    /// Add an option whose label is a shell command, shown syntax-highlighted.
+    pub fn command_option(mut self, key: char, command: impl Into<String>) -> Self {
+        self.options.push(SelectOption { command: true, ..SelectOption::new(key, command) });
+        self
+    }
+
     /// Run the interactive selection and return the selected key.
     ///
     /// Returns `None` if the user cancelled (Escape/Ctrl+C).
     pub fn run(&mut self) -> io::Result<Option<char>> {
-        terminal::enable_raw_mode()?;
-        let result = self.run_inner();
-        terminal::disable_raw_mode()?;
+        Ok(self.run_selection()?.map(|s| s.key))
+    }
+
+    /// Run the interactive selection and return the chosen option and how it
+    /// was chosen.
+    ///
+    /// Returns `None` if the user cancelled (Escape/Ctrl+C).
+    pub fn run_selection(&mut self) -> io::Result<Option<Selection>> {
+        let result = with_raw_mode(&Terminal, || self.run_inner());
 
         // Clear the menu after selection
         execute!(io::stderr(), cursor::MoveToColumn(0))?;
@@ -72,68 +214,179 @@ impl InteractiveSelect {
         result
     }
 
-    fn run_inner(&mut self) -> io::Result<Option<char>> {
+    fn run_inner(&mut self) -> io::Result<Option<Selection>> {
         let mut stderr = io::stderr();
-        let mut first_render = true;
+        // Lines drawn by the previous render; the filter can change the count between renders
+        let mut rendered_lines = 0;
 
         loop {
             // Clear and redraw
-            self.render(&mut stderr, first_render)?;
-            first_render = false;
+            self.scroll_to_selected();
+            self.render(&mut stderr, rendered_lines)?;
+            rendered_lines = self.calculate_total_lines();
 
             // Wait for key event
             if let Event::Key(key_event) = event::read()? {
+                let visible_count = self.visible_options().len();
                 match self.handle_key(key_event) {
-                    KeyAction::Select(key) => {
+                    KeyAction::Select(selection) => {
                         // Clear the menu before returning
-                        self.clear_menu(&mut stderr)?;
-                        return Ok(Some(key));
+                        Self::clear_menu(&mut stderr, rendered_lines)?;
+                        return Ok(Some(selection));
                     }
                     KeyAction::Cancel => {
-                        self.clear_menu(&mut stderr)?;
+                        Self::clear_menu(&mut stderr, rendered_lines)?;
                         return Ok(None);
                     }
                     KeyAction::MoveUp => {
                         if self.selected > 0 {
                             self.selected -= 1;
                         } else {
-                            self.selected = self.options.len().saturating_sub(1);
+                            self.selected = visible_count.saturating_sub(1);
                         }
                     }
                     KeyAction::MoveDown => {
-                        if self.selected < self.options.len().saturating_sub(1) {
+                        if self.selected < visible_count.saturating_sub(1) {
                             self.selected += 1;
                         } else {
                             self.selected = 0;
                         }
                     }
+                    KeyAction::FilterPush(c) => {
+                        self.filter.push(c);
+                        self.clamp_selected();
+                    }
+                    KeyAction::FilterPop => {
+                        self.filter.pop();
+                        self.clamp_selected();
+                    }
+                    KeyAction::FilterClear => {
+                        self.filter.clear();
+                        self.clamp_selected();
+                    }
                     KeyAction::None => {}
                 }
             }
         }
     }
 
+    /// Options whose label contains the filter as a case-insensitive subsequence.
+    fn visible_options(&self) -> Vec<&SelectOption> {
+        self.options
+            .iter()
+            .filter(|o| is_subsequence(&self.filter, &o.label))
+            .collect()
+    }
+
+    /// The highlighted option and its position among all options.
+    fn highlighted(&self) -> Option<(usize, &SelectOption)> {
+        self.options
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| is_subsequence(&self.filter, &o.label))
+            .nth(self.selected)
+    }
+
+    /// Keep the highlight within the filtered options.
+    fn clamp_selected(&mut self) {
+        let count = self.visible_options().len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+
+    /// Terminal lines each filtered option takes.
+    fn option_heights(&self) -> Vec<usize> {
+        let term_width = terminal_width();
+        self.visible_options()
+            .iter()
+            .map(|o| Self::lines_needed_with_prefix(&o.label, term_width, 6))
+            .collect()
+    }
+
+    /// Terminal lines left for options (and scroll indicators) after the
+    /// prompt, blank line, and help line, keeping one for the cursor.
+    fn option_budget(&self) -> usize {
+        let term_width = terminal_width();
+        let chrome = Self::lines_needed(&self.prompt_line(), term_width)
+            + 1
+            + Self::lines_needed(&self.help_text(), term_width)
+            + 1;
+        terminal_height().saturating_sub(chrome).max(1)
+    }
+
+    /// Whether the filtered options need scrolling to fit.
+    fn scrolling(&self, heights: &[usize]) -> bool {
+        heights.iter().sum::<usize>() > self.option_budget()
+    }
+
+    /// Move the window so the highlighted option is inside it.
+    fn scroll_to_selected(&mut self) {
+        let heights = self.option_heights();
+        if !self.scrolling(&heights) {
+            self.offset = 0;
+            return;
+        }
+        // Two lines go to the ▲/▼ indicators
+        let budget = self.option_budget().saturating_sub(2).max(1);
+        self.offset = self.offset.min(self.selected);
+        while self.offset < self.selected && heights[self.offset..=self.selected].iter().sum::<usize>() > budget {
+            self.offset += 1;
+        }
+    }
+
+    /// Range of filtered options shown, starting at the window offset.
+    fn window(&self) -> std::ops::Range<usize> {
+        let heights = self.option_heights();
+        if !self.scrolling(&heights) {
+            return 0..heights.len();
+        }
+        let budget = self.option_budget().saturating_sub(2).max(1);
+        let start = self.offset.min(heights.len());
+        let mut end = start;
+        let mut used = 0;
+        // Always show at least one option, even if it alone overflows
+        while end < heights.len() && (end == start || used + heights[end] <= budget) {
+            used += heights[end];
+            end += 1;
+        }
+        start..end
+    }
+
     fn handle_key(&self, key: KeyEvent) -> KeyAction {
         // Handle Ctrl+C
         if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
             return KeyAction::Cancel;
         }
 
+        let filtering = !self.filter.is_empty();
+
         match key.code {
-            KeyCode::Up | KeyCode::Char('k') => KeyAction::MoveUp,
-            KeyCode::Down | KeyCode::Char('j') => KeyAction::MoveDown,
+            KeyCode::Up => KeyAction::MoveUp,
+            KeyCode::Down => KeyAction::MoveDown,
+            KeyCode::Char('k') if !filtering => KeyAction::MoveUp,
+            KeyCode::Char('j') if !filtering => KeyAction::MoveDown,
             KeyCode::Enter => {
-                if let Some(opt) = self.options.get(self.selected) {
-                    KeyAction::Select(opt.key)
+                if let Some((index, opt)) = self.highlighted() {
+                    KeyAction::Select(Selection { key: opt.key, index, action: None })
+                } else {
+                    KeyAction::None
+                }
+            }
+            code if self.action_keys.iter().any(|(k, _)| *k == code) => {
+                if let Some((index, opt)) = self.highlighted() {
+                    KeyAction::Select(Selection { key: opt.key, index, action: Some(code) })
                 } else {
                     KeyAction::None
                 }
             }
+            KeyCode::Esc if filtering => KeyAction::FilterClear,
             KeyCode::Esc => KeyAction::Cancel,
-            KeyCode::Char(c) => {
-                // Check if this character matches any option key
-                if let Some(opt) = self.options.iter().find(|o| o.key == c) {
-                    KeyAction::Select(opt.key)
+            KeyCode::Backspace if filtering => KeyAction::FilterPop,
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Shortcut keys select directly until a filter is being typed
+                if let Some((index, opt)) = self.options.iter().enumerate().find(|(_, o)| o.key == c && !filtering) {
+                    KeyAction::Select(Selection { key: opt.key, index, action: None })
+                } else if c.is_alphanumeric() || (filtering && !c.is_control()) {
+                    KeyAction::FilterPush(c)
                 } else {
                     KeyAction::None
                 }
@@ -142,21 +395,45 @@ impl InteractiveSelect {
         }
     }
 
-    fn render(&self, w: &mut impl Write, first_render: bool) -> io::Result<()> {
+    /// Prompt line text, including the filter when one is being typed.
+    fn prompt_line(&self) -> String {
+        if self.filter.is_empty() {
+            self.prompt.clone()
+        } else {
+            format!("{} /{}", self.prompt, self.filter)
+        }
+    }
+
+    fn render(&self, w: &mut impl Write, previous_lines: usize) -> io::Result<()> {
         // Move cursor back to start of menu if not first render
-        if !first_render {
-            let lines = self.calculate_total_lines();
-            execute!(w, cursor::MoveUp(lines as u16))?;
+        if previous_lines > 0 {
+            execute!(w, cursor::MoveUp(previous_lines as u16))?;
         }
 
         // Move to column 0 and clear from cursor down
         execute!(w, cursor::MoveToColumn(0), terminal::Clear(ClearType::FromCursorDown))?;
 
-        // Print prompt
-        write!(w, "{}\r\n", self.prompt.white().bold())?;
+        // Print prompt, with the filter if one is being typed
+        if self.filter.is_empty() {
+            write!(w, "{}\r\n", self.prompt.white().bold())?;
+        } else {
+            write!(w, "{} {}\r\n", self.prompt.white().bold(), format!("/{}", self.filter).yellow())?;
+        }
+
+        let visible = self.visible_options();
+        if visible.is_empty() {
+            write!(w, "  {}\r\n", "(no matches)".dimmed())?;
+        }
+
+        let window = self.window();
+        let scrolling = window.len() < visible.len();
+        if scrolling {
+            let above = if window.start > 0 { format!("▲ {} more", window.start) } else { String::new() };
+            write!(w, "  {}\r\n", above.dimmed())?;
+        }
 
         // Print options
-        for (i, opt) in self.options.iter().enumerate() {
+        for (i, opt) in visible.iter().enumerate().take(window.end).skip(window.start) {
             let is_selected = i == self.selected;
 
             let key_display = format!("{}", opt.key);
@@ -166,22 +443,26 @@ impl InteractiveSelect {
                 format!(" {} ", key_display).cyan().to_string()
             };
 
-            let label_for_display = opt.label.replace('\n', "\r\n");
-            let label_styled = if is_selected {
-                label_for_display.bold().to_string()
+            let label_styled = if opt.command {
+                style_command(&opt.label, is_selected)
+            } else if is_selected {
+                opt.label.bold().to_string()
             } else {
-                label_for_display
+                opt.label.clone()
             };
+            let label_styled = label_styled.replace('\n', "\r\n");
 
             write!(w, "  {} {}\r\n", key_styled, label_styled)?;
         }
 
+        if scrolling {
+            let below = visible.len() - window.end;
+            let below = if below > 0 { format!("▼ {} more", below) } else { String::new() };
+            write!(w, "  {}\r\n", below.dimmed())?;
+        }
+
         // Print help line
-        write!(
-            w,
-            "\r\n{}\r\n",
-            "↑↓/jk navigate • key/Enter select • Esc cancel".dimmed()
-        )?;
+        write!(w, "\r\n{}\r\n", self.help_text().dimmed())?;
 
         w.flush()?;
         Ok(())
@@ -190,26 +471,46 @@ impl InteractiveSelect {
     /// Calculate the total number of terminal lines the menu will occupy,
     /// accounting for line wrapping and embedded newlines.
     fn calculate_total_lines(&self) -> usize {
-        let term_width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+        let term_width = terminal_width();
 
         let mut total_lines = 0;
 
         // Prompt line
-        total_lines += Self::lines_needed(&self.prompt, term_width);
+        total_lines += Self::lines_needed(&self.prompt_line(), term_width);
 
         // Option lines (first line has "  [X] " prefix = 6 chars, continuation lines don't)
-        for opt in &self.options {
+        let visible = self.visible_options();
+        if visible.is_empty() {
+            total_lines += 1; // "(no matches)"
+        }
+        let window = self.window();
+        if window.len() < visible.len() {
+            total_lines += 2; // ▲ and ▼ lines
+        }
+        for opt in &visible[window] {
             total_lines += Self::lines_needed_with_prefix(&opt.label, term_width, 6);
         }
 
         // Blank line + help line
-        let help_text = "↑↓/jk navigate • key/Enter select • Esc cancel";
         total_lines += 1; // blank line
-        total_lines += Self::lines_needed(help_text, term_width);
+        total_lines += Self::lines_needed(&self.help_text(), term_width);
 
         total_lines
     }
 
+    fn help_text(&self) -> String {
+        let actions: String = self
+            .action_keys
+            .iter()
+            .map(|(key, description)| format!(" • {} {}", key, description))
+            .collect();
+        if self.filter.is_empty() {
+            format!("↑↓/jk navigate • key/Enter select{} • type to filter • Esc cancel", actions)
+        } else {
+            format!("↑↓ navigate • Enter select{} • Backspace edit filter • Esc clear filter", actions)
+        }
+    }
+
     /// Calculate how many terminal lines a string will occupy,
     /// accounting for embedded newlines and line wrapping.
     fn lines_needed(s: &str, term_width: usize) -> usize {
@@ -221,7 +522,7 @@ impl InteractiveSelect {
                 if line.is_empty() {
                     1
                 } else {
-                    (line.len() + term_width - 1) / term_width // ceiling division
+                    line.len().div_ceil(term_width)
                 }
             })
             .sum()
@@ -242,18 +543,17 @@ impl InteractiveSelect {
         // First line includes prefix
         let first_line = lines.remove(0);
         let first_len = prefix_len + first_line.len();
-        total += if first_len == 0 { 1 } else { (first_len + term_width - 1) / term_width };
+        total += if first_len == 0 { 1 } else { first_len.div_ceil(term_width) };
 
         // Remaining lines have no prefix
         for line in lines {
-            total += if line.is_empty() { 1 } else { (line.len() + term_width - 1) / term_width };
+            total += if line.is_empty() { 1 } else { line.len().div_ceil(term_width) };
         }
 
         total
     }
 
-    fn clear_menu(&self, w: &mut impl Write) -> io::Result<()> {
-        let lines_to_clear = self.calculate_total_lines();
+    fn clear_menu(w: &mut impl Write, lines_to_clear: usize) -> io::Result<()> {
         execute!(
             w,
             cursor::MoveUp(lines_to_clear as u16),
@@ -264,13 +564,398 @@ impl InteractiveSelect {
 }
 
 enum KeyAction {
-    Select(char),
+    Select(Selection),
     Cancel,
     MoveUp,
     MoveDown,
+    FilterPush(char),
+    FilterPop,
+    FilterClear,
     None,
 }
 
+/// Whether all characters of `needle` appear in `haystack` in order (case-insensitive).
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut hay = haystack.chars().flat_map(char::to_lowercase);
+    needle
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|n| hay.any(|h| h == n))
+}
+
+/// Color a shell command for display: command names, flags, quoted strings,
+/// and pipes/redirects each get their own color.
+///
+/// This is a rough tokenizer for readability, not a shell parser. Colors
+/// follow `colored`, so the text stays plain under `NO_COLOR` or when stdout
+/// isn't a terminal.
+pub fn highlight_command(command: &str) -> String {
+    style_command(command, false)
+}
+
+fn style_command(command: &str, bold: bool) -> String {
+    const OPERATOR_CHARS: &str = "|&;<>";
+
+    let paint = |text: &str, color: Option<Color>| {
+        let painted = match color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        if bold { painted.bold() } else { painted }.to_string()
+    };
+
+    let mut out = String::new();
+    let mut rest = command;
+    // Whether the next word is a command name (at the start, or after a pipe or separator)
+    let mut command_position = true;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        // A file descriptor number belongs to the redirect after it (2>, 2>&1)
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let redirect_fd = digits > 0 && rest[digits..].starts_with(['<', '>']);
+
+        if redirect_fd || OPERATOR_CHARS.contains(c) {
+            let end = digits + rest[digits..].len() - rest[digits..].trim_start_matches(|c| OPERATOR_CHARS.contains(c)).len();
+            let (operator, tail) = rest.split_at(end);
+            // After a redirect comes a file name; after anything else, a new command
+            command_position = !operator.contains(['<', '>']);
+            out.push_str(&paint(operator, Some(Color::Magenta)));
+            rest = tail;
+            continue;
+        }
+
+        let end = word_end(rest);
+        let (word, tail) = rest.split_at(end);
+        let color = if word.starts_with(['\'', '"']) {
+            Some(Color::Yellow)
+        } else if command_position && !is_assignment(word) {
+            command_position = false;
+            Some(Color::Green)
+        } else if word.starts_with('-') {
+            Some(Color::Cyan)
+        } else {
+            None
+        };
+        out.push_str(&paint(word, color));
+        rest = tail;
+    }
+
+    out
+}
+
+/// Byte length of the shell word at the start of `text`, keeping quoted
+/// sections and backslash escapes together.
+fn word_end(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            // Unterminated quotes run to the end
+            '\'' if chars.by_ref().all(|(_, c)| c != '\'') => return text.len(),
+            '"' => {
+                let mut escaped = false;
+                let closed = chars.by_ref().any(|(_, c)| {
+                    let end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    end
+                });
+                if !closed {
+                    return text.len();
+                }
+            }
+            c if c.is_whitespace() || "|&;<>".contains(c) => return i,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Whether a word is a variable assignment (`FOO=bar`) preceding a command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// A node in an interactive tree view.
+pub struct TreeItem {
+    /// Plain label text, used for layout
+    label: String,
+    /// Label with color codes, shown instead of `label` when set
+    styled_label: Option<String>,
+    /// Extra text revealed on demand below the label
+    detail: Option<String>,
+    children: Vec<TreeItem>,
+}
+
+impl TreeItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            styled_label: None,
+            detail: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Display the label with color codes (must render to the same text as the plain label).
+    pub fn styled(mut self, styled_label: impl Into<String>) -> Self {
+        self.styled_label = Some(styled_label.into());
+        self
+    }
+
+    /// Set the detail text that can be toggled below the label.
+    pub fn detail(mut self, detail: Option<String>) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    /// Add a child item.
+    pub fn child(mut self, child: TreeItem) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+/// A visible row of the tree: the path of child indices from the root list.
+struct TreeRow {
+    path: Vec<usize>,
+}
+
+/// Interactive collapsible tree with keyboard navigation.
+///
+/// Supports:
+/// - Arrow up/down (or j/k): Move between visible rows
+/// - Arrow right (or l): Expand, or move to the first child when expanded
+/// - Arrow left (or h): Collapse, or move to the parent
+/// - Enter/Space: Toggle expansion
+/// - c: Toggle the detail text of the highlighted row
+/// - q/Escape/Ctrl+C: Leave the tree on screen and return
+pub struct TreeView {
+    title: String,
+    detail_name: String,
+    items: Vec<TreeItem>,
+    expanded: HashSet<Vec<usize>>,
+    details_shown: HashSet<Vec<usize>>,
+    /// Index into the visible rows
+    selected: usize,
+}
+
+impl TreeView {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            detail_name: "details".to_string(),
+            items: Vec::new(),
+            expanded: HashSet::new(),
+            details_shown: HashSet::new(),
+            selected: 0,
+        }
+    }
+
+    /// Add a top-level item.
+    pub fn item(mut self, item: TreeItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Name of the detail text in the help line (e.g. "citation").
+    pub fn detail_name(mut self, name: impl Into<String>) -> Self {
+        self.detail_name = name.into();
+        self
+    }
+
+    /// Run the tree view until the user quits.
+    pub fn run(&mut self) -> io::Result<()> {
+        with_raw_mode(&Terminal, || self.run_inner())
+    }
+
+    fn run_inner(&mut self) -> io::Result<()> {
+        let mut stderr = io::stderr();
+        let mut rendered_lines = 0;
+
+        loop {
+            self.render(&mut stderr, rendered_lines, false)?;
+            rendered_lines = self.calculate_total_lines(false);
+
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) && key_event.code == KeyCode::Char('c') {
+                    break;
+                }
+
+                let rows = self.visible_rows();
+                let row_count = rows.len();
+                let Some(path) = rows.get(self.selected).map(|r| r.path.clone()) else {
+                    break;
+                };
+                let has_children = self.item_at(&path).is_some_and(|i| !i.children.is_empty());
+                let is_expanded = self.expanded.contains(&path);
+
+                match key_event.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.selected = if self.selected > 0 { self.selected - 1 } else { row_count - 1 };
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.selected = if self.selected + 1 < row_count { self.selected + 1 } else { 0 };
+                    }
+                    KeyCode::Right | KeyCode::Char('l') => {
+                        if has_children && !is_expanded {
+                            self.expanded.insert(path);
+                        } else if has_children {
+                            self.selected += 1;
+                        }
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        if is_expanded {
+                            self.expanded.remove(&path);
+                        } else if path.len() > 1 {
+                            let parent = &path[..path.len() - 1];
+                            if let Some(index) = rows.iter().position(|r| r.path == parent) {
+                                self.selected = index;
+                            }
+                        }
+                    }
+                    KeyCode::Enter | KeyCode::Char(' ') if has_children => toggle(&mut self.expanded, path),
+                    KeyCode::Char('c') => toggle(&mut self.details_shown, path),
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    _ => {}
+                }
+            }
+        }
+
+        // Redraw once more without the highlight and help line so the tree stays readable
+        self.render(&mut stderr, rendered_lines, true)?;
+        Ok(())
+    }
+
+    fn item_at(&self, path: &[usize]) -> Option<&TreeItem> {
+        let (first, rest) = path.split_first()?;
+        let mut item = self.items.get(*first)?;
+        for &index in rest {
+            item = item.children.get(index)?;
+        }
+        Some(item)
+    }
+
+    /// Rows currently shown, in display order (children of collapsed items are hidden).
+    fn visible_rows(&self) -> Vec<TreeRow> {
+        fn walk(items: &[TreeItem], prefix: &[usize], expanded: &HashSet<Vec<usize>>, rows: &mut Vec<TreeRow>) {
+            for (i, item) in items.iter().enumerate() {
+                let mut path = prefix.to_vec();
+                path.push(i);
+                let open = expanded.contains(&path);
+                rows.push(TreeRow { path: path.clone() });
+                if open {
+                    walk(&item.children, &path, expanded, rows);
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(&self.items, &[], &self.expanded, &mut rows);
+        rows
+    }
+
+    /// Columns before the label: highlight marker, indentation, and expansion marker.
+    fn row_prefix_len(depth: usize) -> usize {
+        2 + 2 * depth + 2
+    }
+
+    fn render(&self, w: &mut impl Write, previous_lines: usize, final_render: bool) -> io::Result<()> {
+        if previous_lines > 0 {
+            execute!(w, cursor::MoveUp(previous_lines as u16))?;
+        }
+        execute!(w, cursor::MoveToColumn(0), terminal::Clear(ClearType::FromCursorDown))?;
+
+        write!(w, "{}\r\n", self.title.white().bold())?;
+
+        for (i, row) in self.visible_rows().iter().enumerate() {
+            let Some(item) = self.item_at(&row.path) else {
+                continue;
+            };
+            let depth = row.path.len();
+            let is_selected = !final_render && i == self.selected;
+
+            let pointer = if is_selected { "›".cyan().bold().to_string() } else { " ".to_string() };
+            let marker = if item.children.is_empty() {
+                "•"
+            } else if self.expanded.contains(&row.path) {
+                "▾"
+            } else {
+                "▸"
+            };
+            let label = item.styled_label.as_deref().unwrap_or(&item.label);
+            let label = if is_selected { label.bold().to_string() } else { label.to_string() };
+
+            write!(w, "{} {}{} {}\r\n", pointer, "  ".repeat(depth), marker, label)?;
+
+            if self.details_shown.contains(&row.path) {
+                let detail = item.detail.as_deref().map_or_else(
+                    || format!("(no {})", self.detail_name).dimmed().to_string(),
+                    |d| d.dimmed().to_string(),
+                );
+                write!(w, "{}↳ {}\r\n", " ".repeat(Self::row_prefix_len(depth)), detail.replace('\n', "\r\n"))?;
+            }
+        }
+
+        if !final_render {
+            write!(w, "\r\n{}\r\n", self.help_text().dimmed())?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Calculate the total number of terminal lines the tree will occupy.
+    fn calculate_total_lines(&self, final_render: bool) -> usize {
+        let term_width = terminal_width();
+
+        let mut total_lines = InteractiveSelect::lines_needed(&self.title, term_width);
+
+        for row in self.visible_rows() {
+            let Some(item) = self.item_at(&row.path) else {
+                continue;
+            };
+            let prefix_len = Self::row_prefix_len(row.path.len());
+            total_lines += InteractiveSelect::lines_needed_with_prefix(&item.label, term_width, prefix_len);
+
+            if self.details_shown.contains(&row.path) {
+                let detail = item.detail.clone().unwrap_or_else(|| format!("(no {})", self.detail_name));
+                total_lines += InteractiveSelect::lines_needed_with_prefix(&detail, term_width, prefix_len + 2);
+            }
+        }
+
+        if !final_render {
+            total_lines += 1; // blank line
+            total_lines += InteractiveSelect::lines_needed(&self.help_text(), term_width);
+        }
+
+        total_lines
+    }
+
+    fn help_text(&self) -> String {
+        format!(
+            "↑↓/jk move • →/l expand • ←/h collapse • Enter toggle • c {} • q quit",
+            self.detail_name
+        )
+    }
+}
+
+/// Add `path` to the set if absent, remove it if present.
+fn toggle(set: &mut HashSet<Vec<usize>>, path: Vec<usize>) {
+    if !set.remove(&path) {
+        set.insert(path);
+    }
+}
+
 /// Simple text input prompt with readline-style shortcuts.
 ///
 /// Supports:
@@ -280,10 +965,20 @@ enum KeyAction {
 /// - Ctrl+U to kill to beginning, Ctrl+K to kill to end
 /// - Ctrl+W or Alt+Backspace to delete word backward
 /// - Ctrl+Left/Right or Alt+B/Alt+F for word movement
+/// - Up/Down to recall earlier entries (when history is provided)
 /// - Enter to confirm, Escape/Ctrl+C to cancel
+///
+/// In vim mode, input starts in insert mode with the bindings above, and
+/// Escape switches to normal mode instead of cancelling. Normal mode supports
+/// h/l, w/b/e, 0/$, j/k (history), i/a/I/A, x, and dd; Escape there cancels.
+///
+/// In masked mode, each character is shown as `•`, for secrets like API keys.
 pub struct TextInput {
     prompt: String,
     initial_value: String,
+    history: Vec<String>,
+    vim_mode: bool,
+    masked: bool,
 }
 
 impl TextInput {
@@ -291,50 +986,152 @@ impl TextInput {
         Self {
             prompt: prompt.into(),
             initial_value: String::new(),
+            history: Vec::new(),
+            vim_mode: VIM_MODE.load(Ordering::Relaxed),
+            masked: false,
         }
     }
 
+    /// Show `•` in place of each typed character (editing keys still work).
+    pub fn with_masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
+    /// Enable or disable vim-style modal editing (defaults to the `edit_mode` setting).
+    pub fn with_vim_mode(mut self, enabled: bool) -> Self {
+        self.vim_mode = enabled;
+        self
+    }
+
     /// Set an initial value for the input.
     pub fn with_initial_value(mut self, value: impl Into<String>) -> Self {
         self.initial_value = value.into();
         self
     }
 
+    /// Set previous entries (oldest first) to cycle through with Up/Down.
+    pub fn with_history(mut self, history: Vec<String>) -> Self {
+        self.history = history;
+        self
+    }
+
     /// Run the text input and return the entered text.
     ///
     /// Returns `None` if the user cancelled (Escape/Ctrl+C).
     pub fn run(&self) -> io::Result<Option<String>> {
-        terminal::enable_raw_mode()?;
-        let result = self.run_inner();
-        terminal::disable_raw_mode()?;
-        result
+        with_raw_mode(&Terminal, || self.run_inner())
     }
 
     fn run_inner(&self) -> io::Result<Option<String>> {
         let mut stderr = io::stderr();
         let mut input = self.initial_value.clone();
         let mut cursor_pos = input.len();
+        // Position in history while browsing, and the in-progress text to return to
+        let mut history_pos: Option<usize> = None;
+        let mut draft = String::new();
+        // Vim mode starts in insert mode, like a shell's vi mode
+        let mut normal_mode = false;
+        // Whether `d` was pressed in normal mode, awaiting a second `d`
+        let mut pending_delete = false;
 
         loop {
+            // In normal mode the cursor sits on a character, not past the end
+            if normal_mode {
+                cursor_pos = cursor_pos.min(input.len().saturating_sub(1));
+            }
+
             // Render prompt and current input
+            let indicator = match (self.vim_mode, normal_mode) {
+                (false, _) => "",
+                (true, false) => "[I] ",
+                (true, true) => "[N] ",
+            };
             execute!(
                 stderr,
                 cursor::MoveToColumn(0),
                 terminal::Clear(ClearType::CurrentLine)
             )?;
-            write!(stderr, "{} {}", self.prompt.cyan(), input)?;
+            let shown = if self.masked { "•".repeat(input.chars().count()) } else { input.clone() };
+            write!(stderr, "{}{} {}", indicator.dimmed(), self.prompt.cyan(), shown)?;
 
-            // Position cursor
-            let prompt_len = self.prompt.len() + 1; // +1 for space
-            execute!(stderr, cursor::MoveToColumn((prompt_len + cursor_pos) as u16))?;
+            // Position cursor (a mask character is one column, but three bytes)
+            let prompt_len = indicator.len() + self.prompt.len() + 1; // +1 for space
+            let cursor_col = if self.masked { input[..cursor_pos].chars().count() } else { cursor_pos };
+            execute!(stderr, cursor::MoveToColumn((prompt_len + cursor_col) as u16))?;
             stderr.flush()?;
 
             // Wait for key event
             if let Event::Key(key_event) = event::read()? {
                 let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
                 let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+                let mut code = key_event.code;
+
+                // Normal mode: handle motions and edits here, or map keys onto
+                // their insert-mode equivalents below
+                if normal_mode && !ctrl && !alt {
+                    let was_pending = std::mem::take(&mut pending_delete);
+                    match code {
+                        KeyCode::Esc if was_pending => continue,
+                        KeyCode::Char('d') if was_pending => {
+                            input.clear();
+                            cursor_pos = 0;
+                            continue;
+                        }
+                        KeyCode::Char('d') => {
+                            pending_delete = true;
+                            continue;
+                        }
+                        KeyCode::Char('i') => {
+                            normal_mode = false;
+                            continue;
+                        }
+                        KeyCode::Char('a') => {
+                            cursor_pos = (cursor_pos + 1).min(input.len());
+                            normal_mode = false;
+                            continue;
+                        }
+                        KeyCode::Char('I') => {
+                            cursor_pos = 0;
+                            normal_mode = false;
+                            continue;
+                        }
+                        KeyCode::Char('A') => {
+                            cursor_pos = input.len();
+                            normal_mode = false;
+                            continue;
+                        }
+                        KeyCode::Char('w') => {
+                            cursor_pos = find_word_boundary_forward(&input, cursor_pos);
+                            continue;
+                        }
+                        KeyCode::Char('b') => {
+                            cursor_pos = find_word_boundary_backward(&input, cursor_pos);
+                            continue;
+                        }
+                        KeyCode::Char('e') => {
+                            cursor_pos = find_word_end(&input, cursor_pos);
+                            continue;
+                        }
+                        KeyCode::Char('h') => code = KeyCode::Left,
+                        KeyCode::Char('l') => code = KeyCode::Right,
+                        KeyCode::Char('k') => code = KeyCode::Up,
+                        KeyCode::Char('j') => code = KeyCode::Down,
+                        KeyCode::Char('0') => code = KeyCode::Home,
+                        KeyCode::Char('$') => code = KeyCode::End,
+                        KeyCode::Char('x') => code = KeyCode::Delete,
+                        // Other characters do nothing in normal mode
+                        KeyCode::Char(_) => continue,
+                        _ => {}
+                    }
+                }
 
-                match (key_event.code, ctrl, alt) {
+                match (code, ctrl, alt) {
+                    // Leave insert mode
+                    (KeyCode::Esc, _, _) if self.vim_mode && !normal_mode => {
+                        normal_mode = true;
+                        cursor_pos = cursor_pos.saturating_sub(1);
+                    }
                     // Cancel
                     (KeyCode::Char('c'), true, _) | (KeyCode::Esc, _, _) => {
                         execute!(stderr, cursor::MoveToColumn(0), terminal::Clear(ClearType::CurrentLine))?;
@@ -383,29 +1180,46 @@ impl TextInput {
                         cursor_pos = find_word_boundary_forward(&input, cursor_pos);
                     }
                     // Simple backspace
-                    (KeyCode::Backspace, _, _) => {
-                        if cursor_pos > 0 {
-                            input.remove(cursor_pos - 1);
-                            cursor_pos -= 1;
-                        }
+                    (KeyCode::Backspace, _, _) if cursor_pos > 0 => {
+                        input.remove(cursor_pos - 1);
+                        cursor_pos -= 1;
                     }
                     // Delete
-                    (KeyCode::Delete, _, _) | (KeyCode::Char('d'), true, _) => {
-                        if cursor_pos < input.len() {
-                            input.remove(cursor_pos);
-                        }
+                    (KeyCode::Delete, _, _) | (KeyCode::Char('d'), true, _) if cursor_pos < input.len() => {
+                        input.remove(cursor_pos);
                     }
                     // Move left
-                    (KeyCode::Left, _, _) | (KeyCode::Char('b'), true, _) => {
-                        if cursor_pos > 0 {
-                            cursor_pos -= 1;
-                        }
+                    (KeyCode::Left, _, _) | (KeyCode::Char('b'), true, _) if cursor_pos > 0 => {
+                        cursor_pos -= 1;
                     }
                     // Move right
-                    (KeyCode::Right, _, _) | (KeyCode::Char('f'), true, _) => {
-                        if cursor_pos < input.len() {
-                            cursor_pos += 1;
+                    (KeyCode::Right, _, _) | (KeyCode::Char('f'), true, _) if cursor_pos < input.len() => {
+                        cursor_pos += 1;
+                    }
+                    // Recall older history entry
+                    (KeyCode::Up, _, _) if !self.history.is_empty() => {
+                        let pos = match history_pos {
+                            None => {
+                                draft = input.clone();
+                                self.history.len() - 1
+                            }
+                            Some(pos) => pos.saturating_sub(1),
+                        };
+                        history_pos = Some(pos);
+                        input = self.history[pos].clone();
+                        cursor_pos = input.len();
+                    }
+                    // Recall newer history entry, then the draft
+                    (KeyCode::Down, _, _) if history_pos.is_some() => {
+                        let pos = history_pos.unwrap_or_default() + 1;
+                        if pos < self.history.len() {
+                            history_pos = Some(pos);
+                            input = self.history[pos].clone();
+                        } else {
+                            history_pos = None;
+                            input = std::mem::take(&mut draft);
                         }
+                        cursor_pos = input.len();
                     }
                     // Regular character input
                     (KeyCode::Char(c), false, false) => {
@@ -419,6 +1233,16 @@ impl TextInput {
     }
 }
 
+/// Ask the user to type an exact word to confirm an action.
+///
+/// Returns `true` only if the entered text matches `expected`.
+/// Cancelling (Escape/Ctrl+C) counts as declining. Vim mode is off here so
+/// Escape always cancels.
+pub fn confirm_typed(prompt: &str, expected: &str) -> io::Result<bool> {
+    let answer = TextInput::new(prompt).with_vim_mode(false).run()?;
+    Ok(answer.is_some_and(|a| a.trim() == expected))
+}
+
 /// Find the position of the previous word boundary (for backward word operations).
 fn find_word_boundary_backward(s: &str, from: usize) -> usize {
     if from == 0 {
@@ -438,6 +1262,26 @@ fn find_word_boundary_backward(s: &str, from: usize) -> usize {
     pos
 }
 
+/// Find the last character of the current or next word (for vim's `e` motion).
+fn find_word_end(s: &str, from: usize) -> usize {
+    let len = s.len();
+    if from + 1 >= len {
+        return from;
+    }
+    let bytes = s.as_bytes();
+    let mut pos = from + 1;
+
+    // Skip whitespace before the word
+    while pos < len && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    // Advance to the word's last character
+    while pos + 1 < len && !bytes[pos + 1].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos.min(len - 1)
+}
+
 /// Find the position of the next word boundary (for forward word operations).
 fn find_word_boundary_forward(s: &str, from: usize) -> usize {
     let len = s.len();
@@ -462,12 +1306,111 @@ fn find_word_boundary_forward(s: &str, from: usize) -> usize {
 // Clipboard Utilities
 // ============================================================================
 
-/// Copy text to the system clipboard.
+/// Copy text to the system clipboard, falling back through the available backends.
 ///
-/// Prints a success message on success, or logs a warning on failure.
+/// Prints a success message naming the backend on success, or logs a warning on failure.
 pub fn copy_to_clipboard(text: &str) {
-    match arboard::Clipboard::new().and_then(|mut cb| cb.set_text(text)) {
-        Ok(_) => println!("Command copied to clipboard."),
+    match clipboard::copy(text) {
+        Ok(backend) => println!("Command copied to clipboard ({}).", backend),
         Err(e) => log::warn!("Failed to copy to clipboard: {}", e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records raw mode switches instead of touching the terminal.
+    #[derive(Default)]
+    struct FakeTerminal {
+        calls: RefCell<Vec<&'static str>>,
+        fail_enable: bool,
+    }
+
+    impl RawMode for FakeTerminal {
+        fn enable(&self) -> io::Result<()> {
+            self.calls.borrow_mut().push("enable");
+            if self.fail_enable {
+                return Err(io::Error::other("not a terminal"));
+            }
+            Ok(())
+        }
+
+        fn disable(&self) {
+            self.calls.borrow_mut().push("disable");
+        }
+    }
+
+    #[test]
+    fn raw_mode_is_left_when_the_ui_fails() {
+        let terminal = FakeTerminal::default();
+        let result: io::Result<()> = with_raw_mode(&terminal, || {
+            terminal.calls.borrow_mut().push("run");
+            Err(io::Error::other("event read failed"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "event read failed");
+        assert_eq!(*terminal.calls.borrow(), ["enable", "run", "disable"]);
+    }
+
+    #[test]
+    fn raw_mode_is_left_when_the_ui_panics() {
+        let terminal = FakeTerminal::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_raw_mode(&terminal, || -> io::Result<()> { panic!("render bug") })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(*terminal.calls.borrow(), ["enable", "disable"]);
+    }
+
+    #[test]
+    fn ui_does_not_run_without_raw_mode() {
+        let terminal = FakeTerminal { fail_enable: true, ..Default::default() };
+        let result = with_raw_mode(&terminal, || Ok(()));
+
+        assert!(result.is_err());
+        assert_eq!(*terminal.calls.borrow(), ["enable"]);
+    }
+    fn press(select: &InteractiveSelect, code: KeyCode) -> KeyAction {
+        select.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn menu() -> InteractiveSelect {
+        let mut select = InteractiveSelect::new("Select a command:");
+        for i in 1..=10 {
+            let key = char::from_digit(i, 10).unwrap_or('?');
+            select = select.command_option(key, format!("echo {}", i));
+        }
+        select.option('q', "Dismiss").action_key(KeyCode::Tab, "copy & quit")
+    }
+
+    #[test]
+    fn action_keys_choose_the_highlighted_option_by_position() {
+        let mut select = menu();
+        select.selected = 9;
+
+        match press(&select, KeyCode::Tab) {
+            KeyAction::Select(selection) => {
+                assert_eq!(selection, Selection { key: '?', index: 9, action: Some(KeyCode::Tab) })
+            }
+            _ => panic!("Tab should choose the highlighted option"),
+        }
+
+        select.filter = "10".to_string();
+        select.selected = 0;
+        match press(&select, KeyCode::Tab) {
+            KeyAction::Select(selection) => assert_eq!(selection.index, 9),
+            _ => panic!("Tab should work while filtering"),
+        }
+    }
+
+    #[test]
+    fn any_letter_can_start_a_filter() {
+        let select = menu();
+
+        assert!(matches!(press(&select, KeyCode::Char('y')), KeyAction::FilterPush('y')));
+        assert!(matches!(press(&select, KeyCode::Char('q')), KeyAction::Select(Selection { index: 10, .. })));
+    }
+}
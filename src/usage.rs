@@ -0,0 +1,323 @@
+//! Token usage and estimated spend.
+//!
+//! Requests made through a `ProviderConfig` add the provider-reported token
+//! counts to a shared [`UsageTracker`], which prints them with `--show-usage`.
+//! When the model has a `[pricing.<model>]` entry in the config, each report
+//! is also appended to `usage.jsonl` in the data directory so
+//! `shell-ai usage` can total the estimated spend. Recording is skipped with
+//! `SHAI_NO_HISTORY=true`, like history.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::{self, ModelPricing, OutputFormat, ValidatedConfig};
+use crate::history;
+use crate::http::Usage;
+use crate::output::outln;
+use crate::progress;
+
+/// Arguments for the usage subcommand.
+#[derive(Parser, Debug)]
+pub struct UsageArgs {
+    #[command(subcommand)]
+    pub action: Option<UsageAction>,
+
+    /// Only count usage from the last N days (0 = all time).
+    #[arg(long = "days", value_name = "N", default_value_t = 30)]
+    pub days: u64,
+}
+
+/// Usage subcommand actions.
+#[derive(Subcommand, Debug)]
+pub enum UsageAction {
+    /// Delete all recorded usage.
+    Clear,
+}
+
+/// Token usage and estimated cost of one suggest, plan, refine, or explain run.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UsageRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Estimated cost from the model's configured prices.
+    pub cost: f64,
+}
+
+/// Sums token usage over the requests of one run and reports it.
+#[derive(Debug)]
+pub struct UsageTracker {
+    show: bool,
+    provider: String,
+    model: String,
+    pricing: Option<ModelPricing>,
+    /// `None` until a response reports usage.
+    total: Mutex<Option<Usage>>,
+}
+
+impl UsageTracker {
+    /// Create a tracker for the validated provider and model.
+    pub fn new(validated: &ValidatedConfig<'_>) -> Self {
+        let config = validated.app_config();
+        let model = validated.effective_model();
+        Self {
+            show: config.show_usage.value,
+            provider: validated.provider.to_string(),
+            pricing: config.pricing.get(&model).copied(),
+            model,
+            total: Mutex::new(None),
+        }
+    }
+
+    /// Add one response's token counts to the running total.
+    pub fn add(&self, usage: Usage) {
+        let mut total = self.total.lock().unwrap_or_else(|e| e.into_inner());
+        total.get_or_insert_with(Usage::default).add(usage);
+    }
+
+    /// Print and record the usage summed so far, then reset the total so the
+    /// next report covers only later requests.
+    pub fn report(&self) {
+        let usage = self.total.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let cost = usage.zip(self.pricing).map(|(u, p)| p.cost(&u));
+
+        if let (Some(usage), Some(cost)) = (usage, cost) {
+            self.record(&usage, cost);
+        }
+
+        if !self.show {
+            return;
+        }
+        let line = match (usage, cost) {
+            (Some(u), Some(cost)) => format!(
+                "Tokens: {} prompt + {} completion = {} total (est. cost {:.4})",
+                u.prompt_tokens, u.completion_tokens, u.total_tokens, cost
+            ),
+            (Some(u), None) => format!(
+                "Tokens: {} prompt + {} completion = {} total",
+                u.prompt_tokens, u.completion_tokens, u.total_tokens
+            ),
+            (None, _) => "Tokens: not reported by the provider".to_string(),
+        };
+        // A spinner may still be running in the caller
+        progress::with_suspended(|| eprintln!("{}", line.dimmed()));
+    }
+
+    /// Append a run's usage to the usage store.
+    ///
+    /// Failures are logged at debug level and otherwise ignored, like history.
+    fn record(&self, usage: &Usage, cost: f64) {
+        if !history::is_enabled() {
+            return;
+        }
+
+        let record = UsageRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cost,
+        };
+        let line = serde_json::to_string(&record).unwrap_or_default();
+        if let Err(e) = history::write_private(usage_file_path(), &line, true) {
+            log::debug!("Failed to record usage: {}", e);
+        }
+    }
+}
+
+/// Get the usage store path.
+pub fn usage_file_path() -> Option<PathBuf> {
+    let mut base = config::data_dir()?;
+    base.push("usage.jsonl");
+    Some(base)
+}
+
+/// Load all usage records, oldest first. Malformed lines are skipped.
+pub fn load() -> Vec<UsageRecord> {
+    let Some(path) = usage_file_path() else {
+        return Vec::new();
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                log::debug!("Skipping malformed usage line: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Usage totals for one model.
+#[derive(Debug, Default, Serialize)]
+struct ModelTotals {
+    runs: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    cost: f64,
+}
+
+impl ModelTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.runs += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.total_tokens += record.total_tokens;
+        self.cost += record.cost;
+    }
+}
+
+fn run_report(args: &UsageArgs, output_format: OutputFormat) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let since = if args.days == 0 { 0 } else { now.saturating_sub(args.days * 86_400) };
+
+    let mut by_model: BTreeMap<String, ModelTotals> = BTreeMap::new();
+    let mut total = ModelTotals::default();
+    for record in load().iter().filter(|r| r.timestamp >= since) {
+        by_model.entry(record.model.clone()).or_default().add(record);
+        total.add(record);
+    }
+
+    let window = if args.days == 0 {
+        "all time".to_string()
+    } else {
+        format!("last {} days", args.days)
+    };
+
+    match output_format {
+        format @ (OutputFormat::Json | OutputFormat::Yaml) => {
+            format.print_serialized(&json!({
+                "days": args.days,
+                "models": by_model,
+                "total": total,
+            }))?;
+        }
+        OutputFormat::Human => {
+            let heading = format!("Estimated Spend ({})", window);
+            outln!("{}", heading.bold());
+            outln!("{}", "=".repeat(heading.len()));
+            if by_model.is_empty() {
+                outln!("{}", "(no usage recorded)".dimmed());
+                outln!();
+                outln!("{}", "Usage is recorded for models with a [pricing.<model>] config section.".dimmed());
+                return Ok(());
+            }
+            for (model, totals) in &by_model {
+                print_totals_line(model, totals);
+            }
+            outln!();
+            print_totals_line("Total", &total);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_totals_line(name: &str, totals: &ModelTotals) {
+    outln!(
+        "  {:30} {:>5} runs  {:>10} tokens  {}",
+        name.white(),
+        totals.runs,
+        totals.total_tokens,
+        format!("{:.4}", totals.cost).green()
+    );
+}
+
+fn run_clear() -> Result<()> {
+    let path = usage_file_path().context("Could not determine data directory")?;
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        outln!("{} {}", "Cleared:".green(), path.display());
+    } else {
+        outln!("No usage to clear.");
+    }
+    Ok(())
+}
+
+/// Main entry point for the usage subcommand.
+pub fn run(args: UsageArgs, output_format: OutputFormat) -> Result<()> {
+    match args.action {
+        Some(UsageAction::Clear) => run_clear(),
+        None => run_report(&args, output_format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICING: ModelPricing = ModelPricing { input_per_1k: 0.00125, output_per_1k: 0.01 };
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> Usage {
+        Usage { prompt_tokens, completion_tokens, total_tokens: prompt_tokens + completion_tokens }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn cost_is_priced_per_thousand_tokens() {
+        assert_close(PRICING.cost(&usage(1000, 500)), 0.00625);
+        assert_close(PRICING.cost(&usage(2000, 0)), 0.0025);
+        assert_close(PRICING.cost(&usage(0, 250)), 0.0025);
+        assert_close(PRICING.cost(&usage(0, 0)), 0.0);
+    }
+
+    #[test]
+    fn cost_of_summed_usage_matches_the_sum_of_costs() {
+        let (a, b) = (usage(1200, 300), usage(800, 700));
+        let mut total = a;
+        total.add(b);
+        assert_eq!(total, usage(2000, 1000));
+        assert_close(PRICING.cost(&total), PRICING.cost(&a) + PRICING.cost(&b));
+    }
+
+    #[test]
+    fn model_totals_add_up_records() {
+        let record = |prompt_tokens, completion_tokens| UsageRecord {
+            timestamp: 0,
+            provider: "openai".to_string(),
+            model: "gpt-5".to_string(),
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cost: PRICING.cost(&usage(prompt_tokens, completion_tokens)),
+        };
+
+        let mut totals = ModelTotals::default();
+        totals.add(&record(1000, 500));
+        totals.add(&record(3000, 100));
+
+        assert_eq!(totals.runs, 2);
+        assert_eq!((totals.prompt_tokens, totals.completion_tokens, totals.total_tokens), (4000, 600, 4600));
+        assert_close(totals.cost, 0.00625 + 0.00475);
+    }
+}
@@ -0,0 +1,57 @@
+//! End-to-end suggest runs against the in-process mock provider.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Run shell-ai with the mock provider, isolated from the user's config and
+/// history in a scratch directory named after the test.
+fn shell_ai(test: &str, args: &[&str]) -> Output {
+    shell_ai_with_env(test, &[], args)
+}
+
+/// Like [`shell_ai`], with extra environment variables.
+fn shell_ai_with_env(test: &str, vars: &[(&str, &str)], args: &[&str]) -> Output {
+    let dir: PathBuf = std::env::temp_dir().join(format!("shell-ai-{}-{}", test, std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_shell-ai"))
+        .args(args)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("SHAI_CONFIG_DIR", &dir)
+        .env("SHAI_NO_PROJECT_CONFIG", "true")
+        .env("SHAI_API_PROVIDER", "mock")
+        .envs(vars.iter().copied())
+        .output()
+        .expect("failed to run shell-ai");
+    let _ = std::fs::remove_dir_all(&dir);
+    output
+}
+
+#[test]
+fn suggest_prints_the_mock_command() {
+    let output = shell_ai("suggest-human", &["--frontend", "noninteractive", "suggest", "list", "files"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ls -la\n");
+}
+
+#[test]
+fn suggest_emits_structured_output() {
+    let output = shell_ai("suggest-json", &["--output-format", "json", "suggest", "show", "disk", "usage"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let suggestions: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(suggestions, serde_json::json!([{ "command": "df -h" }]));
+}
+
+#[test]
+fn retry_settings_are_bounded() {
+    for (var, value, message) in [
+        ("SHAI_MAX_RETRIES", "11", "Invalid max_retries: 11"),
+        ("SHAI_INITIAL_BACKOFF_MS", "600000", "Invalid initial_backoff_ms: 600000"),
+    ] {
+        let output = shell_ai_with_env("retry-bounds", &[(var, value)], &["--frontend", "noninteractive", "suggest", "list", "files"]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(message), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
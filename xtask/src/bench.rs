@@ -1,4 +1,5 @@
 use anyhow::{bail, Context, Result};
+use serde_json::json;
 use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
@@ -10,11 +11,14 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 static INTERRUPT_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Debug, Clone, Copy)]
+#[allow(clippy::enum_variant_names)]
 enum Shell {
     Bash,
     Zsh,
     Fish,
     PowerShell,
+    Nu,
+    Xonsh,
 }
 
 impl Shell {
@@ -24,6 +28,8 @@ impl Shell {
             Shell::Zsh => "zsh",
             Shell::Fish => "fish",
             Shell::PowerShell => "powershell",
+            Shell::Nu => "nu",
+            Shell::Xonsh => "xonsh",
         }
     }
 
@@ -33,6 +39,8 @@ impl Shell {
             Shell::Zsh => "Zsh",
             Shell::Fish => "Fish",
             Shell::PowerShell => "PowerShell",
+            Shell::Nu => "Nushell",
+            Shell::Xonsh => "Xonsh",
         }
     }
 
@@ -42,6 +50,8 @@ impl Shell {
             Shell::Zsh => "zsh",
             Shell::Fish => "fish",
             Shell::PowerShell => "pwsh",
+            Shell::Nu => "nu",
+            Shell::Xonsh => "xonsh",
         }
     }
 
@@ -51,6 +61,8 @@ impl Shell {
             Shell::Zsh => "zsh",
             Shell::Fish => "fish",
             Shell::PowerShell => "ps1",
+            Shell::Nu => "nu",
+            Shell::Xonsh => "xsh",
         }
     }
 
@@ -62,8 +74,17 @@ impl Shell {
             .unwrap_or(false)
     }
 
+    /// Shells with a cold-start benchmark. Elvish is left out: its integration
+    /// uses the `edit:` module, which only exists in interactive sessions.
     fn all() -> &'static [Shell] {
-        &[Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell]
+        &[
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::PowerShell,
+            Shell::Nu,
+            Shell::Xonsh,
+        ]
     }
 }
 
@@ -79,32 +100,41 @@ struct BenchmarkStats {
 }
 
 impl BenchmarkStats {
-    fn from_times(times: &[f64]) -> Self {
+    /// Summarize the sample times, or `None` when there are none.
+    ///
+    /// A single sample has a standard deviation of 0.
+    fn from_times(times: &[f64]) -> Option<Self> {
         let mut sorted = times.to_vec();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.sort_by(f64::total_cmp);
 
         let n = sorted.len();
-        let sum: f64 = sorted.iter().sum();
-        let mean = sum / n as f64;
+        let (&min, &max) = (sorted.first()?, sorted.last()?);
+        let mean = sorted.iter().sum::<f64>() / n as f64;
 
-        let variance: f64 =
-            sorted.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
-        let stdev = variance.sqrt();
+        let stdev = if n > 1 {
+            let variance = sorted.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+
+        // Nearest-rank quartiles, clamped so tiny samples stay in bounds
+        let quartile = |k: usize| sorted[(k * n / 4).min(n - 1)];
 
-        BenchmarkStats {
+        Some(BenchmarkStats {
             n,
-            min: sorted[0],
-            q1: sorted[n / 4],
-            median: sorted[n / 2],
-            q3: sorted[3 * n / 4],
-            max: sorted[n - 1],
+            min,
+            q1: quartile(1),
+            median: quartile(2),
+            q3: quartile(3),
+            max,
             mean,
             stdev,
-        }
+        })
     }
 }
 
-pub fn run(samples: usize, keep_results: bool) -> Result<()> {
+fn install_interrupt_handler() {
     ctrlc::set_handler(|| {
         let count = INTERRUPT_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
         if count == 1 {
@@ -116,20 +146,20 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
         }
     })
     .ok();
+}
 
-    println!("Shell Integration Benchmark");
-    println!("===========================");
-    println!("Samples per scenario: {}", samples);
-    println!();
-
-    let available_shells: Vec<Shell> = Shell::all()
+fn available_shells(shells: &[Shell]) -> Result<Vec<Shell>> {
+    let available_shells: Vec<Shell> = shells
         .iter()
         .copied()
         .filter(|s| s.is_available())
         .collect();
 
     if available_shells.is_empty() {
-        bail!("No supported shells found (bash, zsh, fish, pwsh)");
+        bail!(
+            "No supported shells found ({})",
+            shells.iter().map(|s| s.command()).collect::<Vec<_>>().join(", ")
+        );
     }
 
     println!(
@@ -141,7 +171,10 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
             .join(", ")
     );
     println!();
+    Ok(available_shells)
+}
 
+fn build_release_binary() -> Result<PathBuf> {
     println!("Building release binary...");
     let status = Command::new("cargo")
         .args(["build", "--release"])
@@ -154,6 +187,19 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
 
     let binary_path = find_release_binary()?;
     println!("Using binary: {}", binary_path.display());
+    Ok(binary_path)
+}
+
+pub fn run(samples: usize, keep_results: bool, json_path: Option<&Path>) -> Result<()> {
+    install_interrupt_handler();
+
+    println!("Shell Integration Benchmark");
+    println!("===========================");
+    println!("Samples per scenario: {}", samples);
+    println!();
+
+    let available_shells = available_shells(Shell::all())?;
+    let binary_path = build_release_binary()?;
 
     let temp_dir = env::temp_dir().join("shai-bench");
     fs::create_dir_all(&temp_dir)?;
@@ -217,13 +263,11 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
 
             let times = run_cold_benchmark(*shell, &file_path, samples)?;
 
-            if times.is_empty() {
+            let Some(stats) = BenchmarkStats::from_times(&times) else {
                 interrupted = true;
                 println!("skipped");
                 break 'outer;
-            }
-
-            let stats = BenchmarkStats::from_times(&times);
+            };
             println!("{:.2}ms mean ({:.2}ms median)", stats.mean, stats.median);
             raw_data.push((*shell, scenario.to_string(), times));
             all_results.push((*shell, scenario.to_string(), stats));
@@ -239,6 +283,11 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
         save_raw_data_csv(&csv_path, &raw_data)?;
         println!("\nRaw data saved to: {}", csv_path.display());
 
+        if let Some(path) = json_path {
+            save_json_report(path, samples, interrupted, &available_shells, &all_results)?;
+            println!("JSON report saved to: {}", path.display());
+        }
+
         println!();
         print_results(&all_results);
     } else {
@@ -255,6 +304,191 @@ pub fn run(samples: usize, keep_results: bool) -> Result<()> {
     Ok(())
 }
 
+/// Benchmark the Ctrl+G keybinding's `_shai_transform` function end to end.
+///
+/// `shell-ai` is replaced on `PATH` by a stub that answers instantly, so the
+/// measured time is the shell's own overhead (temp file, background job,
+/// spinner loop) rather than network latency. Each shell sources the `full`
+/// integration once and then invokes the function repeatedly (warm start).
+pub fn run_keybinding(samples: usize, keep_results: bool, json_path: Option<&Path>) -> Result<()> {
+    install_interrupt_handler();
+
+    println!("Keybinding Latency Benchmark");
+    println!("============================");
+    println!("Samples per shell: {}", samples);
+    println!();
+
+    // PSReadLine key handlers can't be invoked outside an interactive session
+    let available_shells = available_shells(&[Shell::Bash, Shell::Zsh, Shell::Fish])?;
+    let binary_path = build_release_binary()?;
+
+    let temp_dir = env::temp_dir().join("shai-bench-keybinding");
+    fs::create_dir_all(&temp_dir)?;
+    println!("Working directory: {}", temp_dir.display());
+    println!();
+
+    let stub_dir = temp_dir.join("stub");
+    fs::create_dir_all(&stub_dir)?;
+    write_stub_binary(&stub_dir)?;
+
+    let mut all_results: Vec<(Shell, String, BenchmarkStats)> = Vec::new();
+    let mut raw_data: Vec<(Shell, String, Vec<f64>)> = Vec::new();
+
+    for shell in &available_shells {
+        if INTERRUPT_COUNT.load(Ordering::SeqCst) > 0 {
+            println!("\n--- Benchmark interrupted, showing partial results ---");
+            break;
+        }
+
+        let output = Command::new(&binary_path)
+            .args(["integration", "generate", shell.name(), "--preset", "full", "--stdout"])
+            .output()
+            .context("Failed to generate integration")?;
+        let file_path = temp_dir.join(format!("{}_full.{}", shell.name(), shell.extension()));
+        fs::write(&file_path, &output.stdout)?;
+
+        print!("  {}: ", shell.name());
+        std::io::stdout().flush()?;
+
+        let times = run_keybinding_benchmark(*shell, &file_path, &stub_dir, samples)?;
+        let Some(stats) = BenchmarkStats::from_times(&times) else {
+            println!("skipped");
+            continue;
+        };
+        println!("{:.2}ms mean ({:.2}ms median)", stats.mean, stats.median);
+        raw_data.push((*shell, "keybinding".to_string(), times));
+        all_results.push((*shell, "keybinding".to_string(), stats));
+    }
+
+    if !all_results.is_empty() {
+        let csv_path = temp_dir.join("results.csv");
+        save_raw_data_csv(&csv_path, &raw_data)?;
+        println!("\nRaw data saved to: {}", csv_path.display());
+
+        if let Some(path) = json_path {
+            let interrupted = INTERRUPT_COUNT.load(Ordering::SeqCst) > 0;
+            save_json_report(path, samples, interrupted, &available_shells, &all_results)?;
+            println!("JSON report saved to: {}", path.display());
+        }
+
+        println!();
+        print_keybinding_results(&all_results);
+    } else {
+        println!("\nNo benchmark data collected.");
+    }
+
+    if keep_results {
+        println!("\nResults preserved in: {}", temp_dir.display());
+    } else {
+        fs::remove_dir_all(&temp_dir).ok();
+        println!("\nTemporary files cleaned up. Use --keep to preserve.");
+    }
+
+    Ok(())
+}
+
+/// Write a `shell-ai` stub that prints a fixed suggestion and exits.
+fn write_stub_binary(dir: &Path) -> Result<()> {
+    let path = dir.join("shell-ai");
+    fs::write(&path, "#!/bin/sh\necho 'ls -la'\n")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+fn run_keybinding_benchmark(
+    shell: Shell,
+    file_path: &Path,
+    stub_dir: &Path,
+    samples: usize,
+) -> Result<Vec<f64>> {
+    let file_path_str = file_path.to_string_lossy();
+    let path = format!(
+        "{}:{}",
+        stub_dir.display(),
+        env::var("PATH").unwrap_or_default()
+    );
+
+    // Line-editor builtins (zle, commandline) are stubbed out, since there is
+    // no interactive line editor; the function's own work is still measured.
+    let script = match shell {
+        Shell::Bash => format!(
+            r#"source "{0}" 2>/dev/null
+for i in $(seq {1}); do
+    READLINE_LINE="list all files"
+    start=$(date +%s%N); _shai_transform >/dev/null 2>&1; end=$(date +%s%N)
+    echo $((end - start))
+done"#,
+            file_path_str, samples
+        ),
+        Shell::Zsh => format!(
+            r#"source "{0}" 2>/dev/null
+zle() {{ : }}
+for i in $(seq {1}); do
+    BUFFER="list all files"
+    start=$(date +%s%N); _shai_transform >/dev/null 2>&1; end=$(date +%s%N)
+    echo $((end - start))
+done"#,
+            file_path_str, samples
+        ),
+        Shell::Fish => format!(
+            r#"source "{0}" 2>/dev/null
+function commandline
+    test (count $argv) -eq 0; and echo "list all files"
+end
+for i in (seq {1})
+    set start (date +%s%N); _shai_transform >/dev/null 2>&1; set end (date +%s%N)
+    echo (math $end - $start)
+end"#,
+            file_path_str, samples
+        ),
+        Shell::PowerShell | Shell::Nu | Shell::Xonsh => bail!(
+            "The keybinding benchmark does not support {}",
+            shell.display_name()
+        ),
+    };
+
+    let output = Command::new(shell.command())
+        .args(["-c", &script])
+        .env("PATH", path)
+        .output()?;
+
+    // Keep whatever completed if the run was interrupted partway
+    let mut times = Vec::with_capacity(samples);
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match parse_time_output(line.as_bytes()) {
+            Ok(time_ns) => times.push(time_ns / 1_000_000.0),
+            Err(_) if INTERRUPT_COUNT.load(Ordering::SeqCst) > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(times)
+}
+
+fn print_keybinding_results(results: &[(Shell, String, BenchmarkStats)]) {
+    println!("### Keybinding Latency (Stubbed shell-ai)\n");
+    println!("| Shell | N | Min | Q1 | Median | Q3 | Max | Mean | Std Dev |");
+    println!("|-------|--:|----:|---:|-------:|---:|----:|-----:|--------:|");
+
+    for (shell, _, stats) in results {
+        println!(
+            "| {} | {} | {:.2}ms | {:.2}ms | {:.2}ms | {:.2}ms | {:.2}ms | {:.2}ms | {:.2}ms |",
+            shell.display_name(),
+            stats.n,
+            stats.min,
+            stats.q1,
+            stats.median,
+            stats.q3,
+            stats.max,
+            stats.mean,
+            stats.stdev
+        );
+    }
+}
+
 fn find_release_binary() -> Result<PathBuf> {
     let target_dir = Path::new("target");
     if !target_dir.exists() {
@@ -277,7 +511,7 @@ fn find_release_binary() -> Result<PathBuf> {
         );
     }
 
-    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.1));
     Ok(candidates[0].0.canonicalize()?)
 }
 
@@ -353,6 +587,28 @@ fn run_cold_benchmark(shell: Shell, file_path: &Path, samples: usize) -> Result<
                     ),
                 ])
                 .output()?,
+            // Nushell resolves `source` at parse time, so the sourcing happens in a
+            // child `nu`; its startup cost is included in the blank baseline too
+            Shell::Nu => Command::new("nu")
+                .args([
+                    "--no-config-file",
+                    "-c",
+                    &format!(
+                        r#"let start = (date now | into int); ^nu --no-config-file -c 'source "{0}"'; print ((date now | into int) - $start)"#,
+                        file_path_str
+                    ),
+                ])
+                .output()?,
+            Shell::Xonsh => Command::new("xonsh")
+                .args([
+                    "--no-rc",
+                    "-c",
+                    &format!(
+                        r#"import os, time; start = time.perf_counter_ns(); execx("source {0}") if os.path.isfile("{0}") else None; print(time.perf_counter_ns() - start)"#,
+                        file_path_str
+                    ),
+                ])
+                .output()?,
         };
 
         if let Ok(time_ns) = parse_time_output(&output.stdout) {
@@ -399,6 +655,86 @@ fn save_raw_data_csv(path: &Path, data: &[(Shell, String, Vec<f64>)]) -> Result<
     Ok(())
 }
 
+/// Write the summary statistics as JSON, with the environment they were measured in.
+///
+/// Each result's `overhead_ms` is its mean above the shell's `blank` baseline,
+/// or `null` when there is no baseline (as in `bench-keybinding`).
+fn save_json_report(
+    path: &Path,
+    samples: usize,
+    interrupted: bool,
+    shells: &[Shell],
+    results: &[(Shell, String, BenchmarkStats)],
+) -> Result<()> {
+    let shell_versions: serde_json::Map<String, serde_json::Value> = shells
+        .iter()
+        .map(|shell| (shell.name().to_string(), json!(shell_version(*shell))))
+        .collect();
+
+    let baseline = |shell: Shell| {
+        results
+            .iter()
+            .find(|(s, scenario, _)| s.name() == shell.name() && scenario == "blank")
+            .map(|(_, _, stats)| stats.mean)
+    };
+
+    let results: Vec<serde_json::Value> = results
+        .iter()
+        .map(|(shell, scenario, stats)| {
+            json!({
+                "shell": shell.name(),
+                "scenario": scenario,
+                "n": stats.n,
+                "min_ms": stats.min,
+                "q1_ms": stats.q1,
+                "median_ms": stats.median,
+                "q3_ms": stats.q3,
+                "max_ms": stats.max,
+                "mean_ms": stats.mean,
+                "stdev_ms": stats.stdev,
+                "overhead_ms": baseline(*shell).filter(|_| scenario != "blank").map(|b| stats.mean - b),
+            })
+        })
+        .collect();
+
+    let report = json!({
+        "environment": {
+            "os": env::consts::OS,
+            "arch": env::consts::ARCH,
+            "commit": git_commit(),
+            "samples_per_scenario": samples,
+            "shells": shell_versions,
+        },
+        "interrupted": interrupted,
+        "results": results,
+    });
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// First line of `<shell> --version`, or `None` if it can't be determined.
+fn shell_version(shell: Shell) -> Option<String> {
+    let output = Command::new(shell.command()).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|line| line.trim().to_string()).filter(|line| !line.is_empty())
+}
+
+/// The checked-out commit being benchmarked, with `-dirty` for uncommitted changes.
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--abbrev=40"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 fn print_results(results: &[(Shell, String, BenchmarkStats)]) {
     println!("### Baseline: Sourcing an Empty File\n");
     println!("| Shell | N | Min | Q1 | Median | Q3 | Max | Mean | Std Dev |");
@@ -477,4 +813,31 @@ fn print_results(results: &[(Shell, String, BenchmarkStats)]) {
         }
         println!();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_samples_have_no_stats() {
+        assert!(BenchmarkStats::from_times(&[]).is_none());
+    }
+
+    #[test]
+    fn one_sample_has_no_spread() {
+        let stats = BenchmarkStats::from_times(&[4.0]).unwrap();
+        assert_eq!(stats.n, 1);
+        assert_eq!((stats.min, stats.q1, stats.median, stats.q3, stats.max), (4.0, 4.0, 4.0, 4.0, 4.0));
+        assert_eq!((stats.mean, stats.stdev), (4.0, 0.0));
+    }
+
+    #[test]
+    fn two_samples_stay_in_bounds() {
+        let stats = BenchmarkStats::from_times(&[3.0, 1.0]).unwrap();
+        assert_eq!(stats.n, 2);
+        assert_eq!((stats.min, stats.q1, stats.median, stats.q3, stats.max), (1.0, 1.0, 3.0, 3.0, 3.0));
+        assert_eq!(stats.mean, 2.0);
+        assert!((stats.stdev - std::f64::consts::SQRT_2).abs() < 1e-12);
+    }
+}
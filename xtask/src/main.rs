@@ -3,6 +3,7 @@ mod package;
 
 use anyhow::{bail, Result};
 use std::env;
+use std::path::PathBuf;
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
@@ -11,7 +12,12 @@ fn main() -> Result<()> {
         eprintln!("Usage: xtask <command> [args...]");
         eprintln!("Commands:");
         eprintln!("  package <target> [target...]               - Package built binaries for the given targets");
-        eprintln!("  bench-integration [--keep] [sample_count]  - Benchmark shell integration overhead");
+        eprintln!("  package-deb <target>                       - Build a .deb for a Linux target (requires dpkg-deb)");
+        eprintln!("  package-rpm <target>                       - Build an .rpm for a Linux target (requires rpmbuild)");
+        eprintln!("  bench-integration [--keep] [--json <file>] [sample_count]");
+        eprintln!("                                             - Benchmark shell integration overhead");
+        eprintln!("  bench-keybinding [--keep] [--json <file>] [sample_count]");
+        eprintln!("                                             - Benchmark Ctrl+G keybinding latency with a stubbed shell-ai");
         std::process::exit(1);
     }
 
@@ -23,20 +29,49 @@ fn main() -> Result<()> {
             let targets: Vec<&str> = args[2..].iter().map(|s| s.as_str()).collect();
             package::run(&targets)
         }
+        "package-deb" => {
+            if args.len() != 3 {
+                bail!("Usage: xtask package-deb <target>");
+            }
+            package::run_deb(&args[2])
+        }
+        "package-rpm" => {
+            if args.len() != 3 {
+                bail!("Usage: xtask package-rpm <target>");
+            }
+            package::run_rpm(&args[2])
+        }
         "bench-integration" => {
-            let mut samples = 100;
-            let mut keep_results = false;
+            let (samples, keep_results, json_path) = parse_bench_args(&args[2..])?;
+            bench::run(samples, keep_results, json_path.as_deref())
+        }
+        "bench-keybinding" => {
+            let (samples, keep_results, json_path) = parse_bench_args(&args[2..])?;
+            bench::run_keybinding(samples, keep_results, json_path.as_deref())
+        }
+        cmd => bail!("Unknown command: {}", cmd),
+    }
+}
 
-            for arg in &args[2..] {
-                if arg == "--keep" || arg == "-k" {
-                    keep_results = true;
-                } else if let Ok(n) = arg.parse::<usize>() {
-                    samples = n;
-                }
-            }
+/// Parse `[--keep] [--json <file>] [sample_count]` for the bench commands.
+fn parse_bench_args(args: &[String]) -> Result<(usize, bool, Option<PathBuf>)> {
+    let mut samples = 100;
+    let mut keep_results = false;
+    let mut json_path = None;
 
-            bench::run(samples, keep_results)
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--keep" || arg == "-k" {
+            keep_results = true;
+        } else if arg == "--json" {
+            let Some(path) = args.next() else {
+                bail!("--json requires a file path");
+            };
+            json_path = Some(PathBuf::from(path));
+        } else if let Ok(n) = arg.parse::<usize>() {
+            samples = n;
         }
-        cmd => bail!("Unknown command: {}", cmd),
     }
-}
\ No newline at end of file
+
+    Ok((samples, keep_results, json_path))
+}
@@ -1,42 +1,111 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use sha2::{Digest, Sha256};
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 
 const BINARY_NAME: &str = "shell-ai";
+const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+const MAINTAINER: &str = "Shell-AI contributors";
+const HOMEPAGE: &str = "https://github.com/Deltik/shell-ai";
+const LICENSE: &str = "MIT";
+const SUMMARY: &str = "AI-assisted shell command suggestions";
+const DESCRIPTION: &str = "Create shell commands from natural language, or have existing commands explained, using an OpenAI-compatible API.";
+
+/// Linux package formats built from a release binary.
+#[derive(Clone, Copy)]
+enum PackageFormat {
+    Deb,
+    Rpm,
+}
+
+impl PackageFormat {
+    /// Package architecture name for a Rust target triple.
+    fn arch(self, target: &str) -> Option<&'static str> {
+        let cpu = target.split('-').next()?;
+        match (self, cpu) {
+            (PackageFormat::Deb, "x86_64") => Some("amd64"),
+            (PackageFormat::Deb, "aarch64") => Some("arm64"),
+            (PackageFormat::Deb, "armv7") => Some("armhf"),
+            (PackageFormat::Deb, "i686") => Some("i386"),
+            (PackageFormat::Rpm, "x86_64") => Some("x86_64"),
+            (PackageFormat::Rpm, "aarch64") => Some("aarch64"),
+            (PackageFormat::Rpm, "armv7") => Some("armv7hl"),
+            (PackageFormat::Rpm, "i686") => Some("i686"),
+            _ => None,
+        }
+    }
+
+    /// Directory zsh searches for vendor completions on this distro family.
+    fn zsh_completion_dir(self) -> &'static str {
+        match self {
+            PackageFormat::Deb => "usr/share/zsh/vendor-completions",
+            PackageFormat::Rpm => "usr/share/zsh/site-functions",
+        }
+    }
+}
 
 pub fn run(targets: &[&str]) -> Result<()> {
     let artifacts_dir = Path::new("artifacts");
     fs::create_dir_all(artifacts_dir)?;
 
+    let mut produced = Vec::new();
     for target in targets {
-        package_target(target, artifacts_dir)?;
+        produced.extend(package_target(target, artifacts_dir)?);
     }
 
     println!("\nArtifacts:");
-    for entry in fs::read_dir(artifacts_dir)? {
-        let entry = entry?;
-        let metadata = entry.metadata()?;
-        println!(
-            "  {} ({} bytes)",
-            entry.file_name().to_string_lossy(),
-            metadata.len()
-        );
+    for name in &produced {
+        let metadata = fs::metadata(artifacts_dir.join(name))?;
+        println!("  {} ({} bytes)", name, metadata.len());
     }
 
+    let checksums = write_checksums(artifacts_dir, &produced)?;
+    println!("\n{}:", CHECKSUMS_FILE);
+    print!("{}", checksums);
+
     Ok(())
 }
 
-fn package_target(target: &str, artifacts_dir: &Path) -> Result<()> {
+/// Write `SHA256SUMS` for the named artifacts in `sha256sum -c` format and return its contents.
+///
+/// Only files produced by this run are listed, so stale artifacts from earlier
+/// runs (and `.deb`/`.rpm` packages built separately) aren't vouched for.
+fn write_checksums(artifacts_dir: &Path, names: &[String]) -> Result<String> {
+    let mut names = names.to_vec();
+    names.sort();
+
+    let mut checksums = String::new();
+    for name in names {
+        let mut hasher = Sha256::new();
+        let mut file = File::open(artifacts_dir.join(&name))?;
+        std::io::copy(&mut file, &mut hasher)?;
+        let hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        checksums.push_str(&format!("{}  {}\n", hash, name));
+    }
+
+    fs::write(artifacts_dir.join(CHECKSUMS_FILE), &checksums)
+        .context("Failed to write checksums")?;
+    Ok(checksums)
+}
+
+/// Package one target, returning the names of the files written to `artifacts_dir`.
+fn package_target(target: &str, artifacts_dir: &Path) -> Result<Vec<String>> {
     let release_dir = Path::new("target").join(target).join("release");
 
     if !release_dir.exists() {
         println!("Skipping {} (build not found)", target);
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     let is_windows = target.contains("windows");
@@ -49,7 +118,7 @@ fn package_target(target: &str, artifacts_dir: &Path) -> Result<()> {
     let binary_path = release_dir.join(&binary_name);
     if !binary_path.exists() {
         println!("Skipping {} (binary not found)", target);
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     println!("Packaging {}...", target);
@@ -62,16 +131,16 @@ fn package_target(target: &str, artifacts_dir: &Path) -> Result<()> {
     fs::copy(&binary_path, artifacts_dir.join(&standalone_name))
         .context("Failed to copy standalone binary")?;
 
-    if is_windows {
-        create_zip(target, &binary_path, artifacts_dir)?;
+    let archive_name = if is_windows {
+        create_zip(target, &binary_path, artifacts_dir)?
     } else {
-        create_tarball(target, &binary_path, artifacts_dir)?;
-    }
+        create_tarball(target, &binary_path, artifacts_dir)?
+    };
 
-    Ok(())
+    Ok(vec![standalone_name, archive_name])
 }
 
-fn create_zip(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Result<()> {
+fn create_zip(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Result<String> {
     let archive_name = format!("{}-{}.zip", BINARY_NAME, target);
     let archive_path = artifacts_dir.join(&archive_name);
     let file = File::create(&archive_path).context("Failed to create zip file")?;
@@ -90,10 +159,10 @@ fn create_zip(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Result<
     zip.write_all(&binary_data)?;
 
     zip.finish()?;
-    Ok(())
+    Ok(archive_name)
 }
 
-fn create_tarball(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Result<()> {
+fn create_tarball(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Result<String> {
     let archive_name = format!("{}-{}.tar.gz", BINARY_NAME, target);
     let archive_path = artifacts_dir.join(&archive_name);
     let file = File::create(&archive_path).context("Failed to create tarball")?;
@@ -117,5 +186,270 @@ fn create_tarball(target: &str, binary_path: &Path, artifacts_dir: &Path) -> Res
     tar.append_link(&mut header, "shai", BINARY_NAME)?;
 
     tar.finish()?;
+    Ok(archive_name)
+}
+
+/// Build a `.deb` for a Linux target and print its path on stdout.
+pub fn run_deb(target: &str) -> Result<()> {
+    let artifacts_dir = Path::new("artifacts");
+    fs::create_dir_all(artifacts_dir)?;
+
+    let format = PackageFormat::Deb;
+    let arch = package_arch(format, target)?;
+    let binary_path = linux_binary(target)?;
+    let version = package_version(&binary_path)?;
+
+    eprintln!("Packaging {} as .deb...", target);
+
+    let work_dir = Path::new("target").join(target).join("deb");
+    let root = stage_package_root(format, &binary_path, &work_dir)?;
+
+    let debian_dir = root.join("DEBIAN");
+    fs::create_dir_all(&debian_dir)?;
+    let control = format!(
+        "Package: {name}\n\
+         Version: {version}\n\
+         Architecture: {arch}\n\
+         Maintainer: {MAINTAINER}\n\
+         Installed-Size: {size}\n\
+         Section: utils\n\
+         Priority: optional\n\
+         Homepage: {HOMEPAGE}\n\
+         Description: {SUMMARY}\n {DESCRIPTION}\n",
+        name = BINARY_NAME,
+        size = fs::metadata(&binary_path)?.len().div_ceil(1024),
+    );
+    fs::write(debian_dir.join("control"), control)?;
+
+    let deb_path = artifacts_dir.join(format!("{}_{}_{}.deb", BINARY_NAME, version, arch));
+    let status = Command::new("dpkg-deb")
+        .args(["--root-owner-group", "--build"])
+        .arg(&root)
+        .arg(&deb_path)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .context("Failed to run dpkg-deb (is it installed?)")?;
+    if !status.success() {
+        bail!("dpkg-deb failed");
+    }
+
+    println!("{}", deb_path.display());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Build an `.rpm` for a Linux target and print its path on stdout.
+pub fn run_rpm(target: &str) -> Result<()> {
+    let artifacts_dir = Path::new("artifacts");
+    fs::create_dir_all(artifacts_dir)?;
+
+    let format = PackageFormat::Rpm;
+    let arch = package_arch(format, target)?;
+    let binary_path = linux_binary(target)?;
+    let version = package_version(&binary_path)?;
+
+    eprintln!("Packaging {} as .rpm...", target);
+
+    let work_dir = fs::canonicalize("target")?.join(target).join("rpm");
+    let root = stage_package_root(format, &binary_path, &work_dir)?;
+
+    let mut files = vec![
+        format!("/usr/bin/{}", BINARY_NAME),
+        "/usr/bin/shai".to_string(),
+    ];
+    files.extend(
+        completion_files(format)
+            .iter()
+            .map(|(_, dir, name)| format!("/{}/{}", dir, name)),
+    );
+    let spec = format!(
+        "%global debug_package %{{nil}}\n\
+         %global __os_install_post %{{nil}}\n\
+         \n\
+         Name: {name}\n\
+         Version: {version}\n\
+         Release: 1\n\
+         Summary: {SUMMARY}\n\
+         License: {LICENSE}\n\
+         URL: {HOMEPAGE}\n\
+         Packager: {MAINTAINER}\n\
+         \n\
+         %description\n\
+         {DESCRIPTION}\n\
+         \n\
+         %install\n\
+         cp -a {root}/. %{{buildroot}}/\n\
+         \n\
+         %files\n\
+         {files}\n",
+        name = BINARY_NAME,
+        root = root.display(),
+        files = files.join("\n"),
+    );
+    let spec_path = work_dir.join(format!("{}.spec", BINARY_NAME));
+    fs::write(&spec_path, spec)?;
+
+    let status = Command::new("rpmbuild")
+        .args(["-bb", "--target", arch, "--define"])
+        .arg(format!("_topdir {}", work_dir.join("rpmbuild").display()))
+        .arg(&spec_path)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .context("Failed to run rpmbuild (is it installed?)")?;
+    if !status.success() {
+        bail!("rpmbuild failed");
+    }
+
+    let rpm_name = format!("{}-{}-1.{}.rpm", BINARY_NAME, version, arch);
+    let built = work_dir.join("rpmbuild/RPMS").join(arch).join(&rpm_name);
+    let rpm_path = artifacts_dir.join(&rpm_name);
+    fs::copy(&built, &rpm_path)
+        .with_context(|| format!("rpmbuild did not produce {}", built.display()))?;
+
+    println!("{}", rpm_path.display());
+    Ok(())
+}
+
+/// Completion files installed by the Linux packages, as `(shell, directory, file name)`.
+fn completion_files(format: PackageFormat) -> [(&'static str, &'static str, &'static str); 3] {
+    [
+        ("bash", "usr/share/bash-completion/completions", BINARY_NAME),
+        ("zsh", format.zsh_completion_dir(), "_shell-ai"),
+        (
+            "fish",
+            "usr/share/fish/vendor_completions.d",
+            "shell-ai.fish",
+        ),
+    ]
+}
+
+fn package_arch(format: PackageFormat, target: &str) -> Result<&'static str> {
+    if !target.contains("linux") {
+        bail!("{} is not a Linux target", target);
+    }
+    format
+        .arch(target)
+        .with_context(|| format!("Unsupported architecture for {}", target))
+}
+
+fn linux_binary(target: &str) -> Result<PathBuf> {
+    let binary_path = Path::new("target")
+        .join(target)
+        .join("release")
+        .join(BINARY_NAME);
+    if !binary_path.exists() {
+        bail!(
+            "{} not found; build it first with `cargo build --release --target {}`",
+            binary_path.display(),
+            target
+        );
+    }
+    Ok(binary_path)
+}
+
+/// A binary that can run on this host, for generating completions and reading
+/// the version. Falls back to a native release build when cross-packaging.
+fn host_binary(binary_path: &Path) -> Result<PathBuf> {
+    let runs = Command::new(binary_path)
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if runs {
+        return Ok(binary_path.to_path_buf());
+    }
+
+    eprintln!("Building native release binary for completions...");
+    let status = Command::new("cargo")
+        .args(["build", "--release", "--package", BINARY_NAME])
+        .stdout(std::process::Stdio::null())
+        .status()
+        .context("Failed to run cargo build")?;
+    if !status.success() {
+        bail!("cargo build failed");
+    }
+    Ok(Path::new("target").join("release").join(BINARY_NAME))
+}
+
+/// The `GIT_VERSION` baked into the binary, made valid for deb and rpm.
+///
+/// `git describe` output like `0.5.2-3-gabc1234` becomes `0.5.2+3.gabc1234`,
+/// since a hyphen separates the release in both formats.
+fn package_version(binary_path: &Path) -> Result<String> {
+    let output = Command::new(host_binary(binary_path)?)
+        .arg("--version")
+        .output()
+        .context("Failed to read binary version")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .split_whitespace()
+        .nth(1)
+        .context("Unexpected --version output")?;
+
+    Ok(match version.split_once('-') {
+        Some((base, rest)) => format!("{}+{}", base, rest.replace('-', ".")),
+        None => version.to_string(),
+    })
+}
+
+/// Lay out the binary, `shai` symlink, and shell completions under `work_dir/root`.
+fn stage_package_root(
+    format: PackageFormat,
+    binary_path: &Path,
+    work_dir: &Path,
+) -> Result<PathBuf> {
+    let root = work_dir.join("root");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+
+    let bin_dir = root.join("usr/bin");
+    fs::create_dir_all(&bin_dir)?;
+    fs::copy(binary_path, bin_dir.join(BINARY_NAME)).context("Failed to copy binary")?;
+    set_mode(&bin_dir.join(BINARY_NAME), 0o755)?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(BINARY_NAME, bin_dir.join("shai"))?;
+
+    let generator = host_binary(binary_path)?;
+    for (shell, dir, name) in completion_files(format) {
+        let output = Command::new(&generator)
+            .args([
+                "integration",
+                "generate",
+                shell,
+                "--preset",
+                "minimal",
+                "--stdout",
+            ])
+            .output()
+            .with_context(|| format!("Failed to generate {} completions", shell))?;
+        if !output.status.success() {
+            bail!("Failed to generate {} completions", shell);
+        }
+
+        // Drop the integration header so e.g. `#compdef` stays on the first line
+        let script = String::from_utf8_lossy(&output.stdout);
+        let completions = script
+            .split_once("# === Completions ===\n")
+            .map(|(_, body)| body)
+            .unwrap_or(&script);
+
+        let completion_dir = root.join(dir);
+        fs::create_dir_all(&completion_dir)?;
+        fs::write(completion_dir.join(name), completions)?;
+        set_mode(&completion_dir.join(name), 0o644)?;
+    }
+
+    Ok(root)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}